@@ -0,0 +1,65 @@
+//! Counts the allocations of decoding a stream of repeated strings, like the game's debug text,
+//! with and without the string pool.
+//!
+//! `cargo run --release --example intern_allocations`
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use textrender::intern;
+
+/// Frames in the stream.
+const FRAMES: usize = 10_000;
+/// Distinct strings drawn every frame.
+const STRINGS_PER_FRAME: usize = 50;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `decode` on every string of the stream, returning the allocations and seconds it took.
+fn measure<T>(stream: &[Vec<u16>], mut decode: impl FnMut(&[u16]) -> T) -> (usize, f64) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for _ in 0..FRAMES {
+        for units in stream {
+            std::hint::black_box(decode(units));
+        }
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    (ALLOCATIONS.load(Ordering::Relaxed) - before, elapsed)
+}
+
+fn main() {
+    let stream = (0..STRINGS_PER_FRAME)
+        .map(|i| {
+            format!("HP: {} / 1000  Stamina: {}", i * 7, i * 3)
+                .encode_utf16()
+                .collect::<Vec<u16>>()
+        })
+        .collect::<Vec<_>>();
+
+    let (plain, plain_time) = measure(&stream, String::from_utf16_lossy);
+    let (pooled, pooled_time) = measure(&stream, intern::intern_utf16);
+    let total = FRAMES * STRINGS_PER_FRAME;
+    println!("{total} strings, {STRINGS_PER_FRAME} distinct");
+    println!("String::from_utf16_lossy: {plain} allocations, {plain_time:.3}s");
+    println!("intern_utf16: {pooled} allocations, {pooled_time:.3}s");
+}
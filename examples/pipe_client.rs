@@ -0,0 +1,21 @@
+//! Prints the commands the pipe server streams, one JSON object per line.
+//!
+//! Set `pipe.enabled = true` in `textrender.toml`, start the game and run
+//! `cargo run --example pipe_client [name]`, with the `pipe.name` from the config if it isn't the
+//! default.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+fn main() -> std::io::Result<()> {
+    let name = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "textrender".to_string());
+    let pipe = File::open(format!(r"\\.\pipe\{name}"))?;
+    for line in BufReader::new(pipe).lines() {
+        println!("{}", line?);
+    }
+    Ok(())
+}
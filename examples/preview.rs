@@ -0,0 +1,347 @@
+//! Renders a frame of a JSON Lines dump to a PNG, placed by the same code the overlay uses, so
+//! layout changes can be looked at without the game.
+//!
+//! `cargo run --example preview -- <textrender-dump.jsonl> [frame] [preview.png]`
+//!
+//! The frame is the last one in the dump unless given. Text is laid out with the default config
+//! on a 1920x1080 screen and drawn in imgui's own font with a software rasterizer, so this runs
+//! on any platform without a GPU. World text and shapes need the game's camera and are left out.
+
+use std::{
+    ffi::c_char,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use imgui::{
+    Context, DrawCmd, DrawData, FontConfig, FontSource, ImColor32, internal::RawCast, sys,
+};
+use serde::Deserialize;
+
+use textrender::command::DrawCommand;
+use textrender::placement::{OffscreenMode, TextAnchor};
+use textrender::processor::{
+    CommandProcessor, DEFAULT_BAKED_SIZES_PX, DEFAULT_STACK_TOLERANCE_PX, ScreenInfo, ShapeEntry,
+    ShapeKind, TextEntry, WindowMetrics, baked_font_index, stack_overlapping,
+};
+
+const SCREEN_SIZE: [f32; 2] = [1920.0, 1080.0];
+/// Color the frame is drawn on, the game's scenes are rarely brighter than this.
+const BACKGROUND: [u8; 4] = [40, 40, 40, 255];
+
+/// A line of the dump, the fields the preview doesn't use are ignored.
+#[derive(Deserialize)]
+struct Record {
+    frame: u64,
+    #[serde(flatten)]
+    command: DrawCommand,
+}
+
+fn read_dump(path: &PathBuf) -> io::Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        // Lines that aren't commands are skipped like the replay skips them
+        if let Ok(record) = serde_json::from_str(&line?) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// The text and shapes of `frame`, with the state commands of every frame up to it applied.
+fn process(
+    records: &[Record],
+    frame: u64,
+    screen: &ScreenInfo,
+) -> (Vec<TextEntry>, Vec<ShapeEntry>) {
+    let metrics = WindowMetrics {
+        window_size: SCREEN_SIZE,
+        screen_size: SCREEN_SIZE,
+    };
+    let mut processor = CommandProcessor::new(metrics);
+    // The default `text.aspect_mode` is clamp, with `aspect_min` 0.8 and no maximum
+    processor.set_window(metrics, Some((0.8, f32::INFINITY)));
+    let (earlier, drawn): (Vec<_>, Vec<_>) = records
+        .iter()
+        .filter(|record| record.frame <= frame)
+        .partition(|record| record.frame < frame);
+    let commands = |records: Vec<&Record>| {
+        records
+            .into_iter()
+            .map(|record| record.command.clone())
+            .collect::<Vec<_>>()
+    };
+    // Earlier frames only leave their state behind
+    processor.process(screen, &commands(earlier));
+    let (mut texts, shapes) = processor.process(screen, &commands(drawn));
+    texts.sort_by(TextEntry::draw_order);
+    (texts, shapes)
+}
+
+/// Size of text like the overlay measures it, see `text_size` in the library.
+fn text_size(
+    font: *mut sys::ImFont,
+    text: &str,
+    font_size: f32,
+    wrap_width: Option<f32>,
+) -> [f32; 2] {
+    let start = text.as_ptr() as *const c_char;
+    let mut size = sys::ImVec2::zero();
+    unsafe {
+        sys::ImFont_CalcTextSizeA(
+            &mut size,
+            font,
+            font_size,
+            f32::MAX,
+            wrap_width.unwrap_or(0.0),
+            start,
+            start.add(text.len()),
+            std::ptr::null_mut(),
+        );
+    }
+    [size.x.ceil(), size.y]
+}
+
+/// An RGBA image the draw data is rasterized into.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: BACKGROUND.repeat(width * height),
+        }
+    }
+
+    /// Draws the triangles of every draw list, textured with the font atlas.
+    fn draw(&mut self, draw_data: &DrawData, atlas: &Atlas) {
+        for list in draw_data.draw_lists() {
+            let vertices = list.vtx_buffer();
+            let indices = list.idx_buffer();
+            for command in list.commands() {
+                let DrawCmd::Elements { count, cmd_params } = command else {
+                    continue;
+                };
+                let triangles = &indices[cmd_params.idx_offset..cmd_params.idx_offset + count];
+                for triangle in triangles.chunks_exact(3) {
+                    let [a, b, c] = [0, 1, 2]
+                        .map(|corner| vertices[cmd_params.vtx_offset + triangle[corner] as usize]);
+                    self.triangle([a, b, c], cmd_params.clip_rect, atlas);
+                }
+            }
+        }
+    }
+
+    fn triangle(&mut self, [a, b, c]: [imgui::DrawVert; 3], clip: [f32; 4], atlas: &Atlas) {
+        let edge = |p: [f32; 2], q: [f32; 2], r: [f32; 2]| {
+            (q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0])
+        };
+        let area = edge(a.pos, b.pos, c.pos);
+        if area == 0.0 {
+            return;
+        }
+        let min_x = a.pos[0].min(b.pos[0]).min(c.pos[0]).max(clip[0]).max(0.0) as usize;
+        let min_y = a.pos[1].min(b.pos[1]).min(c.pos[1]).max(clip[1]).max(0.0) as usize;
+        let max_x =
+            (a.pos[0].max(b.pos[0]).max(c.pos[0]).min(clip[2]).ceil() as usize).min(self.width);
+        let max_y =
+            (a.pos[1].max(b.pos[1]).max(c.pos[1]).min(clip[3]).ceil() as usize).min(self.height);
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+                let weights = [
+                    edge(b.pos, c.pos, p),
+                    edge(c.pos, a.pos, p),
+                    edge(a.pos, b.pos, p),
+                ]
+                .map(|weight| weight / area);
+                if weights.iter().any(|weight| *weight < 0.0) {
+                    continue;
+                }
+                let mix = |values: [f32; 3]| {
+                    values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+                };
+                let uv = [
+                    mix([a.uv[0], b.uv[0], c.uv[0]]),
+                    mix([a.uv[1], b.uv[1], c.uv[1]]),
+                ];
+                let texel = atlas.sample(uv);
+                let color = [0, 1, 2, 3].map(|channel| {
+                    let vertex =
+                        mix([a.col[channel], b.col[channel], c.col[channel]].map(f32::from));
+                    vertex / 255.0 * texel[channel]
+                });
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: [f32; 4]) {
+        let offset = (y * self.width + x) * 4;
+        let pixel = &mut self.pixels[offset..offset + 3];
+        for (channel, value) in pixel.iter_mut().enumerate() {
+            let blended = color[channel] * 255.0 * color[3] + *value as f32 * (1.0 - color[3]);
+            *value = blended.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    fn write_png(&self, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            self.width as u32,
+            self.height as u32,
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+}
+
+/// The font atlas as colors from 0 to 1.
+struct Atlas {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl Atlas {
+    fn sample(&self, uv: [f32; 2]) -> [f32; 4] {
+        let x = ((uv[0] * self.width as f32) as usize).min(self.width - 1);
+        let y = ((uv[1] * self.height as f32) as usize).min(self.height - 1);
+        let offset = (y * self.width + x) * 4;
+        [0, 1, 2, 3].map(|channel| self.data[offset + channel] as f32 / 255.0)
+    }
+}
+
+fn color32(color: [f32; 4]) -> ImColor32 {
+    ImColor32::from_rgba_f32s(color[0], color[1], color[2], color[3])
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let Some(dump) = args.next().map(PathBuf::from) else {
+        eprintln!("usage: preview <textrender-dump.jsonl> [frame] [preview.png]");
+        std::process::exit(2);
+    };
+    let records = read_dump(&dump)?;
+    let Some(last_frame) = records.last().map(|record| record.frame) else {
+        return Err(format!("{} has no recorded commands", dump.display()).into());
+    };
+    let frame = match args.next() {
+        Some(frame) => frame.parse()?,
+        None => last_frame,
+    };
+    let output = args
+        .next()
+        .map_or_else(|| PathBuf::from("preview.png"), PathBuf::from);
+
+    // The defaults of `[font]` and `[text]`
+    let screen = ScreenInfo {
+        display_size: SCREEN_SIZE,
+        render_scale: [1.0, 1.0],
+        font_scale: 1.0,
+        default_font_size: 24.0,
+        default_color: [1.0, 1.0, 1.0, 1.0],
+        offscreen: OffscreenMode::Wrap,
+        tab_width: 4,
+        wrap_to_screen: true,
+    };
+    let (mut texts, shapes) = process(&records, frame, &screen);
+
+    let mut ctx = Context::create();
+    ctx.set_ini_filename(None);
+    ctx.io_mut().display_size = SCREEN_SIZE;
+    let font_ids = DEFAULT_BAKED_SIZES_PX.map(|size| {
+        let id = ctx.fonts().add_font(&[FontSource::DefaultFontData {
+            config: Some(FontConfig {
+                size_pixels: size,
+                ..FontConfig::default()
+            }),
+        }]);
+        (size, id)
+    });
+    let texture = ctx.fonts().build_rgba32_texture();
+    let atlas = Atlas {
+        width: texture.width as usize,
+        height: texture.height as usize,
+        data: texture.data.to_vec(),
+    };
+    let fonts = font_ids.map(|(size, id)| {
+        let font = ctx
+            .fonts()
+            .get_font(id)
+            .map_or(std::ptr::null_mut(), |font| unsafe {
+                font.raw() as *const sys::ImFont as *mut sys::ImFont
+            });
+        (size, font)
+    });
+    let font = |font_size: f32| fonts[baked_font_index(&fonts, font_size)].1;
+
+    stack_overlapping(&mut texts, DEFAULT_STACK_TOLERANCE_PX, |entry| {
+        text_size(
+            font(entry.font_size),
+            &entry.text,
+            entry.font_size,
+            screen.wrap_width(entry),
+        )[1]
+    });
+
+    let ui = ctx.new_frame();
+    let padding = ui.clone_style().window_padding;
+    let draw_list = ui.get_background_draw_list();
+    for shape in &shapes {
+        let color = color32(shape.color);
+        match shape.kind {
+            ShapeKind::Line => draw_list.add_line(shape.from, shape.to, color).build(),
+            ShapeKind::Rect => draw_list.add_rect(shape.from, shape.to, color).build(),
+            ShapeKind::FilledRect => draw_list
+                .add_rect(shape.from, shape.to, color)
+                .filled(true)
+                .build(),
+            // Only projected world shapes are circles
+            ShapeKind::Circle => {}
+        }
+    }
+    drop(draw_list);
+    for entry in &texts {
+        let font = font(entry.font_size);
+        let wrap_width = screen.wrap_width(entry);
+        let size = text_size(font, &entry.text, entry.font_size, wrap_width);
+        // The default `text.anchor`
+        let position = TextAnchor::BottomLeft.origin(entry.position, size, padding);
+        let start = entry.text.as_ptr() as *const c_char;
+        unsafe {
+            sys::ImDrawList_AddText_FontPtr(
+                sys::igGetBackgroundDrawList(),
+                font,
+                entry.font_size,
+                position.into(),
+                color32(entry.color).to_bits(),
+                start,
+                start.add(entry.text.len()),
+                wrap_width.unwrap_or(0.0),
+                std::ptr::null(),
+            );
+        }
+    }
+
+    let draw_data = ctx.render();
+    let mut canvas = Canvas::new(SCREEN_SIZE[0] as usize, SCREEN_SIZE[1] as usize);
+    canvas.draw(draw_data, &atlas);
+    canvas.write_png(&output)?;
+    println!(
+        "Frame {}: {} lines of text and {} shapes written to {}",
+        frame,
+        texts.len(),
+        shapes.len(),
+        output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,364 @@
+use std::fmt;
+
+use eldenring_util::program::Program;
+use pelite::pattern::Atom;
+use pelite::pe64::{Pe, Rva};
+
+/// Functions hooked by the overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookId {
+    DrawTextRenderRequest,
+    SetFontSize,
+    SetTextScale,
+    ResetTextScale,
+    DrawTextWithSize,
+    SetColor,
+    DrawLine,
+    DrawRect,
+    FillRect,
+    DrawWorldText,
+    DrawWorldLine,
+    DrawWorldSphere,
+    DrawWorldCapsule,
+}
+
+impl HookId {
+    pub const ALL: [HookId; 13] = [
+        HookId::DrawTextRenderRequest,
+        HookId::SetFontSize,
+        HookId::SetTextScale,
+        HookId::ResetTextScale,
+        HookId::DrawTextWithSize,
+        HookId::SetColor,
+        HookId::DrawLine,
+        HookId::DrawRect,
+        HookId::FillRect,
+        HookId::DrawWorldText,
+        HookId::DrawWorldLine,
+        HookId::DrawWorldSphere,
+        HookId::DrawWorldCapsule,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HookId::DrawTextRenderRequest => "DrawTextRenderRequest",
+            HookId::SetFontSize => "SetFontSize",
+            HookId::SetTextScale => "SetTextScale",
+            HookId::ResetTextScale => "ResetTextScale",
+            HookId::DrawTextWithSize => "DrawTextWithSize",
+            HookId::SetColor => "SetColor",
+            HookId::DrawLine => "DrawLine",
+            HookId::DrawRect => "DrawRect",
+            HookId::FillRect => "FillRect",
+            HookId::DrawWorldText => "DrawWorldText",
+            HookId::DrawWorldLine => "DrawWorldLine",
+            HookId::DrawWorldSphere => "DrawWorldSphere",
+            HookId::DrawWorldCapsule => "DrawWorldCapsule",
+        }
+    }
+
+    /// Hooks the overlay works without, failing to find them isn't reported as an error.
+    pub fn is_optional(self) -> bool {
+        matches!(
+            self,
+            HookId::DrawLine
+                | HookId::DrawRect
+                | HookId::FillRect
+                | HookId::DrawWorldText
+                | HookId::DrawWorldLine
+                | HookId::DrawWorldSphere
+                | HookId::DrawWorldCapsule
+        )
+    }
+}
+
+/// RVAs of the hooked functions in one build of the game.
+#[derive(Debug, Clone, Copy)]
+pub struct HookRvas {
+    // void FUN_14264ef60(CSEzDraw *param_1,FloatVector4 *param_2,wchar_t *param_3)
+    pub draw_text_render_request: u32,
+    // void CS::CSEzDraw::SetFontSize(CSEzDraw *param_1,float fontSize)
+    pub set_font_size: u32,
+    // void CS::CSEzDraw::SetTextScale(CSEzDraw *param_1,float textPosWidthScate,float textPosHeightScate,float fontSize)
+    pub set_text_scale: u32,
+    // void CS::CSEzDraw::ResetTextScale(CSEzDraw *param_1)
+    pub reset_text_scale: u32,
+    // void CS::CSEzDraw::DrawTextWithSize(CSEzDraw *param_1,FloatVector4 *param_2,float *param_3,wchar_t *param_4)
+    pub draw_text_with_size: u32,
+    // void CS::CSEzDraw::SetColor(CSEzDraw *param_1,FloatVector4 *color)
+    pub set_color: u32,
+    // 2D primitives, taking (CSEzDraw *, FloatVector4 *from/min, FloatVector4 *to/max) in the same
+    // coordinates as text. They haven't been located in any build yet, `None` leaves them to the
+    // config.
+    pub draw_line: Option<u32>,
+    pub draw_rect: Option<u32>,
+    pub fill_rect: Option<u32>,
+    // Text at a world position, taking (CSEzDraw *, FloatVector4 *position, wchar_t *) like
+    // DrawTextRenderRequest, which the screen space callers wrap. Not located in any build yet,
+    // `world_text.rva` sets it.
+    pub draw_world_text: Option<u32>,
+    // 3D primitives at world positions: a line taking (CSEzDraw *, FloatVector4 *from,
+    // FloatVector4 *to), a sphere taking (CSEzDraw *, FloatVector4 *center, float radius) and a
+    // capsule taking (CSEzDraw *, FloatVector4 *from, FloatVector4 *to, float radius).
+    pub draw_world_line: Option<u32>,
+    pub draw_world_sphere: Option<u32>,
+    pub draw_world_capsule: Option<u32>,
+}
+
+impl HookRvas {
+    fn get(&self, hook: HookId) -> Option<u32> {
+        match hook {
+            HookId::DrawTextRenderRequest => Some(self.draw_text_render_request),
+            HookId::SetFontSize => Some(self.set_font_size),
+            HookId::SetTextScale => Some(self.set_text_scale),
+            HookId::ResetTextScale => Some(self.reset_text_scale),
+            HookId::DrawTextWithSize => Some(self.draw_text_with_size),
+            HookId::SetColor => Some(self.set_color),
+            HookId::DrawLine => self.draw_line,
+            HookId::DrawRect => self.draw_rect,
+            HookId::FillRect => self.fill_rect,
+            HookId::DrawWorldText => self.draw_world_text,
+            HookId::DrawWorldLine => self.draw_world_line,
+            HookId::DrawWorldSphere => self.draw_world_sphere,
+            HookId::DrawWorldCapsule => self.draw_world_capsule,
+        }
+    }
+}
+
+/// Bytes a hooked function starts with in a build, checked before it's detoured so a wrong RVA
+/// doesn't patch random code. `??` matches any byte, e.g. part of a relative offset.
+struct Prologue {
+    hook: HookId,
+    bytes: &'static str,
+}
+
+/// A game build identified by the product version in the executable's version resource, e.g.
+/// `2.6.0.0`. It's the version of the executable, not the app version the title screen shows.
+pub struct KnownBuild {
+    /// Shown in the log and the diagnostics.
+    name: &'static str,
+    /// `None` when the version wasn't noted down, the build is recognized by its prologues then.
+    version: Option<&'static str>,
+    rvas: HookRvas,
+    /// Hooks without a prologue here aren't resolved from the table.
+    prologues: &'static [Prologue],
+}
+
+impl KnownBuild {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether every prologue of the build is at its RVA in `program`.
+    fn matches_code(&self, program: &Program) -> bool {
+        !self.prologues.is_empty()
+            && self.prologues.iter().all(|prologue| {
+                self.rvas
+                    .get(prologue.hook)
+                    .and_then(|rva| {
+                        program
+                            .derva_slice::<u8>(rva, prologue_len(prologue.bytes))
+                            .ok()
+                    })
+                    .is_some_and(|bytes| matches_prologue(prologue.bytes, bytes))
+            })
+    }
+}
+
+/// Builds with RVAs that were checked against the game, used when a signature doesn't match.
+/// Adding a build only takes a row here, the log lists the version of the running game.
+const KNOWN_BUILDS: &[KnownBuild] = &[
+    // The build the overlay was first written against. Its version wasn't noted down, the
+    // prologues are the signatures eldenring-util 0.8 maps its CSEzDraw RVAs with, which are
+    // the ones of this build. The text functions have no recorded prologue, so their RVAs
+    // aren't used and they need the `[rvas]` settings.
+    KnownBuild {
+        name: "eldenring-util 0.8",
+        version: None,
+        rvas: HookRvas {
+            draw_text_render_request: 0x264efc0,
+            set_font_size: 0xbb6370,
+            set_text_scale: 0x1def10,
+            reset_text_scale: 0xbb62f0,
+            draw_text_with_size: 0x264ef20,
+            set_color: 0x1adb10,
+            draw_line: None,
+            draw_rect: None,
+            fill_rect: None,
+            draw_world_text: None,
+            draw_world_line: Some(0x264fd50),
+            draw_world_sphere: Some(0x1d26b0),
+            draw_world_capsule: Some(0x2c0ca0),
+        },
+        prologues: &[
+            Prologue {
+                hook: HookId::SetColor,
+                bytes: "48 83 ec 28 48 63 41 20 f3 0f 10 22 f3 0f 10 4a 04 f3 0f 10 52 08 48 8b 4c c1 10 f3 0f 10 5a 0c",
+            },
+            Prologue {
+                hook: HookId::DrawWorldLine,
+                bytes: "40 57 48 83 ec 40 48 c7 44 24 20 fe ff ff ff 48 89 5c 24 50 48 89 6c 24 58 48 89 74 24 60 49 8b f0 48 8b fa 48 8b d9 48 8d 69 28",
+            },
+            Prologue {
+                hook: HookId::DrawWorldSphere,
+                bytes: "48 8b c4 55 48 8d 68 a1 48 81 ec b0 00 00 00 0f 28 2d ?? ?? ?? ?? 0f 28 25 ?? ?? ?? ?? 0f 28 1d ?? ?? ?? ??",
+            },
+            Prologue {
+                hook: HookId::DrawWorldCapsule,
+                bytes: "48 8b c4 55 48 8d 68 88 48 81 ec 70 01 00 00 0f 29 70 e8 0f 28 35 ?? ?? ?? ?? 0f 29 78 d8",
+            },
+        ],
+    },
+];
+
+/// Signature of the start of a hooked function.
+struct Signature {
+    hook: HookId,
+    pattern: &'static [Atom],
+}
+
+/// Signatures are tried before any RVA table, so they keep working across game patches. These
+/// are the ones eldenring-util 0.8 maps its CSEzDraw RVAs with.
+///
+/// The five text functions still need signatures of their own, the bytes they start with were
+/// never recorded. Until they're added every game patch breaks the text hooks, and their RVAs
+/// have to be set in the `[rvas]` settings.
+const SIGNATURES: &[Signature] = &[
+    Signature {
+        hook: HookId::SetColor,
+        pattern: pelite::pattern!(
+            "48 83 ec 28 48 63 41 20 f3 0f 10 22 f3 0f 10 4a 04 f3 0f 10 52 08 48 8b 4c c1 10 f3 0f 10 5a 0c"
+        ),
+    },
+    Signature {
+        hook: HookId::DrawWorldLine,
+        pattern: pelite::pattern!(
+            "40 57 48 83 ec 40 48 c7 44 24 20 fe ff ff ff 48 89 5c 24 50 48 89 6c 24 58 48 89 74 24 60 49 8b f0 48 8b fa 48 8b d9 48 8d 69 28"
+        ),
+    },
+    Signature {
+        hook: HookId::DrawWorldSphere,
+        pattern: pelite::pattern!(
+            "48 8b c4 55 48 8d 68 a1 48 81 ec b0 00 00 00 0f 28 2d $ {} 0f 28 25 $ {} 0f 28 1d $ {}"
+        ),
+    },
+    Signature {
+        hook: HookId::DrawWorldCapsule,
+        pattern: pelite::pattern!(
+            "48 8b c4 55 48 8d 68 88 48 81 ec 70 01 00 00 0f 29 70 e8 0f 28 35 $ {} 0f 29 78 d8"
+        ),
+    },
+];
+
+/// How the address of a hooked function was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressSource {
+    /// Set in the config.
+    Config,
+    Signature,
+    /// The RVA table of the game build.
+    KnownBuild(&'static str),
+}
+
+impl fmt::Display for AddressSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressSource::Config => write!(f, "config"),
+            AddressSource::Signature => write!(f, "signature"),
+            AddressSource::KnownBuild(name) => write!(f, "known build {name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedAddress {
+    pub va: u64,
+    pub source: AddressSource,
+    /// Bytes the function has to start with, set when the address came from a table of RVAs.
+    pub prologue: Option<&'static str>,
+}
+
+fn scan_signature(program: &Program, hook: HookId) -> Option<Rva> {
+    let signature = SIGNATURES.iter().find(|signature| signature.hook == hook)?;
+    let mut save = [Rva::default(); 1];
+    program
+        .scanner()
+        .finds_code(signature.pattern, &mut save)
+        .then_some(save[0])
+}
+
+/// Product version of the game's executable, `None` when it has no version resource.
+pub fn game_version(program: &Program) -> Option<String> {
+    let resources = program.resources().ok()?;
+    let version_info = resources.version_info().ok()?;
+    Some(version_info.fixed()?.dwProductVersion.to_string())
+}
+
+/// The RVA table of the running build, `None` for builds without one. Builds whose version
+/// wasn't noted down are matched by their prologues instead.
+pub fn known_build(program: &Program, version: Option<&str>) -> Option<&'static KnownBuild> {
+    KNOWN_BUILDS.iter().find(|build| match build.version {
+        Some(known) => version == Some(known),
+        None => build.matches_code(program),
+    })
+}
+
+/// Finds the address of a hooked function, `None` when it couldn't be resolved.
+///
+/// `configured` is an RVA set in the config, it's used over anything else. `build` is the RVA
+/// table of the game version, without one only signatures and the config are tried.
+pub fn resolve(
+    program: &Program,
+    hook: HookId,
+    configured: Option<u32>,
+    build: Option<&'static KnownBuild>,
+) -> Option<ResolvedAddress> {
+    // A signature already matched the bytes, and an RVA from the config is the user's call
+    let (rva, source, prologue) = match configured.or_else(|| scan_signature(program, hook)) {
+        Some(rva) if configured.is_some() => (rva, AddressSource::Config, None),
+        Some(rva) => (rva, AddressSource::Signature, None),
+        None => {
+            let build = build?;
+            let rva = build.rvas.get(hook)?;
+            // Unchecked, it could patch the wrong code of a build that only looks like this one
+            let Some(prologue) = build
+                .prologues
+                .iter()
+                .find(|prologue| prologue.hook == hook)
+            else {
+                tracing::warn!(
+                    "{} has no prologue recorded for {}, not using its RVA",
+                    hook.name(),
+                    build.name
+                );
+                return None;
+            };
+            (
+                rva,
+                AddressSource::KnownBuild(build.name),
+                Some(prologue.bytes),
+            )
+        }
+    };
+
+    let va = program.rva_to_va(rva).ok()?;
+    Some(ResolvedAddress {
+        va,
+        source,
+        prologue,
+    })
+}
+
+/// Whether `bytes` start with `prologue`, written as hex bytes separated by spaces.
+pub fn matches_prologue(prologue: &str, bytes: &[u8]) -> bool {
+    let pattern = prologue.split_whitespace().collect::<Vec<_>>();
+    pattern.len() <= bytes.len()
+        && pattern.iter().zip(bytes).all(|(expected, &byte)| {
+            *expected == "??" || u8::from_str_radix(expected, 16).is_ok_and(|b| b == byte)
+        })
+}
+
+/// Number of bytes `prologue` covers.
+pub fn prologue_len(prologue: &str) -> usize {
+    prologue.split_whitespace().count()
+}
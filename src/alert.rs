@@ -0,0 +1,162 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use hudhook::imgui::Ui;
+use hudhook::windows::Win32::{
+    Foundation::HMODULE,
+    Media::Audio::{PlaySoundW, SND_ASYNC, SND_FILENAME, SND_NODEFAULT},
+    System::Diagnostics::Debug::MessageBeep,
+    UI::WindowsAndMessaging::MB_ICONEXCLAMATION,
+};
+use hudhook::windows::core::HSTRING;
+use regex::Regex;
+
+use crate::DebugTextRender;
+use crate::config::AlertRule;
+use crate::text::TextId;
+
+/// How long the screen edge flashes after an alert.
+const FLASH_DURATION: Duration = Duration::from_millis(300);
+const FLASH_THICKNESS: f32 = 6.0;
+/// Texts whose matching rule is remembered before the cache starts over.
+const MAX_CACHED: usize = 4096;
+
+struct Alert {
+    rule: AlertRule,
+    regex: Option<Regex>,
+    /// Sound file with a relative path resolved against the DLL directory.
+    sound_path: Option<PathBuf>,
+    last_fired: Option<Instant>,
+}
+
+/// Rules that flash the screen edge or play a sound when captured text matches them.
+pub struct AlertList {
+    alerts: Vec<Alert>,
+    /// First matching rule of each text seen, so repeated text isn't matched again every frame.
+    matches: HashMap<TextId, Option<usize>>,
+    /// Color and start of the current flash.
+    flash: Option<([f32; 4], Instant)>,
+}
+
+impl AlertList {
+    pub fn new(rules: &[AlertRule], dir: Option<&Path>) -> Self {
+        let mut alerts = Self {
+            alerts: Vec::new(),
+            matches: HashMap::new(),
+            flash: None,
+        };
+        alerts.set_rules(rules, dir);
+        alerts
+    }
+
+    /// Replaces the rules, sound paths are relative to `dir`.
+    pub fn set_rules(&mut self, rules: &[AlertRule], dir: Option<&Path>) {
+        if self.alerts.iter().map(|alert| &alert.rule).eq(rules.iter()) {
+            return;
+        }
+        self.alerts = rules
+            .iter()
+            .map(|rule| Alert {
+                regex: match Regex::new(&rule.pattern) {
+                    Ok(regex) => Some(regex),
+                    Err(e) => {
+                        tracing::error!("Invalid alert pattern {:?}: {}", rule.pattern, e);
+                        None
+                    }
+                },
+                sound_path: rule.sound_path.as_ref().map(|path| match dir {
+                    Some(dir) if path.is_relative() => dir.join(path),
+                    _ => path.clone(),
+                }),
+                rule: rule.clone(),
+                last_fired: None,
+            })
+            .collect();
+        self.matches.clear();
+    }
+
+    /// Fires the first rule `text` matches, unless it fired within its cooldown.
+    pub fn observe(&mut self, text: &str) {
+        if self.alerts.is_empty() {
+            return;
+        }
+        if self.matches.len() >= MAX_CACHED {
+            self.matches.clear();
+        }
+        let alerts = &self.alerts;
+        let index = *self
+            .matches
+            .entry(TextId::new(text, [0.0, 0.0]))
+            .or_insert_with(|| {
+                alerts.iter().position(|alert| {
+                    alert
+                        .regex
+                        .as_ref()
+                        .is_some_and(|regex| regex.is_match(text))
+                })
+            });
+        let Some(alert) = index.map(|index| &mut self.alerts[index]) else {
+            return;
+        };
+        let cooldown = Duration::from_millis(alert.rule.cooldown_ms);
+        if alert
+            .last_fired
+            .is_some_and(|fired| fired.elapsed() < cooldown)
+        {
+            return;
+        }
+        alert.last_fired = Some(Instant::now());
+
+        tracing::warn!("Alert {:?} matched: {}", alert.rule.pattern, text);
+        if alert.rule.flash {
+            self.flash = Some((alert.rule.color, Instant::now()));
+        }
+        if alert.rule.sound {
+            play_sound(alert.sound_path.as_deref());
+        }
+    }
+}
+
+/// Plays a wav file without waiting for it, or the system's exclamation sound without one.
+fn play_sound(path: Option<&Path>) {
+    let played = match path {
+        Some(path) => unsafe {
+            PlaySoundW(
+                &HSTRING::from(path),
+                HMODULE::default(),
+                SND_FILENAME | SND_ASYNC | SND_NODEFAULT,
+            )
+            .as_bool()
+        },
+        None => unsafe { MessageBeep(MB_ICONEXCLAMATION).is_ok() },
+    };
+    if !played {
+        tracing::warn!("Failed to play the alert sound {:?}", path);
+    }
+}
+
+impl DebugTextRender {
+    /// Draws a border around the screen for a moment after an alert fires.
+    pub(crate) fn draw_alert_flash(&mut self, ui: &Ui) {
+        let Some((color, fired)) = self.alerts.flash else {
+            return;
+        };
+        if fired.elapsed() >= FLASH_DURATION {
+            self.alerts.flash = None;
+            return;
+        }
+        let [width, height] = ui.io().display_size;
+        let inset = FLASH_THICKNESS / 2.0;
+        ui.get_foreground_draw_list()
+            .add_rect(
+                [inset, inset],
+                [width - inset, height - inset],
+                self.color_space().color32(color),
+            )
+            .thickness(FLASH_THICKNESS)
+            .build();
+    }
+}
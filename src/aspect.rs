@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+/// How the ratio between the screen and the window the game renders in is applied to text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AspectMode {
+    /// Kept within `aspect_min..=aspect_max`.
+    Clamp,
+    /// Used as is.
+    Exact,
+}
+
+impl AspectMode {
+    pub const ALL: [AspectMode; 2] = [AspectMode::Clamp, AspectMode::Exact];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AspectMode::Clamp => "clamp",
+            AspectMode::Exact => "exact",
+        }
+    }
+}
+
+/// Ratio of `screen_size` to `window_size` on each axis, kept within `bounds` when set.
+///
+/// A 3840x2160 screen size with a 1920x1080 window gives 2 on both axes, a 2560x1080 ultrawide
+/// with the same window gives 1.33 horizontally and 1 vertically.
+pub fn aspect_ratios(
+    window_size: [f32; 2],
+    screen_size: [f32; 2],
+    bounds: Option<(f32, f32)>,
+) -> (f32, f32) {
+    let ratio = |axis: usize| {
+        let ratio = screen_size[axis] / window_size[axis];
+        match bounds {
+            Some((min, max)) => ratio.clamp(min, max),
+            None => ratio,
+        }
+    };
+    (ratio(0), ratio(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: [f32; 2] = [1920.0, 1080.0];
+
+    #[test]
+    fn exact_ratios() {
+        assert_eq!(aspect_ratios(WINDOW, [3840.0, 2160.0], None), (2.0, 2.0));
+        assert_eq!(aspect_ratios(WINDOW, WINDOW, None), (1.0, 1.0));
+        assert_eq!(
+            aspect_ratios(WINDOW, [1280.0, 720.0], None),
+            (2.0 / 3.0, 2.0 / 3.0)
+        );
+        let (width, height) = aspect_ratios(WINDOW, [2560.0, 1080.0], None);
+        assert!((width - 4.0 / 3.0).abs() < 1e-6);
+        assert_eq!(height, 1.0);
+    }
+
+    #[test]
+    fn ratios_are_clamped_to_the_bounds() {
+        let bounds = Some((0.8, 1.5));
+        assert_eq!(aspect_ratios(WINDOW, [3840.0, 2160.0], bounds), (1.5, 1.5));
+        assert_eq!(aspect_ratios(WINDOW, [1280.0, 720.0], bounds), (0.8, 0.8));
+        // Each axis is clamped on its own
+        assert_eq!(aspect_ratios(WINDOW, [3840.0, 1080.0], bounds), (1.5, 1.0));
+    }
+
+    #[test]
+    fn unbounded_maximum() {
+        let bounds = Some((0.8, f32::INFINITY));
+        assert_eq!(aspect_ratios(WINDOW, [3840.0, 2160.0], bounds), (2.0, 2.0));
+        assert_eq!(aspect_ratios(WINDOW, [960.0, 540.0], bounds), (0.8, 0.8));
+    }
+}
@@ -0,0 +1,49 @@
+use eldenring::cs::WorldChrMan;
+use eldenring_util::singleton::get_instance;
+
+use crate::config::AutoHideSettings;
+use crate::flags::read_flag;
+
+/// A state of the game the overlay hides itself in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Cutscene,
+    /// The title screen, and loading into the game until there's a player.
+    MainMenu,
+    Map,
+}
+
+impl GameState {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameState::Cutscene => "cutscene",
+            GameState::MainMenu => "main menu",
+            GameState::Map => "map",
+        }
+    }
+}
+
+/// The state the overlay is hidden in this frame, `None` when it's shown.
+///
+/// Only the states that hide it are checked, the main menu first since it takes a single
+/// singleton read and nothing else can be open without a player.
+pub fn hiding_state(settings: &AutoHideSettings) -> Option<GameState> {
+    if settings.main_menu && !has_player() {
+        return Some(GameState::MainMenu);
+    }
+    let flag_set = |flag| read_flag(flag).is_some_and(|value| value != 0);
+    if settings.cutscenes && settings.cutscene_flag.as_ref().is_some_and(flag_set) {
+        return Some(GameState::Cutscene);
+    }
+    if settings.map && settings.map_flag.as_ref().is_some_and(flag_set) {
+        return Some(GameState::Map);
+    }
+    None
+}
+
+fn has_player() -> bool {
+    unsafe { get_instance::<WorldChrMan>() }
+        .ok()
+        .flatten()
+        .is_some_and(|world_chr_man| world_chr_man.main_player.is_some())
+}
@@ -0,0 +1,64 @@
+use std::sync::OnceLock;
+
+use hudhook::windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use hudhook::windows::core::w;
+use serde::{Deserialize, Serialize};
+
+/// Graphics API the overlay hooks the game's swapchain through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsApi {
+    /// Picked from the graphics libraries the game has loaded.
+    Auto,
+    Dx12,
+    Dx11,
+}
+
+/// Backend the overlay was hooked with, set once the render hooks are applied.
+static HOOKED_BACKEND: OnceLock<GraphicsApi> = OnceLock::new();
+
+impl GraphicsApi {
+    pub fn name(self) -> &'static str {
+        match self {
+            GraphicsApi::Auto => "auto",
+            GraphicsApi::Dx12 => "dx12",
+            GraphicsApi::Dx11 => "dx11",
+        }
+    }
+
+    /// Resolves `Auto` to the API the game renders with, once its device exists.
+    ///
+    /// The game normally runs on DirectX 12, DirectX 11 is only picked when `d3d12.dll` isn't
+    /// loaded but `d3d11.dll` is. Wrappers that load both need the API set in the config.
+    pub fn resolve(self) -> GraphicsApi {
+        if self != GraphicsApi::Auto {
+            return self;
+        }
+        let loaded_d3d12 = unsafe { GetModuleHandleW(w!("d3d12.dll")) }.is_ok();
+        let loaded_d3d11 = unsafe { GetModuleHandleW(w!("d3d11.dll")) }.is_ok();
+        if !loaded_d3d12 && loaded_d3d11 {
+            GraphicsApi::Dx11
+        } else {
+            GraphicsApi::Dx12
+        }
+    }
+}
+
+/// What happens when the game doesn't finish starting within `init_timeout_s`, e.g. because a
+/// patch broke detecting it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InitTimeout {
+    /// The renderer is hooked anyway.
+    Hook,
+    /// The renderer isn't hooked, the captured commands are written to the dump instead.
+    Dump,
+}
+
+pub fn set_hooked_backend(api: GraphicsApi) {
+    HOOKED_BACKEND.set(api).ok();
+}
+
+pub fn hooked_backend() -> Option<GraphicsApi> {
+    HOOKED_BACKEND.get().copied()
+}
@@ -0,0 +1,186 @@
+use std::time::Instant;
+
+use hudhook::imgui::Ui;
+use serde::{Deserialize, Serialize};
+use toml_edit::InlineTable;
+
+use crate::DebugTextRender;
+use crate::caller::set_capture_callers;
+use crate::config::save_blocked;
+use crate::filter::set_filter;
+
+/// Id of the context menu, opened outside of any window so it's the same wherever it's opened.
+const BLOCK_MENU: &str = "##hide_text";
+
+/// Text hidden from a context menu, kept in `filter.blocked`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockRule {
+    /// Text that's exactly this.
+    Text(String),
+    /// Text starting with this.
+    Prefix(String),
+    /// Text drawn by the game code returning to this RVA.
+    Caller(u32),
+}
+
+impl BlockRule {
+    pub fn blocks(&self, text: &str, caller: Option<u32>) -> bool {
+        match self {
+            BlockRule::Text(blocked) => text == blocked,
+            BlockRule::Prefix(prefix) => text.starts_with(prefix.as_str()),
+            BlockRule::Caller(rva) => caller == Some(*rva),
+        }
+    }
+
+    /// What the rule hides, for the settings window and the log.
+    pub fn describe(&self) -> String {
+        match self {
+            BlockRule::Text(text) => format!("{text:?}"),
+            BlockRule::Prefix(prefix) => format!("{prefix:?}..."),
+            BlockRule::Caller(rva) => format!("text from {rva:#x}"),
+        }
+    }
+
+    /// The rule as written to the config, the same as serde would write it.
+    pub fn to_toml(&self) -> InlineTable {
+        let mut table = InlineTable::new();
+        match self {
+            BlockRule::Text(text) => table.insert("text", text.as_str().into()),
+            BlockRule::Prefix(prefix) => table.insert("prefix", prefix.as_str().into()),
+            BlockRule::Caller(rva) => table.insert("caller", i64::from(*rva).into()),
+        };
+        table
+    }
+}
+
+/// The text a context menu was opened for, kept while it's open since the text under the mouse
+/// doesn't stay the same.
+pub struct BlockTarget {
+    text: String,
+    caller: Option<u32>,
+    /// Edited in the menu, starts out as the whole text.
+    prefix: String,
+    /// Whether the popup was opened yet, imgui only opens it from outside the window clicked in.
+    opened: bool,
+}
+
+impl DebugTextRender {
+    /// Opens the menu for hiding `text`, drawn by the game code at `caller` if it's known.
+    pub(crate) fn open_block_menu(&mut self, text: &str, caller: Option<u32>) {
+        self.block_target = Some(BlockTarget {
+            text: text.to_string(),
+            caller,
+            prefix: text.to_string(),
+            opened: false,
+        });
+    }
+
+    /// Draws the menu opened with [`DebugTextRender::open_block_menu`], to be called outside of
+    /// any window.
+    pub(crate) fn draw_block_menu(&mut self, ui: &Ui) {
+        let Some(target) = &mut self.block_target else {
+            return;
+        };
+        if !target.opened {
+            ui.open_popup(BLOCK_MENU);
+            target.opened = true;
+        }
+        let Some(_popup) = ui.begin_popup(BLOCK_MENU) else {
+            self.block_target = None;
+            return;
+        };
+
+        let mut rule = None;
+        if ui.menu_item("Hide this exact text") {
+            rule = Some(BlockRule::Text(target.text.clone()));
+        }
+        match target.caller {
+            Some(rva) => {
+                if ui.menu_item(format!("Hide this source ({rva:#x})")) {
+                    rule = Some(BlockRule::Caller(rva));
+                }
+            }
+            None => {
+                ui.menu_item_config("Hide this source (turn on Show callers)")
+                    .enabled(false)
+                    .build();
+            }
+        }
+        ui.separator();
+        ui.text("Hide matching prefix...");
+        ui.set_next_item_width(300.0);
+        let submitted = ui
+            .input_text("##prefix", &mut target.prefix)
+            .enter_returns_true(true)
+            .build();
+        ui.same_line();
+        if (ui.button("Hide") || submitted) && !target.prefix.is_empty() {
+            rule = Some(BlockRule::Prefix(target.prefix.clone()));
+        }
+
+        if let Some(rule) = rule {
+            ui.close_current_popup();
+            self.block_target = None;
+            self.block(rule);
+        }
+    }
+
+    /// Hides the text the rule matches from now on, and adds it to the config file.
+    pub(crate) fn block(&mut self, rule: BlockRule) {
+        if self.config.filter.blocked.contains(&rule) {
+            return;
+        }
+        tracing::info!("Hiding {}", rule.describe());
+        self.notice = Some((format!("hid {}", rule.describe()), Instant::now()));
+        self.config.filter.blocked.push(rule);
+        self.blocked_changed();
+    }
+
+    pub(crate) fn unblock(&mut self, index: usize) {
+        let rule = self.config.filter.blocked.remove(index);
+        tracing::info!("No longer hiding {}", rule.describe());
+        self.blocked_changed();
+    }
+
+    fn blocked_changed(&mut self) {
+        let blocked = self.config.filter.blocked.clone();
+        // The shared config is what a frame syncs to, without this the rule would be gone from
+        // the config again on the next one
+        if let Ok(mut shared) = self.shared_config.write() {
+            shared.filter.blocked = blocked.clone();
+        }
+        set_filter(&self.current_config().filter);
+        set_capture_callers(self.needs_callers());
+        match &self.config_path {
+            // Only the rules are written, so the rest of the file and its comments stay as they are
+            Some(path) => {
+                if let Err(e) = save_blocked(path, &blocked) {
+                    tracing::error!("{}: {}", path.display(), e);
+                }
+            }
+            None => tracing::warn!("There's no config file, hidden text shows up again on restart"),
+        }
+    }
+
+    /// The rules added from the context menus, with buttons to remove them.
+    pub(crate) fn draw_blocklist_settings(&mut self, ui: &Ui) {
+        if self.config.filter.blocked.is_empty() {
+            ui.text_disabled("Right-click text while inspecting or in the history to hide it");
+            return;
+        }
+        ui.text("Hidden");
+        let mut removed = None;
+        for (index, rule) in self.config.filter.blocked.iter().enumerate() {
+            let _id = ui.push_id_usize(index);
+            if ui.small_button("x") {
+                removed = Some(index);
+            }
+            ui.same_line();
+            ui.text(rule.describe());
+        }
+        if let Some(index) = removed {
+            self.unblock(index);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::{
+    ffi::c_void,
+    ops::Range,
+    sync::{
+        LazyLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use hudhook::windows::Win32::System::Diagnostics::Debug::RtlCaptureStackBackTrace;
+use pelite::pe64::{Pe, PeObject};
+
+use crate::target::target_program;
+
+/// Frames walked looking for the game's code. The detours run a few frames below the game's
+/// call, through retour's trampoline and the panic guard.
+const MAX_FRAMES: usize = 16;
+
+/// Whether the detours look up who called the hooked function, which walks the stack on every
+/// call. Only set while something uses it.
+static CAPTURE_CALLERS: AtomicBool = AtomicBool::new(false);
+
+/// Addresses the game's executable is loaded at, read once the hooks calling it are in.
+static GAME_IMAGE: LazyLock<Range<usize>> = LazyLock::new(|| {
+    let program = target_program();
+    let base = program.image().as_ptr() as usize;
+    base..base + program.optional_header().SizeOfImage as usize
+});
+
+pub fn set_capture_callers(capture: bool) {
+    CAPTURE_CALLERS.store(capture, Ordering::Relaxed);
+}
+
+/// RVA in the game's executable of the code that called the hooked function, `None` when
+/// callers aren't captured or no frame is in the game.
+///
+/// The hooked function itself isn't on the stack, its prologue jumps straight to the detour, so
+/// the first frame in the game is its caller.
+pub fn caller_rva() -> Option<u32> {
+    if !CAPTURE_CALLERS.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let mut frames = [std::ptr::null_mut::<c_void>(); MAX_FRAMES];
+    let captured = unsafe { RtlCaptureStackBackTrace(1, &mut frames, None) } as usize;
+    let image = &*GAME_IMAGE;
+    frames[..captured]
+        .iter()
+        .map(|&frame| frame as usize)
+        .find(|address| image.contains(address))
+        .map(|address| (address - image.start) as u32)
+}
+
+/// Whether `rva` is in one of the inclusive `[first, last]` ranges.
+pub fn in_ranges(rva: u32, ranges: &[[u32; 2]]) -> bool {
+    ranges
+        .iter()
+        .any(|&[first, last]| (first..=last).contains(&rva))
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use hudhook::imgui::{TreeNodeFlags, Ui};
+use regex::Regex;
+
+use crate::DebugTextRender;
+use crate::config::{CategorySettings, TextCategory};
+use crate::layer::Layer;
+use crate::processor::TextEntry;
+use crate::text::TextId;
+
+/// Texts whose category is remembered before the cache starts over.
+const MAX_CACHED: usize = 4096;
+
+/// Category rules with their compiled patterns, sorting the text on screen into categories.
+///
+/// An entry only keeps the index of its category, whether it's shown and the layer it's on are
+/// looked up while drawing so toggling them applies to retained and fading text at once.
+pub struct Categories {
+    settings: CategorySettings,
+    /// `None` for an empty pattern or one that doesn't compile.
+    regexes: Vec<Option<Regex>>,
+    /// Category of each text seen, `None` for `other`.
+    matches: HashMap<TextId, Option<usize>>,
+}
+
+impl Categories {
+    pub fn new(settings: &CategorySettings) -> Self {
+        let mut categories = Self {
+            settings: CategorySettings::default(),
+            regexes: Vec::new(),
+            matches: HashMap::new(),
+        };
+        categories.set(settings);
+        categories
+    }
+
+    /// Takes new settings, the patterns are only compiled again when one of them changed.
+    pub fn set(&mut self, settings: &CategorySettings) {
+        if *settings == self.settings {
+            return;
+        }
+        let patterns_changed = settings.rules.len() != self.settings.rules.len()
+            || settings
+                .rules
+                .iter()
+                .zip(&self.settings.rules)
+                .any(|(new, old)| new.pattern != old.pattern);
+        self.settings = settings.clone();
+        if !patterns_changed {
+            return;
+        }
+        self.regexes = settings
+            .rules
+            .iter()
+            .map(|rule| match Regex::new(&format!("^(?:{})", rule.pattern)) {
+                _ if rule.pattern.is_empty() => None,
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    tracing::error!("Invalid category pattern {:?}: {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        self.matches.clear();
+    }
+
+    pub fn settings(&self) -> &CategorySettings {
+        &self.settings
+    }
+
+    /// Puts the text into the first category whose pattern matches its start, and gives it
+    /// that category's color.
+    pub fn classify(&mut self, entry: &mut TextEntry) {
+        if self.settings.rules.is_empty() {
+            entry.category = None;
+        } else {
+            if self.matches.len() >= MAX_CACHED {
+                self.matches.clear();
+            }
+            let regexes = &self.regexes;
+            entry.category = *self.matches.entry(entry.id()).or_insert_with(|| {
+                regexes.iter().position(|regex| {
+                    regex
+                        .as_ref()
+                        .is_some_and(|regex| regex.is_match(&entry.text))
+                })
+            });
+        }
+        if let Some(color) = self.category(entry.category).color {
+            entry.color = color;
+        }
+    }
+
+    fn category(&self, index: Option<usize>) -> &TextCategory {
+        index
+            .and_then(|index| self.settings.rules.get(index))
+            .unwrap_or(&self.settings.other)
+    }
+
+    pub fn is_shown(&self, index: Option<usize>) -> bool {
+        self.category(index).shown
+    }
+
+    /// Layer the category's text is drawn on, `None` for the one in `layers.text`.
+    pub fn layer(&self, index: Option<usize>) -> Option<Layer> {
+        self.category(index).layer
+    }
+}
+
+/// Controls for a category's visibility, color and layer.
+fn draw_category_style(ui: &Ui, category: &mut TextCategory) {
+    ui.checkbox("Shown", &mut category.shown);
+    ui.same_line();
+    let mut colored = category.color.is_some();
+    if ui.checkbox("Color", &mut colored) {
+        category.color = colored.then_some([1.0, 1.0, 1.0, 1.0]);
+    }
+    if let Some(color) = &mut category.color {
+        ui.same_line();
+        ui.color_edit4_config("##color", color)
+            .inputs(false)
+            .build();
+    }
+    ui.same_line();
+    let layer_names = ["text layer"]
+        .into_iter()
+        .chain(Layer::ALL.map(Layer::name))
+        .collect::<Vec<_>>();
+    let mut layer = category
+        .layer
+        .and_then(|layer| Layer::ALL.iter().position(|option| *option == layer))
+        .map_or(0, |index| index + 1);
+    ui.set_next_item_width(120.0);
+    if ui.combo_simple_string("##layer", &mut layer, &layer_names) {
+        category.layer = layer.checked_sub(1).map(|index| Layer::ALL[index]);
+    }
+}
+
+impl DebugTextRender {
+    /// Settings section listing the categories, with controls to edit, add and remove them.
+    pub(crate) fn draw_category_settings(&mut self, ui: &Ui) {
+        if !ui.collapsing_header("Categories", TreeNodeFlags::empty()) {
+            return;
+        }
+        ui.text("Text goes into the first category whose pattern matches its start");
+
+        let mut settings = self.categories.settings().clone();
+        let mut removed = None;
+        for (index, rule) in settings.rules.iter_mut().enumerate() {
+            let _id = ui.push_id_usize(index);
+            if ui.small_button("x") {
+                removed = Some(index);
+            }
+            ui.same_line();
+            ui.set_next_item_width(100.0);
+            ui.input_text("##name", &mut rule.name).build();
+            ui.same_line();
+            ui.set_next_item_width(160.0);
+            ui.input_text("##pattern", &mut rule.pattern).build();
+            if !rule.pattern.is_empty()
+                && self
+                    .categories
+                    .regexes
+                    .get(index)
+                    .is_some_and(Option::is_none)
+            {
+                ui.same_line();
+                ui.text_disabled("invalid");
+            }
+            ui.same_line();
+            draw_category_style(ui, rule);
+        }
+        {
+            let _id = ui.push_id("other");
+            ui.text(format!("{} (everything else)", settings.other.name));
+            ui.same_line();
+            draw_category_style(ui, &mut settings.other);
+        }
+        if let Some(index) = removed {
+            settings.rules.remove(index);
+        }
+        if ui.button("Add category") {
+            settings.rules.push(TextCategory::default());
+        }
+        // Only recompiled when a pattern changed
+        self.categories.set(&settings);
+    }
+}
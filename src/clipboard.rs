@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use hudhook::imgui::Ui;
+use hudhook::windows::Win32::{
+    Foundation::{GlobalFree, HANDLE, HWND},
+    System::{
+        DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData},
+        Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalUnlock},
+    },
+};
+
+use crate::DebugTextRender;
+
+/// Standard clipboard format for UTF-16 text.
+const CF_UNICODETEXT: u32 = 13;
+/// How long the confirmation stays on screen after copying.
+const NOTICE_DURATION: Duration = Duration::from_secs(1);
+
+/// Replaces the contents of the clipboard with `text`.
+pub fn set_clipboard_text(text: &str) -> hudhook::windows::core::Result<()> {
+    let wide = text.encode_utf16().chain([0]).collect::<Vec<u16>>();
+    unsafe {
+        OpenClipboard(HWND::default())?;
+        let result = (|| {
+            EmptyClipboard()?;
+            let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * 2)?;
+            let data = GlobalLock(memory) as *mut u16;
+            if data.is_null() {
+                let e = hudhook::windows::core::Error::from_win32();
+                GlobalFree(memory).ok();
+                return Err(e);
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), data, wide.len());
+            // Fails with no error once the memory is unlocked, which is the point
+            GlobalUnlock(memory).ok();
+            // The clipboard owns the memory once this succeeds
+            if let Err(e) = SetClipboardData(CF_UNICODETEXT, HANDLE(memory.0 as isize)) {
+                GlobalFree(memory).ok();
+                return Err(e);
+            }
+            Ok(())
+        })();
+        CloseClipboard().ok();
+        result
+    }
+}
+
+impl DebugTextRender {
+    /// Copies the text on screen to the clipboard as `x,y: text` lines, top to bottom and left
+    /// to right.
+    pub(crate) fn copy_visible_text(&mut self) {
+        let mut entries = self.retained.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            a.position[1]
+                .total_cmp(&b.position[1])
+                .then(a.position[0].total_cmp(&b.position[0]))
+        });
+        let text = entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{:.0},{:.0}: {}",
+                    entry.position[0], entry.position[1], entry.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n");
+
+        match set_clipboard_text(&text) {
+            Ok(()) => {
+                self.notice = Some((format!("copied {} lines", entries.len()), Instant::now()));
+            }
+            Err(e) => {
+                tracing::error!("Failed to copy the text on screen to the clipboard: {}", e);
+                self.notice = Some(("copying failed, see the log".to_string(), Instant::now()));
+            }
+        }
+    }
+
+    /// Draws the last notice, e.g. the result of a copy, at the top of the screen for a second.
+    pub(crate) fn draw_notice(&mut self, ui: &Ui) {
+        let Some((notice, shown_at)) = &self.notice else {
+            return;
+        };
+        if shown_at.elapsed() >= NOTICE_DURATION {
+            self.notice = None;
+            return;
+        }
+        let width = ui.calc_text_size(notice)[0];
+        let x = (ui.io().display_size[0] - width) / 2.0;
+        ui.get_foreground_draw_list().add_text(
+            [x, 10.0],
+            self.color_space().rgb(255, 255, 255),
+            notice,
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// A call captured by the hooks, queued for the render loop.
+///
+/// Serialized for the JSON Lines dump as `{"type": "text", "fields": {...}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "fields", rename_all = "snake_case")]
+pub enum DrawCommand {
+    Text {
+        ez_draw: usize,
+        text: Arc<str>,
+        /// RVA of the game code that drew the text, only looked up while something uses it.
+        caller: Option<u32>,
+        x: f32,
+        y: f32,
+        /// Explicit font size that overrides the current one.
+        font_size: Option<f32>,
+        /// Width of the box the text is drawn in, before the text scale is applied.
+        box_width: Option<f32>,
+        /// Set for text at a position in the world, `x`, `y` and this are its coordinates.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        world_z: Option<f32>,
+    },
+    SetFontSize(usize, f32),
+    SetTextScale(usize, f32, f32, f32),
+    ResetTextScale(usize),
+    /// Color of the primitives drawn after it.
+    SetColor(usize, [f32; 4]),
+    Line {
+        ez_draw: usize,
+        from: [f32; 2],
+        to: [f32; 2],
+    },
+    Rect {
+        ez_draw: usize,
+        min: [f32; 2],
+        max: [f32; 2],
+        filled: bool,
+    },
+    /// 3D primitives at world positions, drawn in the color of `SetColor` like the 2D ones.
+    WorldLine {
+        ez_draw: usize,
+        from: [f32; 3],
+        to: [f32; 3],
+    },
+    WorldSphere {
+        ez_draw: usize,
+        center: [f32; 3],
+        radius: f32,
+    },
+    WorldCapsule {
+        ez_draw: usize,
+        from: [f32; 3],
+        to: [f32; 3],
+        radius: f32,
+    },
+}
@@ -0,0 +1,1463 @@
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    fmt, io,
+    os::windows::ffi::OsStringExt,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use hudhook::windows::Win32::{
+    Foundation::{HINSTANCE, HMODULE},
+    System::LibraryLoader::GetModuleFileNameW,
+};
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::filter::{Directive, LevelFilter};
+
+use crate::TextOutline;
+use crate::address::HookId;
+use crate::aspect::AspectMode;
+use crate::backend::{GraphicsApi, InitTimeout};
+use crate::blocklist::BlockRule;
+use crate::dump::DumpFormat;
+use crate::flags::{DrawCategory, FlagMode};
+use crate::focus::UnfocusedMode;
+use crate::glyphs::{GlyphRange, is_valid_range};
+use crate::hdr::HdrMode;
+use crate::hotkey::parse_key;
+use crate::layer::Layer;
+use crate::panel::Presentation;
+use crate::placement::{OffscreenMode, TextAnchor};
+use crate::processor::{DEFAULT_BAKED_SIZES_PX, DEFAULT_STACK_TOLERANCE_PX};
+use crate::toast::{Severity, notify};
+
+pub const CONFIG_FILE_NAME: &str = "textrender.toml";
+/// Environment variable overriding `enabled`, `0` or `false` keeps the overlay down and `1` or
+/// `true` brings it up.
+pub const ENABLED_ENV_VAR: &str = "TEXTRENDER_ENABLED";
+
+/// Settings read from `textrender.toml` next to the DLL.
+///
+/// Every field has a default, so a missing file or a missing key behaves like the values below.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Install the hooks and the renderer. While it's `false` the DLL only logs that it's
+    /// disabled and waits for the config to enable it. Default `true`.
+    pub enabled: bool,
+    /// Install the hooks and the renderer only once `hotkeys.toggle` is pressed, instead of while
+    /// the game starts. Default `false`.
+    pub lazy: bool,
+    /// Log level, one of `off`, `error`, `warn`, `info`, `debug` or `trace`. Default `debug`.
+    pub log_level: String,
+    /// `target=level` directives applied on top of `log_level`, e.g. `textrender::hooks=trace`.
+    /// Default empty.
+    pub log_directives: Vec<String>,
+    /// Log every text, font size and text scale command drawn, at the trace level. Default
+    /// `false`.
+    pub log_captured_text: bool,
+    /// Megabytes `textrender.log` grows to before it's renamed with the time appended and a new
+    /// one is started, 0 lets it grow. Default 10.
+    pub log_max_size_mb: u64,
+    /// Renamed log files kept next to the DLL, the oldest are deleted. Default 5.
+    pub log_max_files: usize,
+    /// Write a minidump and a crash report when code in the DLL faults, e.g. with an access
+    /// violation in a detour. Faults elsewhere are left to the game. Default `false`.
+    pub minidump: bool,
+    /// Commands that can be queued between two rendered frames. Default 10000.
+    pub queue_capacity: usize,
+    /// Identical text at the same position is dropped for this long after it was queued, 0 queues
+    /// every copy. Default 0.
+    pub duplicate_window_ms: u64,
+    /// Text queued longer ago than this is discarded when it's finally drawn, after the render
+    /// loop stalled. 0 keeps it. Default 250.
+    pub max_command_age_ms: u64,
+    /// UTF-16 code units read from a captured string before it's cut off. Default 4096.
+    pub max_text_length: usize,
+    /// Open a console window with the log at startup, `textrender.log` next to the DLL is always
+    /// written. Default `false`.
+    pub console: bool,
+    /// Whether the diagnostics panel is shown at startup. Default `false`.
+    pub diagnostics: bool,
+    /// Graphics API the overlay hooks, one of `auto`, `dx12` or `dx11`. `auto` uses DirectX 11
+    /// only when the game didn't load DirectX 12. Default `auto`.
+    pub graphics_api: GraphicsApi,
+    /// Seconds the renderer waits for the game to finish starting before `on_init_timeout`
+    /// applies, 0 waits forever. Default 300.
+    pub init_timeout_s: u64,
+    /// `hook` hooks the renderer anyway, `dump` leaves it alone and writes what the hooks
+    /// capture to `dump.path`. Default `dump`.
+    pub on_init_timeout: InitTimeout,
+    /// Hook the renderer even when another hudhook based overlay, e.g. a practice tool, is
+    /// loaded. The two usually crash the game. Default `false`.
+    pub ignore_overlay_conflicts: bool,
+    /// Let the game draw its own debug text and shapes as well, which it otherwise only does
+    /// while the overlay isn't hooked. Default `false`.
+    pub passthrough_native: bool,
+    pub target: TargetSettings,
+    pub rvas: RvaSettings,
+    pub font: FontSettings,
+    pub text: TextSettings,
+    pub hotkeys: HotkeySettings,
+    pub hooks: HookSettings,
+    pub filter: FilterSettings,
+    pub watch: WatchSettings,
+    pub history: HistorySettings,
+    pub dump: DumpSettings,
+    pub replay: ReplaySettings,
+    pub pipe: PipeSettings,
+    pub shared_memory: SharedMemorySettings,
+    pub world_text: WorldTextSettings,
+    pub world_shapes: WorldShapeSettings,
+    pub game_flags: GameFlagSettings,
+    pub auto_hide: AutoHideSettings,
+    pub layers: LayerSettings,
+    pub categories: CategorySettings,
+    /// Names the source panel groups text from these RVA ranges under. Default empty.
+    pub caller_labels: Vec<CallerLabel>,
+    /// Colors for text matching a pattern, the first matching rule is used. Default empty.
+    pub highlights: Vec<HighlightRule>,
+    /// Patterns that flash the screen edge or play a sound when text matches them. Default
+    /// empty.
+    pub alerts: Vec<AlertRule>,
+}
+
+/// The module the game's code is in, for wrappers that start the game from another executable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TargetSettings {
+    /// File name of the module, e.g. `eldenring.exe`. It's waited for when it isn't loaded yet.
+    /// Default not set, the main executable.
+    pub module: Option<String>,
+    /// PE header timestamp the module has to have to be hooked. Default not set, any.
+    pub timestamp: Option<u32>,
+    /// Size of the loaded image the module has to have to be hooked. Default not set, any.
+    pub image_size: Option<u32>,
+}
+
+/// RVAs of the hooked functions for game versions the overlay has no table for, the world ones
+/// are set in `world_text` and `world_shapes`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RvaSettings {
+    /// Game version the RVAs are for, as the log reports it. On any other version they're
+    /// ignored, so they don't point a game update at the wrong code. Default not set, any.
+    pub version: Option<String>,
+    /// Default not set for every hook, its signature or the version's table is used.
+    pub draw_text_render_request: Option<u32>,
+    pub set_font_size: Option<u32>,
+    pub set_text_scale: Option<u32>,
+    pub reset_text_scale: Option<u32>,
+    pub draw_text_with_size: Option<u32>,
+    pub set_color: Option<u32>,
+    pub draw_line: Option<u32>,
+    pub draw_rect: Option<u32>,
+    pub fill_rect: Option<u32>,
+}
+
+impl RvaSettings {
+    pub fn get(&self, hook: HookId) -> Option<u32> {
+        match hook {
+            HookId::DrawTextRenderRequest => self.draw_text_render_request,
+            HookId::SetFontSize => self.set_font_size,
+            HookId::SetTextScale => self.set_text_scale,
+            HookId::ResetTextScale => self.reset_text_scale,
+            HookId::DrawTextWithSize => self.draw_text_with_size,
+            HookId::SetColor => self.set_color,
+            HookId::DrawLine => self.draw_line,
+            HookId::DrawRect => self.draw_rect,
+            HookId::FillRect => self.fill_rect,
+            HookId::DrawWorldText
+            | HookId::DrawWorldLine
+            | HookId::DrawWorldSphere
+            | HookId::DrawWorldCapsule => None,
+        }
+    }
+}
+
+/// The draw list each kind of overlay content goes into, `foreground` or `background`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayerSettings {
+    /// Text at the positions the game drew it at, with its plates and arrows. Default
+    /// `foreground`.
+    pub text: Layer,
+    /// The source and watch panels. Default `foreground`.
+    pub panels: Layer,
+    /// Lines, rectangles and world shapes. Default `foreground`.
+    pub shapes: Layer,
+}
+
+impl Default for LayerSettings {
+    fn default() -> Self {
+        Self {
+            text: Layer::Foreground,
+            panels: Layer::Foreground,
+            shapes: Layer::Foreground,
+        }
+    }
+}
+
+/// Patterns whose latest matching text is pinned to the watch panel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchSettings {
+    /// Regular expressions matched against captured text. A pattern with a `value` group, e.g.
+    /// `fps: (?P<value>[0-9.]+)`, also plots the numbers it captures. Default empty.
+    pub patterns: Vec<String>,
+    /// Values plotted per watch. Default 120.
+    pub history: usize,
+    /// A watch without a match for this long is dimmed. Default 5000.
+    pub stale_after_ms: u64,
+}
+
+impl Default for WatchSettings {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            history: 120,
+            stale_after_ms: 5000,
+        }
+    }
+}
+
+/// The window listing recently captured text, opened with `hotkeys.history`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistorySettings {
+    /// Entries kept, text drawn at the same position in consecutive frames is one entry. 0
+    /// doesn't keep any. Default 10000.
+    pub capacity: usize,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self { capacity: 10000 }
+    }
+}
+
+/// Writing every captured text to a file, toggled with `hotkeys.dump`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DumpSettings {
+    /// Start writing when the game starts. Default `false`.
+    pub enabled: bool,
+    /// File the text is appended to, a relative path is next to the DLL. Default
+    /// `textrender-dump.tsv`.
+    pub path: PathBuf,
+    /// Size after which the file is moved to `<path>.1` and a new one is started. Default 64.
+    pub max_size_mb: u64,
+    /// `text` writes the text commands tab separated, `jsonl` writes every command as JSON.
+    /// Default `text`.
+    pub format: DumpFormat,
+}
+
+impl Default for DumpSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: PathBuf::from("textrender-dump.tsv"),
+            max_size_mb: 64,
+            format: DumpFormat::Text,
+        }
+    }
+}
+
+/// Replaying a JSON Lines dump instead of capturing from the game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplaySettings {
+    /// Dump to replay, a relative path is next to the DLL. The capture hooks aren't installed
+    /// while it's set. Default not set.
+    pub path: Option<PathBuf>,
+    /// Multiplier applied to the recorded timing. Default 1.0.
+    pub speed: f32,
+    /// Start over at the end of the recording. Default `true`.
+    pub repeat: bool,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            speed: 1.0,
+            repeat: true,
+        }
+    }
+}
+
+/// Text the game draws at a position in the world, projected to the screen with its camera.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldTextSettings {
+    /// RVA of the function drawing text at a world position. It hasn't been found in any build,
+    /// so it's only hooked when this is set. Default not set.
+    pub rva: Option<u32>,
+    /// Distance from the camera at which text is drawn at its font size. Default 10.0.
+    pub reference_distance: f32,
+    /// How fast text shrinks with distance, its size is multiplied by
+    /// `(reference_distance / distance) ^ falloff`. 0 keeps the size. Default 1.0.
+    pub falloff: f32,
+    /// Bounds of that multiplier. Default 0.25 and 2.0.
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Text further away than this isn't drawn, 0 draws it at any distance. Default 200.0.
+    pub max_distance: f32,
+    /// Text further away than this fades out until it reaches `max_distance`. Default 100.0.
+    pub fade_distance: f32,
+    /// Share of the way to its new position a label stays behind each frame, which evens out
+    /// the jitter from the game's tick. 0 moves it right away. Default 0.5.
+    pub smoothing: f32,
+    /// A label that moved further than this many pixels is moved right away. Default 100.0.
+    pub snap_distance: f32,
+}
+
+impl Default for WorldTextSettings {
+    fn default() -> Self {
+        Self {
+            rva: None,
+            reference_distance: 10.0,
+            falloff: 1.0,
+            min_scale: 0.25,
+            max_scale: 2.0,
+            max_distance: 200.0,
+            fade_distance: 100.0,
+            smoothing: 0.5,
+            snap_distance: 100.0,
+        }
+    }
+}
+
+/// Lines, spheres and capsules the game draws in the world, projected like world text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldShapeSettings {
+    /// Draw any of them, they're still captured while it's off. Default `true`.
+    pub enabled: bool,
+    /// Which kinds are drawn. Default `true` for each.
+    pub lines: bool,
+    pub spheres: bool,
+    pub capsules: bool,
+    /// Segments spheres and the ends of capsules are drawn with. Default 24.
+    pub circle_segments: u32,
+    /// RVAs of the functions drawing them, for builds their signatures don't match and that have
+    /// no table. Default not set.
+    pub line_rva: Option<u32>,
+    pub sphere_rva: Option<u32>,
+    pub capsule_rva: Option<u32>,
+}
+
+impl Default for WorldShapeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lines: true,
+            spheres: true,
+            capsules: true,
+            circle_segments: 24,
+            line_rva: None,
+            sphere_rva: None,
+            capsule_rva: None,
+        }
+    }
+}
+
+/// The game's own switches for its debug drawing, which the overlay can follow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameFlagSettings {
+    /// `always` draws everything captured, `follow` only what `flags` allow. Default `always`.
+    pub mode: FlagMode,
+    /// Byte flags read every frame in `follow` mode. Default empty.
+    pub flags: Vec<GameFlag>,
+}
+
+impl Default for GameFlagSettings {
+    fn default() -> Self {
+        Self {
+            mode: FlagMode::Always,
+            flags: Vec::new(),
+        }
+    }
+}
+
+/// Game states the overlay hides itself in. Commands are still captured while it's hidden.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoHideSettings {
+    /// Hide while a cutscene plays, needs `cutscene_flag`. Default `true`.
+    pub cutscenes: bool,
+    /// Hide on the title screen and while loading into the game. Default `true`.
+    pub main_menu: bool,
+    /// Hide while the map is open, needs `map_flag`. Default `false`.
+    pub map: bool,
+    /// Keep writing the dump while hidden. Default `true`.
+    pub dump_while_hidden: bool,
+    /// Byte that isn't 0 while a cutscene plays, its `applies_to` isn't used. Default not set.
+    pub cutscene_flag: Option<GameFlag>,
+    /// Byte that isn't 0 while the map is open. Default not set.
+    pub map_flag: Option<GameFlag>,
+}
+
+impl Default for AutoHideSettings {
+    fn default() -> Self {
+        Self {
+            cutscenes: true,
+            main_menu: true,
+            map: false,
+            dump_while_hidden: true,
+            cutscene_flag: None,
+            map_flag: None,
+        }
+    }
+}
+
+/// A byte the game checks before drawing, commands it covers are drawn while it isn't 0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameFlag {
+    /// Shown in the diagnostics panel.
+    pub name: String,
+    /// Singleton the flag is a field of, e.g. `WorldChrManDbg`. Either this or `rva` is set.
+    #[serde(default)]
+    pub singleton: Option<String>,
+    /// RVA of a static the flag is in.
+    #[serde(default)]
+    pub rva: Option<u32>,
+    /// Offset of the flag from the singleton instance or the RVA. Default 0.
+    #[serde(default)]
+    pub offset: u32,
+    /// What the flag covers, any of `text`, `shapes`, `world_text` and `world_shapes`. Default
+    /// empty, which covers everything.
+    #[serde(default)]
+    pub applies_to: Vec<DrawCategory>,
+}
+
+/// Streaming captured commands to other programs over a named pipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipeSettings {
+    /// Accept clients on `\\.\pipe\<name>`. Default `false`.
+    pub enabled: bool,
+    /// Default `textrender`.
+    pub name: String,
+    /// Stream every command instead of only the text ones. Default `false`.
+    pub all_commands: bool,
+}
+
+impl Default for PipeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: "textrender".to_string(),
+            all_commands: false,
+        }
+    }
+}
+
+/// Mirroring the text on screen into shared memory, for OBS text sources and other overlays.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SharedMemorySettings {
+    /// Default `false`.
+    pub enabled: bool,
+    /// Name of the file mapping. Default `textrender_shared`.
+    pub name: String,
+    /// Size of the mapping, text past it is cut off. Default 64.
+    pub size_kb: usize,
+    /// The snapshot is updated at most this often. Default 100.
+    pub update_interval_ms: u64,
+}
+
+impl Default for SharedMemorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: "textrender_shared".to_string(),
+            size_kb: 64,
+            update_interval_ms: 100,
+        }
+    }
+}
+
+/// Color for captured text matching a pattern.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighlightRule {
+    /// Regular expression matched against the text.
+    pub pattern: String,
+    /// RGBA color the text is drawn in. Default red.
+    pub color: [f32; 4],
+    /// Draw the text twice, one pixel apart, so it looks bold. Default `false`.
+    pub emphasis: bool,
+}
+
+impl Default for HighlightRule {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            color: [1.0, 0.3, 0.3, 1.0],
+            emphasis: false,
+        }
+    }
+}
+
+/// Named groups of text told apart by how it starts, each one shown, colored and layered on
+/// its own. Everything is still captured, only what's drawn on the screen changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CategorySettings {
+    /// Checked in order, text goes into the first one whose pattern matches its start. Default
+    /// empty.
+    pub rules: Vec<TextCategory>,
+    /// Text no rule matches, its `pattern` isn't used. Default named `other`.
+    pub other: TextCategory,
+}
+
+impl Default for CategorySettings {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            other: TextCategory {
+                name: "other".to_string(),
+                ..TextCategory::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextCategory {
+    /// Shown in the settings window. Default empty.
+    pub name: String,
+    /// Regular expression matched at the start of the text, e.g. `\[AI\]`. Default empty,
+    /// which matches nothing.
+    pub pattern: String,
+    /// Whether its text is drawn. Default `true`.
+    pub shown: bool,
+    /// RGBA color its text is drawn in instead of its own, highlights still apply over it.
+    /// Default not set.
+    pub color: Option<[f32; 4]>,
+    /// Layer its text is drawn on, `layers.text` when not set. Default not set.
+    pub layer: Option<Layer>,
+}
+
+impl Default for TextCategory {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            pattern: String::new(),
+            shown: true,
+            color: None,
+            layer: None,
+        }
+    }
+}
+
+/// Flashing the screen edge or playing a sound when captured text matches a pattern.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertRule {
+    /// Regular expression matched against the text.
+    pub pattern: String,
+    /// Flash a border around the screen. Default `true`.
+    pub flash: bool,
+    /// RGBA color of the border. Default red.
+    pub color: [f32; 4],
+    /// Play a sound. Default `false`.
+    pub sound: bool,
+    /// Wav file to play, a relative path is next to the DLL. The system's exclamation sound when
+    /// not set. Default not set.
+    pub sound_path: Option<PathBuf>,
+    /// The rule doesn't fire again for this long. Default 2000.
+    pub cooldown_ms: u64,
+}
+
+impl Default for AlertRule {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            flash: true,
+            color: [1.0, 0.2, 0.2, 1.0],
+            sound: false,
+            sound_path: None,
+            cooldown_ms: 2000,
+        }
+    }
+}
+
+/// Name for the game code in an RVA range, shown in the source panel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CallerLabel {
+    pub name: String,
+    /// Inclusive `[first, last]` RVA range.
+    pub range: [u32; 2],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontSettings {
+    /// TTF/TTC file to render text with. Default MS Gothic, the embedded DejaVu Sans if neither
+    /// can be read.
+    pub path: Option<PathBuf>,
+    /// Face of the font file used when it's a TrueType collection, e.g. 2 for MS PGothic in
+    /// `msgothic.ttc`. Default 0.
+    pub face_index: u32,
+    /// Font files merged into the font for glyphs it lacks, tried in order. Missing files are
+    /// skipped. Default Segoe UI Symbol.
+    pub fallback_paths: Vec<PathBuf>,
+    /// Font size of text whose `CSEzDraw` instance hasn't set one. Default 24.
+    pub size: f32,
+    /// Multiplier applied to every font size. Default 1.
+    pub scale: f32,
+    /// Pixel sizes the font is rendered at when it's loaded, text uses the nearest one and is
+    /// scaled from there, up by at most 20%. More sizes keep more text sharp but take more
+    /// memory. Default `[13, 18, 24, 32, 48]`.
+    pub baked_sizes: Vec<f32>,
+    /// Character sets the font is loaded with, any of `default`, `japanese`, `chinese_full`,
+    /// `chinese_simplified_common`, `korean`, `cyrillic`, `thai` or `vietnamese`. Large sets like
+    /// `chinese_full` take a lot of atlas memory. Default `["default", "japanese"]`.
+    pub glyph_ranges: Vec<GlyphRange>,
+    /// Extra inclusive `[first, last]` codepoint ranges loaded on top of `glyph_ranges`. Default
+    /// empty.
+    pub custom_glyph_ranges: Vec<[u32; 2]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextSettings {
+    /// RGBA color of the text, before highlights and categories. Default white.
+    pub color: [f32; 4],
+    /// One of `none`, `shadow`, `outline4` or `outline8`. Default `none`.
+    pub outline: TextOutline,
+    /// RGBA color of the outline. Default black.
+    pub outline_color: [f32; 4],
+    /// Draw a plate behind each text so it stays readable over bright scenes. Default `false`.
+    pub background: bool,
+    /// RGBA color of the plate. Default black at half opacity.
+    pub background_color: [f32; 4],
+    /// Corner radius of the plate in pixels. Default 4.0.
+    pub background_rounding: f32,
+    /// Fade text in when it appears and out when the game stops drawing it, instead of showing
+    /// and hiding it at once. Default `false`.
+    pub fade: bool,
+    /// Default 150.
+    pub fade_in_ms: u64,
+    /// Starts when the game draws a frame without the text, or `retain_timeout_ms` after it
+    /// stopped drawing any. Default 250.
+    pub fade_out_ms: u64,
+    /// Draw text that changed since the previous frame in `diff_changed_color` and text drawn
+    /// where there was none in `diff_new_color`, for `diff_frames` frames. Text is compared by
+    /// its position and caller. Default `false`.
+    pub diff: bool,
+    /// Default 30.
+    pub diff_frames: u64,
+    /// Default yellow.
+    pub diff_changed_color: [f32; 4],
+    /// Default green.
+    pub diff_new_color: [f32; 4],
+    /// Point of the text placed at the position the game draws it at, one of `top-left`,
+    /// `center` or `bottom-left`. Default `bottom-left`.
+    pub anchor: TextAnchor,
+    /// What happens to text and shapes positioned outside the screen, one of `wrap` (they come
+    /// back from the opposite edge), `clamp` (pinned to the nearest edge) or `cull` (not drawn).
+    /// Default `wrap`.
+    pub offscreen: OffscreenMode,
+    /// Draw an arrow towards the real position of clamped text. Default `true`.
+    pub offscreen_indicator: bool,
+    /// What happens to text and shapes while the game's window isn't focused: `show`, `dim` (drawn
+    /// at `unfocused_alpha`) or `hide`. Default `show`.
+    pub unfocused: UnfocusedMode,
+    /// Opacity `dim` draws at, from 0 to 1. Default 0.3.
+    pub unfocused_alpha: f32,
+    /// Color space colors are converted to: `auto` (from the display the game is on), `off`,
+    /// `hdr10` or `scrgb`. Default `auto`.
+    pub hdr: HdrMode,
+    /// Luminance white is drawn at on an HDR output, in nits. Default 200.
+    pub hdr_white_nits: f32,
+    /// Ratio of the output resolution to the resolution the game renders at internally, e.g. 1.5
+    /// with an upscaler at 67%. Measured from the swapchain when not set. Default not set.
+    pub render_scale: Option<f32>,
+    /// Text scale set by the game is corrected for the ratio of the screen to the window it
+    /// renders in. `clamp` keeps that ratio within `aspect_min..=aspect_max`, `exact` uses it as
+    /// is. Default `clamp`.
+    pub aspect_mode: AspectMode,
+    /// Default 0.8.
+    pub aspect_min: f32,
+    /// Default `inf`.
+    pub aspect_max: f32,
+    /// How long text stays on screen after the game stops drawing it. Default 500.
+    pub retain_timeout_ms: u64,
+    /// Text drawn where the game positions it per frame, the rest is summed up in a single line.
+    /// Filtered out text doesn't count. 0 draws all of it. Default 500.
+    pub max_texts_per_frame: usize,
+    /// Text drawn within this many pixels of earlier text in the same frame is moved below it,
+    /// 0 draws it on top instead. Default 1.
+    pub stack_tolerance_px: f32,
+    /// Wrap text at the right edge of the screen instead of letting it run off. Default `true`.
+    pub wrap: bool,
+    /// Spaces a tab in captured text is replaced with. Default 4.
+    pub tab_width: usize,
+    /// Show the RVA of the game code that drew each text in front of it and in the log. Default
+    /// `false`.
+    pub show_callers: bool,
+    /// Show the frame each text was captured in and the seconds since the game started in front
+    /// of it. Default `false`.
+    pub show_stamps: bool,
+    /// Where captured text is shown, one of `positional` (where the game draws it), `panel` (in a
+    /// window grouped by the code that drew it) or `both`. Default `positional`.
+    pub presentation: Presentation,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HotkeySettings {
+    /// Shows or hides the overlay. Default `F10`.
+    pub toggle: String,
+    /// Opens the settings window. Default `F11`.
+    pub settings: String,
+    /// Shows or hides the diagnostics panel. Default `F9`.
+    pub diagnostics: String,
+    /// Opens or closes the console window. Default `F8`.
+    pub console: String,
+    /// Starts or stops writing captured text to the dump file. Default `F7`.
+    pub dump: String,
+    /// Copies the text on screen to the clipboard. Default `F5`.
+    pub copy: String,
+    /// Opens the window listing recently captured text. Default `F2`.
+    pub history: String,
+    /// Opens the window showing how much text each source draws. Default `F1`.
+    pub stats: String,
+    /// Keeps the text on screen as it is, ignoring new text until it's pressed again. Default
+    /// `F3`.
+    pub freeze: String,
+    /// Captures the mouse to show the details of the text under it and copy it by clicking.
+    /// Default `F4`.
+    pub inspect: String,
+    /// Pauses or resumes a replay. Default `Pause`.
+    pub replay_pause: String,
+    /// Plays the next recorded frame of a paused replay. Default `F6`.
+    pub replay_step: String,
+}
+
+/// Which of the installed hooks capture calls.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HookSettings {
+    /// Whether a disabled hook lets the game run its own function. Otherwise it stays installed
+    /// and swallows the calls, which keeps the game from drawing that text itself. Default
+    /// `true`.
+    pub native_when_disabled: bool,
+    /// Default `true` for every hook.
+    pub draw_text_render_request: bool,
+    pub set_font_size: bool,
+    pub set_text_scale: bool,
+    pub reset_text_scale: bool,
+    pub draw_text_with_size: bool,
+    pub set_color: bool,
+    pub draw_line: bool,
+    pub draw_rect: bool,
+    pub fill_rect: bool,
+    pub draw_world_text: bool,
+    pub draw_world_line: bool,
+    pub draw_world_sphere: bool,
+    pub draw_world_capsule: bool,
+}
+
+impl HookSettings {
+    pub fn enabled(&self, hook: HookId) -> bool {
+        match hook {
+            HookId::DrawTextRenderRequest => self.draw_text_render_request,
+            HookId::SetFontSize => self.set_font_size,
+            HookId::SetTextScale => self.set_text_scale,
+            HookId::ResetTextScale => self.reset_text_scale,
+            HookId::DrawTextWithSize => self.draw_text_with_size,
+            HookId::SetColor => self.set_color,
+            HookId::DrawLine => self.draw_line,
+            HookId::DrawRect => self.draw_rect,
+            HookId::FillRect => self.fill_rect,
+            HookId::DrawWorldText => self.draw_world_text,
+            HookId::DrawWorldLine => self.draw_world_line,
+            HookId::DrawWorldSphere => self.draw_world_sphere,
+            HookId::DrawWorldCapsule => self.draw_world_capsule,
+        }
+    }
+
+    pub fn enabled_mut(&mut self, hook: HookId) -> &mut bool {
+        match hook {
+            HookId::DrawTextRenderRequest => &mut self.draw_text_render_request,
+            HookId::SetFontSize => &mut self.set_font_size,
+            HookId::SetTextScale => &mut self.set_text_scale,
+            HookId::ResetTextScale => &mut self.reset_text_scale,
+            HookId::DrawTextWithSize => &mut self.draw_text_with_size,
+            HookId::SetColor => &mut self.set_color,
+            HookId::DrawLine => &mut self.draw_line,
+            HookId::DrawRect => &mut self.draw_rect,
+            HookId::FillRect => &mut self.fill_rect,
+            HookId::DrawWorldText => &mut self.draw_world_text,
+            HookId::DrawWorldLine => &mut self.draw_world_line,
+            HookId::DrawWorldSphere => &mut self.draw_world_sphere,
+            HookId::DrawWorldCapsule => &mut self.draw_world_capsule,
+        }
+    }
+}
+
+/// Regular expressions captured text is matched against before it's queued.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterSettings {
+    /// Only text matching one of these is shown, all text when empty. Default empty.
+    pub include: Vec<String>,
+    /// Text matching one of these is never shown, even if it matches an include pattern.
+    /// Default empty.
+    pub exclude: Vec<String>,
+    /// Inclusive `[first, last]` RVA ranges of game code whose text is shown, like `include`.
+    /// Default empty.
+    pub include_callers: Vec<[u32; 2]>,
+    /// Inclusive `[first, last]` RVA ranges of game code whose text is never shown. Default
+    /// empty.
+    pub exclude_callers: Vec<[u32; 2]>,
+    /// Text hidden from the context menu of the inspector or the history window, each one
+    /// `{ text = "..." }`, `{ prefix = "..." }` or `{ caller = 0x... }`. Never shown, like
+    /// `exclude`. Default empty.
+    pub blocked: Vec<BlockRule>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lazy: false,
+            log_level: "debug".to_string(),
+            log_directives: Vec::new(),
+            log_captured_text: false,
+            log_max_size_mb: 10,
+            log_max_files: 5,
+            minidump: false,
+            queue_capacity: 10000,
+            duplicate_window_ms: 0,
+            max_command_age_ms: 250,
+            max_text_length: 4096,
+            console: false,
+            diagnostics: false,
+            graphics_api: GraphicsApi::Auto,
+            init_timeout_s: 300,
+            on_init_timeout: InitTimeout::Dump,
+            ignore_overlay_conflicts: false,
+            passthrough_native: false,
+            target: TargetSettings::default(),
+            rvas: RvaSettings::default(),
+            font: FontSettings::default(),
+            text: TextSettings::default(),
+            hotkeys: HotkeySettings::default(),
+            hooks: HookSettings::default(),
+            filter: FilterSettings::default(),
+            watch: WatchSettings::default(),
+            history: HistorySettings::default(),
+            dump: DumpSettings::default(),
+            replay: ReplaySettings::default(),
+            pipe: PipeSettings::default(),
+            shared_memory: SharedMemorySettings::default(),
+            world_text: WorldTextSettings::default(),
+            world_shapes: WorldShapeSettings::default(),
+            game_flags: GameFlagSettings::default(),
+            auto_hide: AutoHideSettings::default(),
+            layers: LayerSettings::default(),
+            categories: CategorySettings::default(),
+            caller_labels: Vec::new(),
+            highlights: Vec::new(),
+            alerts: Vec::new(),
+        }
+    }
+}
+
+impl Default for FontSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            face_index: 0,
+            fallback_paths: vec![PathBuf::from("C:\\Windows\\Fonts\\seguisym.ttf")],
+            size: 24.0,
+            scale: 1.0,
+            baked_sizes: DEFAULT_BAKED_SIZES_PX.to_vec(),
+            glyph_ranges: vec![GlyphRange::Default, GlyphRange::Japanese],
+            custom_glyph_ranges: Vec::new(),
+        }
+    }
+}
+
+impl Default for TextSettings {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0, 1.0],
+            outline: TextOutline::None,
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+            background: false,
+            background_color: [0.0, 0.0, 0.0, 0.5],
+            background_rounding: 4.0,
+            fade: false,
+            fade_in_ms: 150,
+            fade_out_ms: 250,
+            diff: false,
+            diff_frames: 30,
+            diff_changed_color: [1.0, 0.85, 0.2, 1.0],
+            diff_new_color: [0.4, 1.0, 0.4, 1.0],
+            anchor: TextAnchor::BottomLeft,
+            offscreen: OffscreenMode::Wrap,
+            offscreen_indicator: true,
+            unfocused: UnfocusedMode::Show,
+            unfocused_alpha: 0.3,
+            hdr: HdrMode::Auto,
+            hdr_white_nits: 200.0,
+            render_scale: None,
+            aspect_mode: AspectMode::Clamp,
+            aspect_min: 0.8,
+            aspect_max: f32::INFINITY,
+            retain_timeout_ms: 500,
+            max_texts_per_frame: 500,
+            stack_tolerance_px: DEFAULT_STACK_TOLERANCE_PX,
+            wrap: true,
+            tab_width: 4,
+            show_callers: false,
+            show_stamps: false,
+            presentation: Presentation::Positional,
+        }
+    }
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            toggle: "F10".to_string(),
+            settings: "F11".to_string(),
+            diagnostics: "F9".to_string(),
+            console: "F8".to_string(),
+            dump: "F7".to_string(),
+            copy: "F5".to_string(),
+            inspect: "F4".to_string(),
+            freeze: "F3".to_string(),
+            history: "F2".to_string(),
+            stats: "F1".to_string(),
+            replay_pause: "Pause".to_string(),
+            replay_step: "F6".to_string(),
+        }
+    }
+}
+
+impl Default for HookSettings {
+    fn default() -> Self {
+        Self {
+            native_when_disabled: true,
+            draw_text_render_request: true,
+            set_font_size: true,
+            set_text_scale: true,
+            reset_text_scale: true,
+            draw_text_with_size: true,
+            set_color: true,
+            draw_line: true,
+            draw_rect: true,
+            fill_rect: true,
+            draw_world_text: true,
+            draw_world_line: true,
+            draw_world_sphere: true,
+            draw_world_capsule: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(io::Error),
+    Parse(toml::de::Error),
+    InvalidValue { key: &'static str, value: String },
+    Serialize(toml::ser::Error),
+    Write(io::Error),
+    Edit(toml_edit::TomlError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(e) => write!(f, "failed to read config: {e}"),
+            ConfigError::Parse(e) => write!(f, "failed to parse config: {e}"),
+            ConfigError::InvalidValue { key, value } => {
+                write!(f, "invalid value {value:?} for {key}")
+            }
+            ConfigError::Serialize(e) => write!(f, "failed to serialize config: {e}"),
+            ConfigError::Write(e) => write!(f, "failed to write config: {e}"),
+            ConfigError::Edit(e) => write!(f, "failed to parse config for editing: {e}"),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config at `path`, the defaults are used when the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(ConfigError::Read(e)),
+        };
+
+        let config: Self = toml::from_str(&contents).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Writes the settings the settings window edits to the file at `path`.
+    ///
+    /// Only the ones that differ from the file are written, its comments and the other keys stay
+    /// as they are.
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(ConfigError::Read(e)),
+        };
+        let mut document = contents
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(ConfigError::Edit)?;
+
+        // Both serialized the same way, so equal settings compare equal however the file
+        // writes them
+        let saved = toml::from_str::<Config>(&contents).unwrap_or_default();
+        let new = serialized(self)?;
+        let old = serialized(&saved)?;
+        for key in SETTINGS_WINDOW_KEYS {
+            let path: Vec<_> = key.split('.').collect();
+            update_key(
+                document.as_table_mut(),
+                &path,
+                lookup(&new, &path),
+                lookup(&old, &path),
+            );
+        }
+        std::fs::write(path, document.to_string()).map_err(ConfigError::Write)
+    }
+
+    /// Checks the values serde can't check by itself.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |key, value: &str| ConfigError::InvalidValue {
+            key,
+            value: value.to_string(),
+        };
+
+        self.log_level
+            .parse::<LevelFilter>()
+            .map_err(|_| invalid("log_level", &self.log_level))?;
+        if let Some(directive) = self
+            .log_directives
+            .iter()
+            .find(|directive| directive.parse::<Directive>().is_err())
+        {
+            return Err(invalid("log_directives", directive));
+        }
+        if self.queue_capacity == 0 {
+            return Err(invalid("queue_capacity", "0"));
+        }
+        if self.pipe.name.is_empty() || self.pipe.name.contains('\\') {
+            return Err(invalid("pipe.name", &self.pipe.name));
+        }
+        if self.replay.speed.is_nan() || self.replay.speed <= 0.0 {
+            return Err(invalid("replay.speed", &self.replay.speed.to_string()));
+        }
+        let world_text = &self.world_text;
+        if world_text.reference_distance.is_nan() || world_text.reference_distance <= 0.0 {
+            return Err(invalid(
+                "world_text.reference_distance",
+                &world_text.reference_distance.to_string(),
+            ));
+        }
+        if world_text.min_scale.is_nan() || world_text.min_scale > world_text.max_scale {
+            return Err(invalid(
+                "world_text.min_scale",
+                &world_text.min_scale.to_string(),
+            ));
+        }
+        if let Some(scale) = self.text.render_scale
+            && (scale.is_nan() || scale <= 0.0)
+        {
+            return Err(invalid("text.render_scale", &scale.to_string()));
+        }
+        for (key, ranges) in [
+            ("filter.include_callers", &self.filter.include_callers),
+            ("filter.exclude_callers", &self.filter.exclude_callers),
+        ] {
+            if let Some(range) = ranges.iter().find(|[first, last]| first > last) {
+                return Err(invalid(key, &format!("{range:?}")));
+            }
+        }
+        if let Some(label) = self
+            .caller_labels
+            .iter()
+            .find(|label| label.range[0] > label.range[1])
+        {
+            return Err(invalid("caller_labels", &format!("{label:?}")));
+        }
+        let auto_hide_flags = [
+            ("auto_hide.cutscene_flag", &self.auto_hide.cutscene_flag),
+            ("auto_hide.map_flag", &self.auto_hide.map_flag),
+        ];
+        if let Some((key, flag)) = self
+            .game_flags
+            .flags
+            .iter()
+            .map(|flag| ("game_flags.flags", flag))
+            .chain(
+                auto_hide_flags
+                    .into_iter()
+                    .filter_map(|(key, flag)| Some((key, flag.as_ref()?))),
+            )
+            .find(|(_, flag)| flag.singleton.is_some() == flag.rva.is_some())
+        {
+            return Err(invalid(key, &flag.name));
+        }
+        // An empty pattern would match every text
+        if self.alerts.iter().any(|alert| alert.pattern.is_empty()) {
+            return Err(invalid("alerts.pattern", ""));
+        }
+        if let Some(range) = self
+            .font
+            .custom_glyph_ranges
+            .iter()
+            .find(|range| !is_valid_range(**range))
+        {
+            return Err(invalid("font.custom_glyph_ranges", &format!("{range:?}")));
+        }
+        if !(0.0..=1.0).contains(&self.text.unfocused_alpha) {
+            return Err(invalid(
+                "text.unfocused_alpha",
+                &self.text.unfocused_alpha.to_string(),
+            ));
+        }
+        if !(1.0..=10000.0).contains(&self.text.hdr_white_nits) {
+            return Err(invalid(
+                "text.hdr_white_nits",
+                &self.text.hdr_white_nits.to_string(),
+            ));
+        }
+        if self.text.aspect_min.is_nan() || self.text.aspect_min <= 0.0 {
+            return Err(invalid(
+                "text.aspect_min",
+                &self.text.aspect_min.to_string(),
+            ));
+        }
+        if self.text.aspect_max.is_nan() || self.text.aspect_max < self.text.aspect_min {
+            return Err(invalid(
+                "text.aspect_max",
+                &self.text.aspect_max.to_string(),
+            ));
+        }
+        parse_key(&self.hotkeys.toggle)
+            .ok_or_else(|| invalid("hotkeys.toggle", &self.hotkeys.toggle))?;
+        parse_key(&self.hotkeys.settings)
+            .ok_or_else(|| invalid("hotkeys.settings", &self.hotkeys.settings))?;
+        parse_key(&self.hotkeys.diagnostics)
+            .ok_or_else(|| invalid("hotkeys.diagnostics", &self.hotkeys.diagnostics))?;
+        parse_key(&self.hotkeys.console)
+            .ok_or_else(|| invalid("hotkeys.console", &self.hotkeys.console))?;
+        parse_key(&self.hotkeys.dump).ok_or_else(|| invalid("hotkeys.dump", &self.hotkeys.dump))?;
+        parse_key(&self.hotkeys.copy).ok_or_else(|| invalid("hotkeys.copy", &self.hotkeys.copy))?;
+        parse_key(&self.hotkeys.stats)
+            .ok_or_else(|| invalid("hotkeys.stats", &self.hotkeys.stats))?;
+        parse_key(&self.hotkeys.history)
+            .ok_or_else(|| invalid("hotkeys.history", &self.hotkeys.history))?;
+        parse_key(&self.hotkeys.freeze)
+            .ok_or_else(|| invalid("hotkeys.freeze", &self.hotkeys.freeze))?;
+        parse_key(&self.hotkeys.inspect)
+            .ok_or_else(|| invalid("hotkeys.inspect", &self.hotkeys.inspect))?;
+        parse_key(&self.hotkeys.replay_pause)
+            .ok_or_else(|| invalid("hotkeys.replay_pause", &self.hotkeys.replay_pause))?;
+        parse_key(&self.hotkeys.replay_step)
+            .ok_or_else(|| invalid("hotkeys.replay_step", &self.hotkeys.replay_step))?;
+        Ok(())
+    }
+
+    pub fn log_level(&self) -> LevelFilter {
+        self.log_level.parse().unwrap_or(LevelFilter::DEBUG)
+    }
+
+    /// `enabled`, unless `ENABLED_ENV_VAR` overrides it.
+    pub fn is_enabled(&self) -> bool {
+        match std::env::var(ENABLED_ENV_VAR).as_deref().map(str::trim) {
+            Ok("0" | "false") => false,
+            Ok("1" | "true") => true,
+            _ => self.enabled,
+        }
+    }
+
+    /// Whether anything uses the caller of captured text, looking it up walks the stack.
+    pub fn needs_callers(&self) -> bool {
+        self.text.show_callers
+            || self.text.presentation.is_panel()
+            || !self.filter.include_callers.is_empty()
+            || !self.filter.exclude_callers.is_empty()
+            || self
+                .filter
+                .blocked
+                .iter()
+                .any(|rule| matches!(rule, BlockRule::Caller(_)))
+    }
+}
+
+/// Replaces `filter.blocked` in the config at `path`, leaving the rest of the file as it is.
+pub fn save_blocked(path: &Path, blocked: &[BlockRule]) -> Result<(), ConfigError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(ConfigError::Read(e)),
+    };
+    let mut document = contents
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(ConfigError::Edit)?;
+
+    let mut rules: toml_edit::Array = blocked.iter().map(BlockRule::to_toml).collect();
+    for rule in rules.iter_mut() {
+        rule.decor_mut().set_prefix("\n    ");
+    }
+    rules.set_trailing_comma(!blocked.is_empty());
+    rules.set_trailing(if blocked.is_empty() { "" } else { "\n" });
+    document["filter"]["blocked"] = toml_edit::value(rules);
+    std::fs::write(path, document.to_string()).map_err(ConfigError::Write)
+}
+
+/// Keys `Config::save` writes, the settings the settings window edits. A table is written key by
+/// key.
+const SETTINGS_WINDOW_KEYS: [&str; 43] = [
+    "auto_hide",
+    "categories",
+    "duplicate_window_ms",
+    "filter.exclude",
+    "filter.include",
+    "font.scale",
+    "font.size",
+    "game_flags.mode",
+    "highlights",
+    "hooks",
+    "layers",
+    "log_captured_text",
+    "log_directives",
+    "log_level",
+    "max_command_age_ms",
+    "text.anchor",
+    "text.aspect_max",
+    "text.aspect_min",
+    "text.aspect_mode",
+    "text.background",
+    "text.background_color",
+    "text.background_rounding",
+    "text.color",
+    "text.diff",
+    "text.fade",
+    "text.hdr",
+    "text.hdr_white_nits",
+    "text.max_texts_per_frame",
+    "text.offscreen",
+    "text.offscreen_indicator",
+    "text.outline",
+    "text.outline_color",
+    "text.presentation",
+    "text.render_scale",
+    "text.retain_timeout_ms",
+    "text.show_callers",
+    "text.show_stamps",
+    "text.stack_tolerance_px",
+    "text.tab_width",
+    "text.unfocused",
+    "text.unfocused_alpha",
+    "text.wrap",
+    "watch.patterns",
+];
+
+fn serialized(config: &Config) -> Result<toml_edit::DocumentMut, ConfigError> {
+    let contents = toml::to_string(config).map_err(ConfigError::Serialize)?;
+    contents.parse().map_err(ConfigError::Edit)
+}
+
+fn lookup<'a>(document: &'a toml_edit::DocumentMut, path: &[&str]) -> Option<&'a toml_edit::Item> {
+    path.iter()
+        .try_fold(document.as_item(), |item, key| item.get(key))
+}
+
+/// Sets the key at `path` in `table` to `new` if it isn't `old`, removing it for `None`. Values
+/// keep the comment after them, tables are updated key by key.
+fn update_key(
+    table: &mut dyn toml_edit::TableLike,
+    path: &[&str],
+    new: Option<&toml_edit::Item>,
+    old: Option<&toml_edit::Item>,
+) {
+    if new.map(ToString::to_string) == old.map(ToString::to_string) {
+        return;
+    }
+    let Some((key, rest)) = path.split_first() else {
+        return;
+    };
+    let tables = new.is_some_and(toml_edit::Item::is_table_like)
+        && old.is_some_and(toml_edit::Item::is_table_like);
+    let written_as_table = table.get(key).is_none_or(toml_edit::Item::is_table_like);
+    if (rest.is_empty() && !tables) || !written_as_table {
+        let Some(new) = new else {
+            table.remove(key);
+            return;
+        };
+        match (table.get_mut(key), new) {
+            (Some(toml_edit::Item::Value(existing)), toml_edit::Item::Value(value)) => {
+                let decor = existing.decor().clone();
+                *existing = value.clone();
+                *existing.decor_mut() = decor;
+            }
+            (Some(existing), _) => *existing = new.clone(),
+            (None, _) => {
+                table.insert(key, new.clone());
+            }
+        }
+        return;
+    }
+
+    let Some(child) = table
+        .entry(key)
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+    else {
+        return;
+    };
+    if !rest.is_empty() {
+        update_key(child, rest, new, old);
+        return;
+    }
+    let keys: BTreeSet<&str> = [new, old]
+        .into_iter()
+        .flatten()
+        .filter_map(toml_edit::Item::as_table_like)
+        .flat_map(|table| table.iter().map(|(key, _)| key))
+        .collect();
+    for key in keys {
+        update_key(
+            child,
+            &[key],
+            new.and_then(|new| new.get(key)),
+            old.and_then(|old| old.get(key)),
+        );
+    }
+}
+
+/// Polls the modification time of the config file to pick up edits while the game runs.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    current: Config,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf, current: Config) -> Self {
+        let modified = modified_time(&path);
+        Self {
+            path,
+            modified,
+            current,
+        }
+    }
+
+    /// Returns the new config when the file changed since the last poll and still parses.
+    ///
+    /// Invalid edits are logged and ignored, the last good config stays in effect.
+    pub fn poll(&mut self) -> Option<Config> {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        let config = match Config::load(&self.path) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!(
+                    "{}: {}, keeping the last good config",
+                    self.path.display(),
+                    e
+                );
+                notify(
+                    Severity::Error,
+                    format!(
+                        "{}: {}\nThe last good settings are kept.",
+                        self.path.display(),
+                        e
+                    ),
+                );
+                return None;
+            }
+        };
+        if config == self.current {
+            return None;
+        }
+
+        if config.log_max_size_mb != self.current.log_max_size_mb
+            || config.log_max_files != self.current.log_max_files
+            || config.minidump != self.current.minidump
+            || config.queue_capacity != self.current.queue_capacity
+            || config.graphics_api != self.current.graphics_api
+            || config.init_timeout_s != self.current.init_timeout_s
+            || config.on_init_timeout != self.current.on_init_timeout
+            || config.replay != self.current.replay
+            || config.target != self.current.target
+            || config.world_text.rva != self.current.world_text.rva
+            || config.world_shapes.line_rva != self.current.world_shapes.line_rva
+            || config.world_shapes.sphere_rva != self.current.world_shapes.sphere_rva
+            || config.world_shapes.capsule_rva != self.current.world_shapes.capsule_rva
+            || config.rvas != self.current.rvas
+            || config.pipe.enabled != self.current.pipe.enabled
+            || config.pipe.name != self.current.pipe.name
+            || config.shared_memory.enabled != self.current.shared_memory.enabled
+            || config.shared_memory.name != self.current.shared_memory.name
+            || config.shared_memory.size_kb != self.current.shared_memory.size_kb
+            || config.font.path != self.current.font.path
+            || config.font.face_index != self.current.font.face_index
+            || config.font.fallback_paths != self.current.font.fallback_paths
+            || config.font.baked_sizes != self.current.font.baked_sizes
+            || config.font.glyph_ranges != self.current.font.glyph_ranges
+            || config.font.custom_glyph_ranges != self.current.font.custom_glyph_ranges
+        {
+            tracing::warn!(
+                "log_max_size_mb, log_max_files, minidump, queue_capacity, graphics_api, \
+                 init_timeout_s, on_init_timeout, the replay, target and rvas settings, the RVAs \
+                 in world_text and world_shapes, pipe.enabled, pipe.name, the shared_memory \
+                 settings other than update_interval_ms and the font settings other than size \
+                 and scale only take effect after a restart"
+            );
+            notify(
+                Severity::Warning,
+                "Some of the changed settings only take effect after a restart, see the log",
+            );
+        }
+        tracing::info!("Reloaded {}", self.path.display());
+        self.current = config.clone();
+        Some(config)
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Directory of the DLL `hinst` belongs to, the config and log files are kept there.
+pub fn dll_directory(hinst: HINSTANCE) -> Option<PathBuf> {
+    let mut buffer = vec![0u16; 1024];
+    loop {
+        let len = unsafe { GetModuleFileNameW(HMODULE::from(hinst), &mut buffer) } as usize;
+        if len == 0 {
+            return None;
+        }
+        // The path is truncated when it fills the whole buffer
+        if len < buffer.len() {
+            buffer.truncate(len);
+            break;
+        }
+        buffer.resize(buffer.len() * 2, 0);
+    }
+
+    let dll_path = PathBuf::from(OsString::from_wide(&buffer));
+    Some(dll_path.parent()?.to_path_buf())
+}
@@ -0,0 +1,65 @@
+use std::ffi::OsString;
+use std::os::windows::ffi::OsStringExt;
+
+use hudhook::windows::Win32::{
+    Foundation::{CloseHandle, HINSTANCE},
+    System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, MODULEENTRY32W, Module32FirstW, Module32NextW, TH32CS_SNAPMODULE,
+    },
+};
+use pelite::pe64::{Pe, PeView};
+
+/// Marker every hudhook build carries in its read-only data, from the source paths in its panic
+/// locations and log targets.
+const HUDHOOK_MARKER: &[u8] = b"hudhook";
+
+/// Names of the loaded DLLs other than this one that are built on hudhook.
+///
+/// Two of them hooking `Present` fight over the swapchain and usually crash the game. System
+/// DLLs and the game itself are skipped, they can't contain it.
+pub fn find_hudhook_overlays(own_module: HINSTANCE) -> Vec<String> {
+    let Ok(snapshot) = (unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPMODULE, 0) }) else {
+        return Vec::new();
+    };
+
+    let mut overlays = Vec::new();
+    let mut entry = MODULEENTRY32W {
+        dwSize: size_of::<MODULEENTRY32W>() as u32,
+        ..Default::default()
+    };
+    let mut next = unsafe { Module32FirstW(snapshot, &mut entry) };
+    // The first module is the game's executable
+    let mut first = true;
+    while next.is_ok() {
+        let path = wide_string(&entry.szExePath).to_lowercase();
+        let skipped = std::mem::take(&mut first)
+            || entry.hModule.0 == own_module.0
+            || path.contains("\\windows\\");
+        if !skipped && unsafe { contains_hudhook(entry.modBaseAddr) } {
+            overlays.push(wide_string(&entry.szModule));
+        }
+        next = unsafe { Module32NextW(snapshot, &mut entry) };
+    }
+    unsafe { CloseHandle(snapshot) }.ok();
+    overlays
+}
+
+/// Whether the read-only data of the module loaded at `base` mentions hudhook.
+unsafe fn contains_hudhook(base: *const u8) -> bool {
+    let view = unsafe { PeView::module(base) };
+    let Some(section) = view.section_headers().by_name(".rdata") else {
+        return false;
+    };
+    view.get_section_bytes(section).is_ok_and(|bytes| {
+        bytes
+            .windows(HUDHOOK_MARKER.len())
+            .any(|window| window == HUDHOOK_MARKER)
+    })
+}
+
+fn wide_string(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    OsString::from_wide(&buffer[..len])
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,72 @@
+use std::sync::{
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+use hudhook::windows::Win32::{
+    Foundation::FALSE,
+    System::Console::{
+        ATTACH_PARENT_PROCESS, AllocConsole, AttachConsole, FreeConsole, GetConsoleWindow,
+        SetConsoleCtrlHandler, SetConsoleTitleW,
+    },
+    UI::WindowsAndMessaging::{DeleteMenu, GetSystemMenu, MF_BYCOMMAND, SC_CLOSE},
+};
+use hudhook::windows::core::w;
+
+/// Whether the process currently has a console the log is written to, read for every log line.
+static CONSOLE_OPEN: AtomicBool = AtomicBool::new(false);
+/// Held while the console is opened or closed, so two threads can't do it at once.
+static CONSOLE_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn is_console_open() -> bool {
+    CONSOLE_OPEN.load(Ordering::Relaxed)
+}
+
+/// Opens a console window the log is shown in, or attaches to the one the game was started from.
+///
+/// Standard output and error follow the console, the standard library looks up their handles on
+/// every write.
+pub fn open_console() {
+    let _lock = CONSOLE_LOCK.lock().unwrap();
+    if is_console_open() {
+        return;
+    }
+
+    if unsafe { AllocConsole() }.is_ok() {
+        unsafe {
+            SetConsoleTitleW(w!("textrender log")).ok();
+            // Closing a console window terminates the process it belongs to, closing it goes
+            // through the overlay instead
+            DeleteMenu(
+                GetSystemMenu(GetConsoleWindow(), FALSE),
+                SC_CLOSE,
+                MF_BYCOMMAND,
+            )
+            .ok();
+        }
+    } else if let Err(e) = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) } {
+        tracing::error!("Failed to open a console: {}", e);
+        return;
+    }
+
+    // Ctrl+C in the console would otherwise terminate the game
+    unsafe { SetConsoleCtrlHandler(None, true) }.ok();
+    CONSOLE_OPEN.store(true, Ordering::Relaxed);
+}
+
+/// Detaches from the console, which closes it if it was opened by `open_console`.
+pub fn close_console() {
+    let _lock = CONSOLE_LOCK.lock().unwrap();
+    if is_console_open() {
+        CONSOLE_OPEN.store(false, Ordering::Relaxed);
+        unsafe { FreeConsole() }.ok();
+    }
+}
+
+pub fn toggle_console() {
+    if is_console_open() {
+        close_console();
+    } else {
+        open_console();
+    }
+}
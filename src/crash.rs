@@ -0,0 +1,162 @@
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, TryLockError},
+};
+
+use crate::QueuedCommand;
+use crate::address::{HookId, game_version};
+use crate::config::Config;
+use crate::hooks::{install_error, is_hook_poisoned, resolved_address};
+use crate::logging::local_timestamp;
+use crate::target::target_program;
+
+/// Log lines kept for a crash report.
+const MAX_LOG_LINES: usize = 200;
+/// Drained commands kept for a crash report.
+const MAX_COMMANDS: usize = 100;
+
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static RECENT_COMMANDS: Mutex<VecDeque<QueuedCommand>> = Mutex::new(VecDeque::new());
+/// The config that was loaded last, serialized when it was.
+static LOADED_CONFIG: Mutex<String> = Mutex::new(String::new());
+
+/// The lock if it can be taken without waiting, a panic while it's held would otherwise wait on
+/// itself.
+fn try_lock<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Writer for the log layer that keeps the last lines for the crash report, made for every
+/// event and stored when it's dropped.
+#[derive(Default)]
+pub struct RecentLogLine(Vec<u8>);
+
+impl Write for RecentLogLine {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RecentLogLine {
+    fn drop(&mut self) {
+        let line = String::from_utf8_lossy(&self.0).trim_end().to_string();
+        let Some(mut lines) = try_lock(&RECENT_LOG) else {
+            return;
+        };
+        if lines.len() >= MAX_LOG_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+}
+
+/// Keeps the last commands of a frame about to be drained.
+pub fn record_commands(frame: &[QueuedCommand]) {
+    let Some(mut commands) = try_lock(&RECENT_COMMANDS) else {
+        return;
+    };
+    let start = frame.len().saturating_sub(MAX_COMMANDS);
+    commands.extend(frame[start..].iter().cloned());
+    let excess = commands.len().saturating_sub(MAX_COMMANDS);
+    commands.drain(..excess);
+}
+
+/// Remembers the config for the crash report, called whenever one is loaded.
+pub fn set_loaded_config(config: &Config) {
+    let serialized = toml::to_string(config).unwrap_or_else(|_| format!("{config:#?}"));
+    if let Some(mut loaded) = try_lock(&LOADED_CONFIG) {
+        *loaded = serialized;
+    }
+}
+
+/// Writes `textrender-crash-<date>_<time>.txt` to `dir` with what's known about the panic and
+/// the game, returning its path.
+pub fn write_crash_report(dir: &Path, panic: &str, backtrace: &str) -> io::Result<PathBuf> {
+    let mut report = format!(
+        "textrender {} crash report\n\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        panic
+    );
+
+    let version = game_version(&target_program());
+    writeln!(
+        report,
+        "\nGame version: {}",
+        version.as_deref().unwrap_or("unknown")
+    )
+    .ok();
+    report.push_str("\nHooks:\n");
+    for hook in HookId::ALL {
+        let resolved = match resolved_address(hook) {
+            Some(resolved) => format!("{:#x} ({})", resolved.va, resolved.source),
+            None => "not resolved".to_string(),
+        };
+        let resolved = if is_hook_poisoned(hook) {
+            format!("{resolved}, poisoned")
+        } else {
+            resolved
+        };
+        match install_error(hook) {
+            Some(e) => writeln!(
+                report,
+                "  {}: {}, not installed: {}",
+                hook.name(),
+                resolved,
+                e
+            ),
+            None => writeln!(report, "  {}: {}", hook.name(), resolved),
+        }
+        .ok();
+    }
+
+    writeln!(report, "\nBacktrace:\n{backtrace}").ok();
+
+    report.push_str("\nConfig:\n");
+    match try_lock(&LOADED_CONFIG) {
+        Some(config) => report.push_str(&config),
+        None => report.push_str("unavailable\n"),
+    }
+
+    match try_lock(&RECENT_LOG) {
+        Some(lines) => {
+            writeln!(report, "\nLast {} log lines:", lines.len()).ok();
+            for line in lines.iter() {
+                writeln!(report, "{line}").ok();
+            }
+        }
+        None => report.push_str("\nThe log lines were unavailable\n"),
+    }
+
+    match try_lock(&RECENT_COMMANDS) {
+        Some(commands) => {
+            writeln!(report, "\nLast {} drained commands:", commands.len()).ok();
+            for queued in commands.iter() {
+                writeln!(
+                    report,
+                    "#{} {:.3}s {:?}",
+                    queued.frame,
+                    queued.time_us as f64 / 1_000_000.0,
+                    queued.command
+                )
+                .ok();
+            }
+        }
+        None => report.push_str("\nThe drained commands were unavailable\n"),
+    }
+
+    let path = dir.join(format!("textrender-crash-{}.txt", local_timestamp()));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
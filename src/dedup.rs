@@ -0,0 +1,76 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::text::TextId;
+
+/// Entries kept before the ones outside the window are pruned.
+const MAX_ENTRIES: usize = 4096;
+
+/// Window in milliseconds, 0 disables suppression.
+static WINDOW_MS: AtomicU64 = AtomicU64::new(0);
+static SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+
+/// When each text was last let through, by its position rounded to whole pixels so jitter
+/// doesn't let copies through.
+static LAST_SEEN: LazyLock<Mutex<HashMap<TextId, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn set_window(window: Duration) {
+    WINDOW_MS.store(window.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Returns `true` if the same text was let through at the same position within the window.
+///
+/// The first occurrence always gets through, different text or a different position is a
+/// different key.
+pub fn is_duplicate(text: &str, x: f32, y: f32) -> bool {
+    let window = Duration::from_millis(WINDOW_MS.load(Ordering::Relaxed));
+    if window.is_zero() {
+        return false;
+    }
+
+    let key = TextId::of_command(text, x, y);
+
+    let now = Instant::now();
+    let mut last_seen = LAST_SEEN.lock().unwrap();
+    if let Some(seen) = last_seen.get(&key)
+        && now.duration_since(*seen) < window
+    {
+        SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+        return true;
+    }
+
+    if last_seen.len() >= MAX_ENTRIES {
+        last_seen.retain(|_, seen| now.duration_since(*seen) < window);
+    }
+    last_seen.insert(key, now);
+    false
+}
+
+/// Whether copies of the text with this key are being suppressed, the last one let through is
+/// at most twice the window old.
+///
+/// The text is still drawn by the game then, the render loop keeps it on screen. The slack
+/// covers the copy that was let through after the window but isn't drawn yet.
+pub fn is_suppressing(key: TextId) -> bool {
+    let window = Duration::from_millis(WINDOW_MS.load(Ordering::Relaxed));
+    if window.is_zero() {
+        return false;
+    }
+
+    let last_seen = LAST_SEEN.lock().unwrap();
+    last_seen
+        .get(&key)
+        .is_some_and(|seen| seen.elapsed() < window * 2)
+}
+
+/// Commands suppressed since the game started.
+pub fn suppressed_count() -> u64 {
+    SUPPRESSED.load(Ordering::Relaxed)
+}
@@ -0,0 +1,95 @@
+use hudhook::imgui::{Condition, Ui};
+
+use crate::address::HookId;
+use crate::backend::hooked_backend;
+use crate::flags::FlagMode;
+use crate::hooks::{
+    install_error, is_capturing, is_hook_enabled, is_hook_poisoned, reentrant_calls,
+    resolved_address,
+};
+use crate::{DebugTextRender, TEXT_RENDER_QUEUE, dedup, dropped_count, intern};
+
+impl DebugTextRender {
+    /// Draws the diagnostics panel, it only reads counters so it can stay open.
+    pub(crate) fn draw_diagnostics(&self, ui: &Ui) {
+        ui.window("Debug Text Diagnostics")
+            .position([10.0, 10.0], Condition::FirstUseEver)
+            .always_auto_resize(true)
+            .no_inputs()
+            .bg_alpha(0.6)
+            .build(|| {
+                ui.text(format!(
+                    "Renderer: {}",
+                    hooked_backend().map_or("not hooked", |api| api.name())
+                ));
+                ui.separator();
+                for hook in HookId::ALL {
+                    let state = match (is_hook_enabled(hook), is_capturing(hook)) {
+                        _ if is_hook_poisoned(hook) => "poisoned",
+                        (true, true) => "enabled",
+                        (true, false) => "suppressed",
+                        (false, _) => "disabled",
+                    };
+                    match resolved_address(hook) {
+                        Some(resolved) if let Some(error) = install_error(hook) => ui.text_colored(
+                            [1.0, 0.4, 0.4, 1.0],
+                            format!("{}: failed at {:#x}: {}", hook.name(), resolved.va, error),
+                        ),
+                        Some(resolved) => ui.text(format!(
+                            "{}: {} at {:#x} ({})",
+                            hook.name(),
+                            state,
+                            resolved.va,
+                            resolved.source
+                        )),
+                        None => ui.text(format!("{}: not resolved", hook.name())),
+                    }
+                    let reentrant = reentrant_calls(hook);
+                    if reentrant > 0 {
+                        ui.same_line();
+                        ui.text_disabled(format!("({reentrant} nested calls passed through)"));
+                    }
+                }
+
+                ui.separator();
+                ui.text(format!("Drained last frame: {}", self.drained_last_frame));
+                ui.text(format!(
+                    "Queue: {} / {}",
+                    TEXT_RENDER_QUEUE.len(),
+                    TEXT_RENDER_QUEUE.capacity()
+                ));
+                ui.text(format!("Dropped: {}", dropped_count()));
+                ui.text(format!("Expired: {}", self.expired_commands));
+                ui.text(format!("Frames over the text cap: {}", self.cap_hits));
+                ui.text(format!(
+                    "Suppressed duplicates: {}",
+                    dedup::suppressed_count()
+                ));
+                let (shared, decoded) = intern::pool_stats();
+                ui.text(format!("Strings shared: {shared}, decoded: {decoded}"));
+                ui.text(format!("Text on screen: {}", self.retained.len()));
+                ui.text(format!("Shapes on screen: {}", self.retained_shapes.len()));
+
+                ui.text(format!(
+                    "Auto-hide: {}",
+                    self.hidden_in.map_or("shown", |state| state.name())
+                ));
+
+                ui.separator();
+                ui.text(format!("Game flags: {}", self.game_flag_mode.name()));
+                if self.game_flag_mode == FlagMode::Follow {
+                    let flags = &self.config.game_flags.flags;
+                    if flags.is_empty() {
+                        ui.text_disabled("No flags configured, everything is drawn");
+                    }
+                    for (flag, value) in flags.iter().zip(self.game_flags.values()) {
+                        match value {
+                            Some(value) => ui.text(format!("{}: {}", flag.name, value)),
+                            None => ui.text(format!("{}: not readable", flag.name)),
+                        }
+                    }
+                    ui.text(format!("Not allowed by the flags: {}", self.gated_commands));
+                }
+            });
+    }
+}
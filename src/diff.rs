@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use crate::processor::TextEntry;
+use crate::text::TextId;
+
+/// Where a text is drawn from: the exact bits of its position and the game code that drew it.
+type SlotKey = ([u32; 2], Option<u32>);
+
+/// How a text differs from what was drawn in its place before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// Nothing was drawn in its place.
+    New,
+    /// Different text was drawn in its place.
+    Changed,
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+    content: TextId,
+    /// The last change and the frame it happened in.
+    change: Option<(Change, u64)>,
+}
+
+/// Compares the text of each frame to the one before, by where it's drawn from.
+pub struct TextDiff {
+    previous: HashMap<SlotKey, Slot>,
+    /// Filled while a frame is compared, swapped with `previous` once it's done. Both are kept
+    /// so they don't have to be reallocated every frame.
+    current: HashMap<SlotKey, Slot>,
+}
+
+impl TextDiff {
+    pub fn new() -> Self {
+        Self {
+            previous: HashMap::new(),
+            current: HashMap::new(),
+        }
+    }
+
+    /// Compares `entry` to the text drawn in its place in the previous frame, `None` if it's
+    /// unchanged or changed more than `highlight_frames` frames ago.
+    pub fn observe(&mut self, entry: &TextEntry, highlight_frames: u64) -> Option<Change> {
+        let key = (entry.position.map(f32::to_bits), entry.caller);
+        let content = entry.id();
+        let change = match self.previous.get(&key) {
+            None => Some((Change::New, entry.frame)),
+            Some(slot) if slot.content != content => Some((Change::Changed, entry.frame)),
+            Some(slot) => slot
+                .change
+                .filter(|(_, frame)| entry.frame.saturating_sub(*frame) < highlight_frames),
+        };
+        self.current.insert(key, Slot { content, change });
+        change.map(|(change, _)| change)
+    }
+
+    /// Makes the text observed since the last call the one the next frame is compared to.
+    pub fn finish_frame(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+}
@@ -0,0 +1,411 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::Ordering,
+        mpsc::{Receiver, SyncSender, TrySendError, sync_channel},
+    },
+    time::{Duration, Instant},
+};
+
+use hudhook::imgui::Ui;
+use serde::{Deserialize, Serialize};
+
+use crate::command::DrawCommand;
+use crate::config::Config;
+use crate::{DebugTextRender, FRAME_COUNTER, QueuedCommand, TEXT_RENDER_QUEUE};
+
+/// Lines waiting for the writer thread before new ones are dropped.
+const DUMP_QUEUE_CAPACITY: usize = 65536;
+/// How often the capture queue is drained while there's no render loop to do it.
+const UNRENDERED_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Layout of the lines in the dump file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpFormat {
+    /// Tab separated, one line per text command: `seconds since the dump started, frame,
+    /// microseconds since the DLL was loaded, x, y, font size, caller RVA, text`, with `-` for an
+    /// unknown caller. Tabs, newlines and backslashes in the text are escaped.
+    Text,
+    /// JSON Lines, one `DumpRecord` per command of any kind.
+    Jsonl,
+}
+
+/// A line of the JSON Lines dump, e.g.
+/// `{"time":1.5,"frame":90,"time_us":61500000,"type":"set_font_size","fields":[140737488355328,24.0]}`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpRecord<C> {
+    /// Seconds since the dump started.
+    pub time: f64,
+    /// Frame the command was captured in.
+    pub frame: u64,
+    /// Microseconds since the DLL was loaded when the command was captured, missing from dumps
+    /// written before it was recorded.
+    #[serde(default)]
+    pub time_us: u64,
+    #[serde(flatten)]
+    pub command: C,
+}
+
+/// Captured text appended to a file by a background thread.
+pub struct TextDump {
+    format: DumpFormat,
+    sender: SyncSender<String>,
+    started: Instant,
+    /// Lines dropped because the writer thread fell behind.
+    dropped: u64,
+}
+
+impl TextDump {
+    /// Starts writing to `path`, appending to it. When the file grows past `max_bytes` it's
+    /// renamed with a `.1` suffix, replacing the previous one, and a new file is started.
+    pub fn start(path: PathBuf, format: DumpFormat, max_bytes: u64) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        let (sender, receiver) = sync_channel(DUMP_QUEUE_CAPACITY);
+        std::thread::Builder::new()
+            .name("textrender dump".to_string())
+            .spawn(move || write_lines(receiver, path, file, written, max_bytes))?;
+        Ok(Self {
+            format,
+            sender,
+            started: Instant::now(),
+            dropped: 0,
+        })
+    }
+
+    pub fn format(&self) -> DumpFormat {
+        self.format
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Queues the line of a text command in the text format, never waits on the writer.
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_text(
+        &mut self,
+        frame: u64,
+        time_us: u64,
+        position: [f32; 2],
+        font_size: f32,
+        caller: Option<u32>,
+        text: &str,
+    ) {
+        let caller = caller.map_or("-".to_string(), |rva| format!("{rva:#x}"));
+        let line = format!(
+            "{:.6}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            self.started.elapsed().as_secs_f64(),
+            frame,
+            time_us,
+            position[0],
+            position[1],
+            font_size,
+            caller,
+            escape(text)
+        );
+        self.send(line);
+    }
+
+    /// Queues the line of a command in the JSON Lines format, never waits on the writer.
+    pub fn write_command(&mut self, queued: &QueuedCommand) {
+        let record = DumpRecord {
+            time: self.started.elapsed().as_secs_f64(),
+            frame: queued.frame,
+            time_us: queued.time_us,
+            command: &queued.command,
+        };
+        match serde_json::to_string(&record) {
+            Ok(mut line) => {
+                line.push('\n');
+                self.send(line);
+            }
+            // Commands only hold numbers and strings, this doesn't fail in practice
+            Err(_) => self.dropped += 1,
+        }
+    }
+
+    fn send(&mut self, line: String) {
+        match self.sender.try_send(line) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => self.dropped += 1,
+            // The writer gave up on an IO error, which it logged
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Writes lines until the `TextDump` is dropped, rotating the file by size.
+fn write_lines(
+    receiver: Receiver<String>,
+    path: PathBuf,
+    file: File,
+    mut written: u64,
+    max_bytes: u64,
+) {
+    let mut writer = BufWriter::new(file);
+    let result = (|| -> std::io::Result<()> {
+        while let Ok(first) = receiver.recv() {
+            for line in std::iter::once(first).chain(receiver.try_iter()) {
+                if written > 0 && written + line.len() as u64 > max_bytes {
+                    writer.flush()?;
+                    writer = BufWriter::new(rotate(&path)?);
+                    written = 0;
+                }
+                writer.write_all(line.as_bytes())?;
+                written += line.len() as u64;
+            }
+            // Flushed whenever the queue runs dry, so the file is current while the game runs
+            writer.flush()?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        tracing::error!("Failed to write the text dump {}: {}", path.display(), e);
+    }
+}
+
+/// Moves the full dump aside and opens a new one at `path`.
+fn rotate(path: &Path) -> std::io::Result<File> {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    std::fs::rename(path, rotated)?;
+    File::create(path)
+}
+
+/// Path the dump is written to, a relative one is next to the DLL.
+fn dump_path(config: &Config, config_path: Option<&Path>) -> PathBuf {
+    let path = &config.dump.path;
+    match config_path.and_then(Path::parent) {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.clone(),
+    }
+}
+
+fn dump_max_bytes(config: &Config) -> u64 {
+    config.dump.max_size_mb.max(1) * 1024 * 1024
+}
+
+/// Writes what the hooks capture to the dump from a thread of its own, for when the renderer
+/// isn't hooked and no render loop drains the queue. Returns the path written to.
+pub fn dump_without_renderer(
+    config: &Config,
+    config_path: Option<&Path>,
+) -> std::io::Result<PathBuf> {
+    let path = dump_path(config, config_path);
+    let mut dump = TextDump::start(path.clone(), config.dump.format, dump_max_bytes(config))?;
+    let default_font_size = config.font.size;
+    std::thread::Builder::new()
+        .name("textrender unrendered dump".to_string())
+        .spawn(move || {
+            // Only the font size is tracked, the text format doesn't include the text scale
+            let mut font_sizes = HashMap::new();
+            loop {
+                // Each pass stands in for a frame, so the dump still groups the commands
+                FRAME_COUNTER.fetch_add(1, Ordering::Relaxed);
+                let pending = TEXT_RENDER_QUEUE.len();
+                for queued in std::iter::from_fn(|| TEXT_RENDER_QUEUE.pop()).take(pending) {
+                    if dump.format() == DumpFormat::Jsonl {
+                        dump.write_command(&queued);
+                        continue;
+                    }
+                    match &queued.command {
+                        DrawCommand::SetFontSize(ez_draw, size) => {
+                            font_sizes.insert(*ez_draw, *size);
+                        }
+                        DrawCommand::Text {
+                            ez_draw,
+                            text,
+                            caller,
+                            x,
+                            y,
+                            font_size,
+                            ..
+                        } => {
+                            let font_size = font_size
+                                .or_else(|| font_sizes.get(ez_draw).copied())
+                                .unwrap_or(default_font_size);
+                            dump.write_text(
+                                queued.frame,
+                                queued.time_us,
+                                [*x, *y],
+                                font_size,
+                                *caller,
+                                text,
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                std::thread::sleep(UNRENDERED_POLL_INTERVAL);
+            }
+        })?;
+    Ok(path)
+}
+
+impl DebugTextRender {
+    pub(crate) fn start_dump(&mut self) {
+        let path = dump_path(&self.config, self.config_path.as_deref());
+        match TextDump::start(
+            path.clone(),
+            self.config.dump.format,
+            dump_max_bytes(&self.config),
+        ) {
+            Ok(dump) => {
+                tracing::info!("Writing captured text to {}", path.display());
+                self.dump = Some(dump);
+            }
+            Err(e) => tracing::error!("Failed to open {}: {}", path.display(), e),
+        }
+    }
+
+    pub(crate) fn stop_dump(&mut self) {
+        if let Some(dump) = self.dump.take() {
+            tracing::info!(
+                "Stopped writing captured text after {:.1}s, {} lines dropped",
+                dump.elapsed().as_secs_f32(),
+                dump.dropped()
+            );
+        }
+    }
+
+    pub(crate) fn toggle_dump(&mut self) {
+        if self.dump.is_some() {
+            self.stop_dump();
+        } else {
+            self.start_dump();
+        }
+    }
+
+    /// Draws a recording indicator in the top left corner while the dump is written.
+    pub(crate) fn draw_dump_indicator(&self, ui: &Ui) {
+        let Some(dump) = &self.dump else {
+            return;
+        };
+        let label = format!("REC {:.0}s", dump.elapsed().as_secs_f32());
+        let draw_list = ui.get_foreground_draw_list();
+        let radius = ui.current_font_size() * 0.35;
+        let center = [10.0 + radius, 10.0 + ui.current_font_size() * 0.5];
+        draw_list
+            .add_circle(center, radius, self.color_space().rgb(230, 40, 40))
+            .filled(true)
+            .build();
+        draw_list.add_text(
+            [center[0] + radius * 2.0, 10.0],
+            self.color_space().rgb(230, 40, 40),
+            label,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands() -> Vec<DrawCommand> {
+        vec![
+            DrawCommand::Text {
+                ez_draw: 0x7ff0_0000_1000,
+                text: "HP\t100\n\"quoted\" \\ テキスト".into(),
+                caller: Some(0x264_1234),
+                x: 12.5,
+                y: -3.25,
+                font_size: Some(18.0),
+                box_width: None,
+                world_z: None,
+            },
+            DrawCommand::Text {
+                ez_draw: 0x7ff0_0000_1000,
+                text: "world".into(),
+                caller: None,
+                x: 1.0,
+                y: 2.0,
+                font_size: None,
+                box_width: Some(300.0),
+                world_z: Some(-4.5),
+            },
+            DrawCommand::SetFontSize(0x7ff0_0000_1000, 24.0),
+            DrawCommand::SetTextScale(0x7ff0_0000_1000, 0.5, 0.75, 20.0),
+            DrawCommand::ResetTextScale(0x7ff0_0000_1000),
+            DrawCommand::SetColor(0x7ff0_0000_2000, [0.0, 1.0, 0.0, 0.5]),
+            DrawCommand::Rect {
+                ez_draw: 0x7ff0_0000_2000,
+                min: [10.0, 20.0],
+                max: [30.0, 40.0],
+                filled: true,
+            },
+            DrawCommand::WorldCapsule {
+                ez_draw: 0x7ff0_0000_2000,
+                from: [1.0, 2.0, 3.0],
+                to: [4.0, 5.0, 6.0],
+                radius: 0.5,
+            },
+        ]
+    }
+
+    #[test]
+    fn jsonl_dump_round_trips() {
+        let path =
+            std::env::temp_dir().join(format!("textrender-roundtrip-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let commands = commands();
+        let mut dump = TextDump::start(path.clone(), DumpFormat::Jsonl, u64::MAX).unwrap();
+        for (sequence, command) in commands.iter().enumerate() {
+            dump.write_command(&QueuedCommand {
+                frame: 7 + sequence as u64 / 4,
+                time_us: 1000 * sequence as u64,
+                sequence: sequence as u64,
+                command: command.clone(),
+            });
+        }
+        drop(dump);
+
+        // The writer thread flushes once it ran out of lines
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let contents = loop {
+            let contents = std::fs::read_to_string(&path).unwrap_or_default();
+            if contents.lines().count() == commands.len() || Instant::now() > deadline {
+                break contents;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        std::fs::remove_file(&path).ok();
+
+        let records = contents
+            .lines()
+            .map(|line| serde_json::from_str::<DumpRecord<DrawCommand>>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(records.len(), commands.len());
+        for (sequence, (record, command)) in records.iter().zip(&commands).enumerate() {
+            assert_eq!(&record.command, command);
+            assert_eq!(record.frame, 7 + sequence as u64 / 4);
+            assert_eq!(record.time_us, 1000 * sequence as u64);
+        }
+    }
+
+    #[test]
+    fn records_without_time_us_still_parse() {
+        let record: DumpRecord<DrawCommand> = serde_json::from_str(
+            r#"{"time":1.5,"frame":90,"type":"set_font_size","fields":[4096,24.0]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(record.time_us, 0);
+        assert_eq!(record.command, DrawCommand::SetFontSize(4096, 24.0));
+    }
+}
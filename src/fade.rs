@@ -0,0 +1,72 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use crate::processor::TextEntry;
+use crate::text::TextId;
+
+/// When the text on screen appeared, and the text that left it still fading out.
+pub struct TextFades {
+    appeared: HashMap<TextId, Instant>,
+    fading_out: Vec<(TextEntry, Instant)>,
+}
+
+impl TextFades {
+    pub fn new() -> Self {
+        Self {
+            appeared: HashMap::new(),
+            fading_out: Vec::new(),
+        }
+    }
+
+    /// Records that `shown` replaced `previous` on screen. Text in both keeps the time it
+    /// appeared, text only in `previous` starts fading out.
+    pub fn replace(&mut self, previous: impl IntoIterator<Item = TextEntry>, shown: &[TextEntry]) {
+        let now = Instant::now();
+        let ids = shown.iter().map(TextEntry::id).collect::<HashSet<_>>();
+        self.appeared = ids
+            .iter()
+            .map(|id| (*id, self.appeared.get(id).copied().unwrap_or(now)))
+            .collect();
+        // Text that comes back is drawn normally again
+        self.fading_out
+            .retain(|(entry, _)| !ids.contains(&entry.id()));
+        self.fading_out.extend(
+            previous
+                .into_iter()
+                .filter(|entry| !ids.contains(&entry.id()))
+                .map(|entry| (entry, now)),
+        );
+    }
+
+    /// Opacity of text on screen, rising from 0 to 1 over `fade_in` after it appeared.
+    pub fn fade_in_alpha(&self, entry: &TextEntry, fade_in: Duration) -> f32 {
+        let Some(appeared) = self.appeared.get(&entry.id()) else {
+            return 1.0;
+        };
+        progress(appeared.elapsed(), fade_in)
+    }
+
+    /// Drops the text that finished fading out.
+    pub fn prune(&mut self, fade_out: Duration) {
+        self.fading_out
+            .retain(|(_, left)| left.elapsed() < fade_out);
+    }
+
+    /// Text that left the screen with its opacity, falling from 1 to 0 over `fade_out`.
+    pub fn fading_out(&self, fade_out: Duration) -> impl Iterator<Item = (&TextEntry, f32)> {
+        self.fading_out
+            .iter()
+            .map(move |(entry, left)| (entry, 1.0 - progress(left.elapsed(), fade_out)))
+    }
+}
+
+/// How far `elapsed` is into `duration`, from 0 to 1. A zero duration is already over.
+fn progress(elapsed: Duration, duration: Duration) -> f32 {
+    if duration.is_zero() {
+        1.0
+    } else {
+        (elapsed.as_secs_f32() / duration.as_secs_f32()).min(1.0)
+    }
+}
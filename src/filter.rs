@@ -0,0 +1,158 @@
+use std::{
+    collections::HashSet,
+    sync::{LazyLock, Mutex, RwLock},
+};
+
+use regex::Regex;
+
+use crate::blocklist::BlockRule;
+use crate::caller::in_ranges;
+use crate::config::FilterSettings;
+
+/// Filter applied to captured text before it's queued, shared by every detour.
+static TEXT_FILTER: LazyLock<RwLock<TextFilter>> =
+    LazyLock::new(|| RwLock::new(TextFilter::default()));
+
+/// Patterns that failed to compile and were logged already.
+static REPORTED_PATTERNS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Default)]
+struct TextFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    include_callers: Vec<[u32; 2]>,
+    exclude_callers: Vec<[u32; 2]>,
+    blocked: Vec<BlockRule>,
+}
+
+impl TextFilter {
+    fn new(settings: &FilterSettings) -> Self {
+        Self {
+            include: compile(&settings.include),
+            exclude: compile(&settings.exclude),
+            include_callers: settings.include_callers.clone(),
+            exclude_callers: settings.exclude_callers.clone(),
+            blocked: settings.blocked.clone(),
+        }
+    }
+
+    /// Exclude rules win over include rules, no include rules lets everything through. Text
+    /// without a known caller only matches patterns.
+    fn allows(&self, text: &str, caller: Option<u32>) -> bool {
+        let in_callers = |ranges: &[[u32; 2]]| caller.is_some_and(|rva| in_ranges(rva, ranges));
+        if self.exclude.iter().any(|pattern| pattern.is_match(text))
+            || in_callers(&self.exclude_callers)
+            || self.blocked.iter().any(|rule| rule.blocks(text, caller))
+        {
+            return false;
+        }
+        (self.include.is_empty() && self.include_callers.is_empty())
+            || self.include.iter().any(|pattern| pattern.is_match(text))
+            || in_callers(&self.include_callers)
+    }
+}
+
+/// Compiles every valid pattern, invalid ones are logged the first time they're seen and skipped.
+fn compile(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter(|pattern| !pattern.is_empty())
+        .filter_map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                if REPORTED_PATTERNS.lock().unwrap().insert(pattern.clone()) {
+                    tracing::error!("Invalid filter pattern {:?}: {}", pattern, e);
+                }
+                None
+            }
+        })
+        .collect()
+}
+
+/// Replaces the patterns captured text is filtered with.
+pub fn set_filter(settings: &FilterSettings) {
+    *TEXT_FILTER.write().unwrap() = TextFilter::new(settings);
+}
+
+/// Whether captured text drawn by the game code at `caller` should be queued.
+pub fn allows(text: &str, caller: Option<u32>) -> bool {
+    TEXT_FILTER.read().unwrap().allows(text, caller)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    #[test]
+    fn no_rules_allow_everything() {
+        let filter = TextFilter::new(&FilterSettings::default());
+
+        assert!(filter.allows("HP 100", None));
+        assert!(filter.allows("", Some(0x1234)));
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let filter = TextFilter::new(&FilterSettings {
+            include: patterns(&["^HP"]),
+            exclude: patterns(&["debug"]),
+            ..FilterSettings::default()
+        });
+
+        assert!(filter.allows("HP 100", None));
+        assert!(!filter.allows("HP debug", None));
+        assert!(!filter.allows("FP 50", None));
+    }
+
+    #[test]
+    fn callers_match_inclusive_ranges() {
+        let filter = TextFilter::new(&FilterSettings {
+            include_callers: vec![[0x1000, 0x1fff]],
+            exclude_callers: vec![[0x1800, 0x1800]],
+            ..FilterSettings::default()
+        });
+
+        assert!(filter.allows("text", Some(0x1000)));
+        assert!(filter.allows("text", Some(0x1fff)));
+        assert!(!filter.allows("text", Some(0x1800)));
+        assert!(!filter.allows("text", Some(0x2000)));
+        // Without a caller only patterns could let it through
+        assert!(!filter.allows("text", None));
+    }
+
+    #[test]
+    fn blocked_text_is_never_allowed() {
+        let filter = TextFilter::new(&FilterSettings {
+            include: patterns(&["."]),
+            blocked: vec![
+                BlockRule::Text("exact".to_string()),
+                BlockRule::Prefix("Pos:".to_string()),
+                BlockRule::Caller(0x1234),
+            ],
+            ..FilterSettings::default()
+        });
+
+        assert!(!filter.allows("exact", None));
+        assert!(filter.allows("exactly", None));
+        assert!(!filter.allows("Pos: 1, 2", None));
+        assert!(!filter.allows("anything", Some(0x1234)));
+        assert!(filter.allows("anything", Some(0x1235)));
+    }
+
+    #[test]
+    fn invalid_patterns_are_skipped() {
+        let filter = TextFilter::new(&FilterSettings {
+            include: patterns(&["(", "^ok$", ""]),
+            ..FilterSettings::default()
+        });
+
+        assert_eq!(filter.include.len(), 1);
+        assert!(filter.allows("ok", None));
+        assert!(!filter.allows("(", None));
+    }
+}
@@ -0,0 +1,127 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use eldenring_util::singleton::build_singleton_table;
+use pelite::pe64::Pe;
+use serde::{Deserialize, Serialize};
+
+use crate::command::DrawCommand;
+use crate::config::GameFlag;
+use crate::memory::is_readable;
+use crate::target::target_program;
+
+/// Addresses of the statics holding each singleton instance, by name. Built the first time a
+/// flag on a singleton is read.
+static SINGLETONS: OnceLock<HashMap<String, usize>> = OnceLock::new();
+
+/// Whether captured commands are drawn regardless of the game's own debug-draw flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagMode {
+    /// Everything the game submits is drawn.
+    Always,
+    /// Only what the flags covering it allow is drawn, like the game would.
+    Follow,
+}
+
+impl FlagMode {
+    pub const ALL: [FlagMode; 2] = [FlagMode::Always, FlagMode::Follow];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FlagMode::Always => "always",
+            FlagMode::Follow => "follow",
+        }
+    }
+}
+
+/// What a flag decides is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawCategory {
+    Text,
+    /// Lines and rectangles on the screen.
+    Shapes,
+    WorldText,
+    WorldShapes,
+}
+
+impl DrawCategory {
+    /// The category of a command, `None` for the ones that only set state.
+    fn of(command: &DrawCommand) -> Option<Self> {
+        match command {
+            DrawCommand::Text { world_z: None, .. } => Some(DrawCategory::Text),
+            DrawCommand::Text { .. } => Some(DrawCategory::WorldText),
+            DrawCommand::Line { .. } | DrawCommand::Rect { .. } => Some(DrawCategory::Shapes),
+            DrawCommand::WorldLine { .. }
+            | DrawCommand::WorldSphere { .. }
+            | DrawCommand::WorldCapsule { .. } => Some(DrawCategory::WorldShapes),
+            DrawCommand::SetFontSize(..)
+            | DrawCommand::SetTextScale(..)
+            | DrawCommand::ResetTextScale(..)
+            | DrawCommand::SetColor(..) => None,
+        }
+    }
+}
+
+/// The game's debug-draw flags as read at the start of the frame.
+pub struct GameFlags {
+    /// Value of each configured flag, `None` when it couldn't be read.
+    values: Vec<Option<u8>>,
+}
+
+impl GameFlags {
+    pub fn new() -> Self {
+        Self { values: Vec::new() }
+    }
+
+    /// Reads the flags, only done in `follow` mode so nothing is touched otherwise.
+    pub fn update(&mut self, mode: FlagMode, flags: &[GameFlag]) {
+        self.values.clear();
+        if mode == FlagMode::Follow {
+            self.values.extend(flags.iter().map(read_flag));
+        }
+    }
+
+    /// Whether `command` is drawn in `follow` mode: every flag covering its category has to be
+    /// set, an unreadable flag counts as unset since the game can't have drawn with it either.
+    pub fn allows(&self, flags: &[GameFlag], command: &DrawCommand) -> bool {
+        let Some(category) = DrawCategory::of(command) else {
+            return true;
+        };
+        flags
+            .iter()
+            .zip(&self.values)
+            .filter(|(flag, _)| flag.applies_to.is_empty() || flag.applies_to.contains(&category))
+            .all(|(_, value)| value.is_some_and(|value| value != 0))
+    }
+
+    /// The value read for each flag this frame, in the order of `flags`.
+    pub fn values(&self) -> &[Option<u8>] {
+        &self.values
+    }
+}
+
+/// Reads the byte a flag points at, `None` when the singleton isn't there yet or the memory
+/// can't be read.
+pub fn read_flag(flag: &GameFlag) -> Option<u8> {
+    let base = match (&flag.singleton, flag.rva) {
+        (Some(name), _) => {
+            let table = SINGLETONS.get_or_init(|| {
+                build_singleton_table(&target_program()).unwrap_or_else(|e| {
+                    tracing::error!("Failed to find the game's singletons: {}", e);
+                    HashMap::new()
+                })
+            });
+            let instance_static = *table.get(name)? as *const usize;
+            // The static is in the executable's data, it's the instance that may not exist yet
+            unsafe { instance_static.read() }
+        }
+        (None, Some(rva)) => target_program().rva_to_va(rva).ok()? as usize,
+        (None, None) => return None,
+    };
+    if base == 0 {
+        return None;
+    }
+    let ptr = (base + flag.offset as usize) as *const u8;
+    is_readable(ptr.cast(), 1).then(|| unsafe { ptr.read() })
+}
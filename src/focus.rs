@@ -0,0 +1,46 @@
+use eldenring::cs::CSWindowImp;
+use eldenring_util::singleton::get_instance;
+use hudhook::windows::Win32::{
+    System::Threading::GetCurrentProcessId,
+    UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId},
+};
+use serde::{Deserialize, Serialize};
+
+/// What happens to the overlay while the game's window isn't the foreground window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnfocusedMode {
+    /// Drawn as usual.
+    Show,
+    /// Drawn at `unfocused_alpha`.
+    Dim,
+    /// Not drawn.
+    Hide,
+}
+
+impl UnfocusedMode {
+    pub const ALL: [UnfocusedMode; 3] =
+        [UnfocusedMode::Show, UnfocusedMode::Dim, UnfocusedMode::Hide];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            UnfocusedMode::Show => "show",
+            UnfocusedMode::Dim => "dim",
+            UnfocusedMode::Hide => "hide",
+        }
+    }
+}
+
+/// Whether the game's window is the foreground window.
+///
+/// Without `CSWindowImp`, e.g. while replaying in another program, any window of the process
+/// counts.
+pub fn is_game_focused() -> bool {
+    let foreground = unsafe { GetForegroundWindow() };
+    if let Ok(Some(window)) = unsafe { get_instance::<CSWindowImp>() } {
+        return foreground.0 == window.window_handle;
+    }
+    let mut process_id = 0;
+    unsafe { GetWindowThreadProcessId(foreground, Some(&mut process_id)) };
+    process_id == unsafe { GetCurrentProcessId() }
+}
@@ -0,0 +1,66 @@
+use hudhook::imgui::FontGlyphRanges;
+use serde::{Deserialize, Serialize};
+
+/// Named sets of characters the font atlas can be built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlyphRange {
+    /// Basic Latin and Latin-1 Supplement.
+    Default,
+    Japanese,
+    ChineseFull,
+    ChineseSimplifiedCommon,
+    Korean,
+    Cyrillic,
+    Thai,
+    Vietnamese,
+}
+
+impl GlyphRange {
+    pub fn ranges(self) -> FontGlyphRanges {
+        match self {
+            GlyphRange::Default => FontGlyphRanges::default(),
+            GlyphRange::Japanese => FontGlyphRanges::japanese(),
+            GlyphRange::ChineseFull => FontGlyphRanges::chinese_full(),
+            GlyphRange::ChineseSimplifiedCommon => FontGlyphRanges::chinese_simplified_common(),
+            GlyphRange::Korean => FontGlyphRanges::korean(),
+            GlyphRange::Cyrillic => FontGlyphRanges::cyrillic(),
+            GlyphRange::Thai => FontGlyphRanges::thai(),
+            GlyphRange::Vietnamese => FontGlyphRanges::vietnamese(),
+        }
+    }
+}
+
+/// Whether `[start, end]` is a range imgui accepts, codepoint 0 terminates its range lists.
+pub fn is_valid_range(range: [u32; 2]) -> bool {
+    range[0] != 0 && range[0] <= range[1] && range[1] <= char::MAX as u32
+}
+
+/// Turns inclusive `[start, end]` pairs into the zero-terminated list imgui reads.
+///
+/// Overlapping and adjacent ranges are merged, imgui rejects overlapping ones. The list is
+/// leaked, the atlas keeps pointing at it for as long as the fonts exist. `None` if no valid
+/// range is left.
+pub fn custom_ranges(ranges: &[[u32; 2]]) -> Option<FontGlyphRanges> {
+    let mut ranges: Vec<[u32; 2]> = ranges
+        .iter()
+        .copied()
+        .filter(|range| is_valid_range(*range))
+        .collect();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<[u32; 2]> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range[0] <= last[1].saturating_add(1) => last[1] = last[1].max(range[1]),
+            _ => merged.push(range),
+        }
+    }
+    if merged.is_empty() {
+        return None;
+    }
+
+    let mut list: Vec<u32> = merged.into_iter().flatten().collect();
+    list.push(0);
+    Some(FontGlyphRanges::from_slice(list.leak()))
+}
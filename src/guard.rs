@@ -0,0 +1,92 @@
+use std::{
+    cell::Cell,
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+thread_local! {
+    /// Set while the current thread runs overlay code called from the game.
+    static IN_GUARD: Cell<bool> = const { Cell::new(false) };
+    /// Whether this thread is running a detour, including the original function it calls.
+    static IN_DETOUR: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Set by the first panic in guarded code, from then on the overlay neither draws nor captures.
+static OVERLAY_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a panic on the current thread would be caught by `catch`.
+pub fn in_guard() -> bool {
+    IN_GUARD.get()
+}
+
+pub fn is_overlay_disabled() -> bool {
+    OVERLAY_DISABLED.load(Ordering::Relaxed)
+}
+
+/// Turns the overlay off until the game restarts, returns `true` for the call that did it.
+pub fn disable_overlay() -> bool {
+    !OVERLAY_DISABLED.swap(true, Ordering::Relaxed)
+}
+
+/// Runs overlay code, returning `None` instead of unwinding into the game if it panics.
+pub fn catch<R>(f: impl FnOnce() -> R) -> Option<R> {
+    let was_guarded = IN_GUARD.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    IN_GUARD.set(was_guarded);
+    result.ok()
+}
+
+/// Marks the thread as being in a detour until it's dropped.
+pub struct DetourScope;
+
+impl Drop for DetourScope {
+    fn drop(&mut self) {
+        IN_DETOUR.set(false);
+    }
+}
+
+/// Enters a detour, `None` if the thread already is in one. `nested` counts the calls turned
+/// away.
+///
+/// A game function that calls another hooked one, or the overlay's own code doing so, would
+/// otherwise capture the inner call too. The detour then only calls the original.
+pub fn enter_detour(nested: &AtomicU64) -> Option<DetourScope> {
+    if IN_DETOUR.replace(true) {
+        nested.fetch_add(1, Ordering::Relaxed);
+        return None;
+    }
+    Some(DetourScope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_detours_are_turned_away() {
+        let nested = AtomicU64::new(0);
+        let outer = enter_detour(&nested);
+        assert!(outer.is_some());
+
+        assert!(enter_detour(&nested).is_none());
+        assert!(enter_detour(&nested).is_none());
+        assert_eq!(nested.load(Ordering::Relaxed), 2);
+
+        drop(outer);
+        assert!(!IN_DETOUR.get());
+        let next = enter_detour(&nested);
+        assert!(next.is_some());
+        assert_eq!(nested.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn detours_on_other_threads_are_separate() {
+        let nested = AtomicU64::new(0);
+        let _outer = enter_detour(&nested).unwrap();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| assert!(enter_detour(&nested).is_some()));
+        });
+        assert_eq!(nested.load(Ordering::Relaxed), 0);
+    }
+}
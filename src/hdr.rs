@@ -0,0 +1,168 @@
+use hudhook::imgui::ImColor32;
+use hudhook::windows::Win32::{
+    Foundation::HWND,
+    Graphics::{
+        Dxgi::{
+            Common::{
+                DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709, DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+            },
+            CreateDXGIFactory1, DXGI_OUTPUT_DESC1, IDXGIFactory1, IDXGIOutput6,
+        },
+        Gdi::{MONITOR_DEFAULTTOPRIMARY, MonitorFromWindow},
+    },
+};
+use hudhook::windows::core::Interface;
+use serde::{Deserialize, Serialize};
+
+/// Luminance scRGB's 1.0 stands for.
+const SCRGB_WHITE_NITS: f32 = 80.0;
+/// Luminance PQ's 1.0 stands for.
+const PQ_MAX_NITS: f32 = 10000.0;
+
+/// Rec. 709 primaries, which sRGB colors are in, to Rec. 2020 ones, which HDR10 uses.
+const REC709_TO_REC2020: [[f32; 3]; 3] = [
+    [0.6274, 0.3293, 0.0433],
+    [0.0691, 0.9195, 0.0114],
+    [0.0164, 0.0880, 0.8956],
+];
+
+/// How the colors the overlay draws with are encoded for the game's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HdrMode {
+    /// From the color space of the display the game is on.
+    Auto,
+    /// sRGB, as on an SDR display.
+    Off,
+    /// PQ encoded Rec. 2020, what the game outputs with HDR enabled.
+    Hdr10,
+    /// Linear Rec. 709 in a floating point swapchain.
+    Scrgb,
+}
+
+impl HdrMode {
+    pub const ALL: [HdrMode; 4] = [HdrMode::Auto, HdrMode::Off, HdrMode::Hdr10, HdrMode::Scrgb];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HdrMode::Auto => "auto",
+            HdrMode::Off => "off",
+            HdrMode::Hdr10 => "hdr10",
+            HdrMode::Scrgb => "scrgb",
+        }
+    }
+
+    /// The output to encode for, `detected` being what `detect_output` found.
+    pub fn output(self, detected: HdrOutput) -> HdrOutput {
+        match self {
+            HdrMode::Auto => detected,
+            HdrMode::Off => HdrOutput::Sdr,
+            HdrMode::Hdr10 => HdrOutput::Hdr10,
+            HdrMode::Scrgb => HdrOutput::Scrgb,
+        }
+    }
+}
+
+/// Color space the overlay's colors end up in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HdrOutput {
+    Sdr,
+    Hdr10,
+    Scrgb,
+}
+
+impl HdrOutput {
+    pub fn name(self) -> &'static str {
+        match self {
+            HdrOutput::Sdr => "SDR",
+            HdrOutput::Hdr10 => "HDR10",
+            HdrOutput::Scrgb => "scRGB",
+        }
+    }
+}
+
+/// Converts the overlay's sRGB colors for the output, every color drawn goes through it.
+#[derive(Debug, Clone, Copy)]
+pub struct ColorSpace {
+    pub output: HdrOutput,
+    /// Luminance white is shown at on an HDR output.
+    pub white_nits: f32,
+}
+
+impl ColorSpace {
+    pub fn color32(self, color: [f32; 4]) -> ImColor32 {
+        let [r, g, b, a] = color;
+        let rgb = match self.output {
+            HdrOutput::Sdr => [r, g, b],
+            // Vertex colors are 8 bit, so white can't go past the 80 nits of 1.0
+            HdrOutput::Scrgb => [r, g, b].map(|channel| {
+                (srgb_to_linear(channel) * self.white_nits / SCRGB_WHITE_NITS).min(1.0)
+            }),
+            HdrOutput::Hdr10 => {
+                let linear = [r, g, b].map(srgb_to_linear);
+                REC709_TO_REC2020.map(|row| {
+                    let channel = row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+                    pq_encode(channel * self.white_nits / PQ_MAX_NITS)
+                })
+            }
+        };
+        ImColor32::from([rgb[0], rgb[1], rgb[2], a])
+    }
+
+    /// An opaque color given as 8 bit sRGB, like `ImColor32::from_rgb`.
+    pub fn rgb(self, r: u8, g: u8, b: u8) -> ImColor32 {
+        let [r, g, b] = [r, g, b].map(|channel| channel as f32 / 255.0);
+        self.color32([r, g, b, 1.0])
+    }
+}
+
+fn srgb_to_linear(channel: f32) -> f32 {
+    let channel = channel.clamp(0.0, 1.0);
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// SMPTE ST 2084, `luminance` as a fraction of 10000 nits.
+fn pq_encode(luminance: f32) -> f32 {
+    const M1: f32 = 0.159_301_76;
+    const M2: f32 = 78.843_75;
+    const C1: f32 = 0.835_937_5;
+    const C2: f32 = 18.851_563;
+    const C3: f32 = 18.6875;
+    let y = luminance.clamp(0.0, 1.0).powf(M1);
+    ((C1 + C2 * y) / (1.0 + C3 * y)).powf(M2)
+}
+
+/// The color space of the display `window` is on.
+///
+/// hudhook doesn't expose the swapchain, so this is the display's mode rather than the
+/// swapchain's format. The game only outputs HDR on a display in HDR mode, but with its own HDR
+/// setting off it draws SDR there, which needs `hdr = "off"`.
+pub fn detect_output(window: HWND) -> HdrOutput {
+    let monitor = unsafe { MonitorFromWindow(window, MONITOR_DEFAULTTOPRIMARY) };
+    let Ok(factory) = (unsafe { CreateDXGIFactory1::<IDXGIFactory1>() }) else {
+        return HdrOutput::Sdr;
+    };
+    let adapters = (0..).map_while(|index| unsafe { factory.EnumAdapters1(index) }.ok());
+    for adapter in adapters {
+        let outputs = (0..).map_while(|index| unsafe { adapter.EnumOutputs(index) }.ok());
+        for output in outputs {
+            let Ok(output) = output.cast::<IDXGIOutput6>() else {
+                continue;
+            };
+            let mut desc = DXGI_OUTPUT_DESC1::default();
+            if unsafe { output.GetDesc1(&mut desc) }.is_err() || desc.Monitor != monitor {
+                continue;
+            }
+            return match desc.ColorSpace {
+                DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020 => HdrOutput::Hdr10,
+                DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709 => HdrOutput::Scrgb,
+                _ => HdrOutput::Sdr,
+            };
+        }
+    }
+    HdrOutput::Sdr
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use hudhook::imgui::{TreeNodeFlags, Ui};
+use regex::Regex;
+
+use crate::DebugTextRender;
+use crate::config::HighlightRule;
+use crate::processor::TextEntry;
+use crate::text::TextId;
+
+/// Texts whose matching rule is remembered before the cache starts over.
+const MAX_CACHED: usize = 4096;
+
+/// Highlight rules with their compiled patterns, matched against the text on screen.
+pub struct Highlighter {
+    rules: Vec<HighlightRule>,
+    /// `None` for an empty pattern or one that doesn't compile, the rule is kept so it can be
+    /// fixed.
+    regexes: Vec<Option<Regex>>,
+    /// First matching rule of each text seen, so repeated text isn't matched again every frame.
+    matches: HashMap<TextId, Option<usize>>,
+}
+
+impl Highlighter {
+    pub fn new(rules: &[HighlightRule]) -> Self {
+        let mut highlighter = Self {
+            rules: Vec::new(),
+            regexes: Vec::new(),
+            matches: HashMap::new(),
+        };
+        highlighter.set_rules(rules);
+        highlighter
+    }
+
+    pub fn set_rules(&mut self, rules: &[HighlightRule]) {
+        if rules == self.rules {
+            return;
+        }
+        self.rules = rules.to_vec();
+        self.regexes = rules
+            .iter()
+            .map(|rule| match Regex::new(&rule.pattern) {
+                // Would match everything, e.g. a rule just added in the settings window
+                _ if rule.pattern.is_empty() => None,
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    tracing::error!("Invalid highlight pattern {:?}: {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        self.matches.clear();
+    }
+
+    pub fn rules(&self) -> &[HighlightRule] {
+        &self.rules
+    }
+
+    /// Applies the color and emphasis of the first rule the text matches.
+    pub fn apply(&mut self, entry: &mut TextEntry) {
+        if self.rules.is_empty() {
+            return;
+        }
+        if self.matches.len() >= MAX_CACHED {
+            self.matches.clear();
+        }
+        let regexes = &self.regexes;
+        let rule = *self.matches.entry(entry.id()).or_insert_with(|| {
+            regexes.iter().position(|regex| {
+                regex
+                    .as_ref()
+                    .is_some_and(|regex| regex.is_match(&entry.text))
+            })
+        });
+        if let Some(rule) = rule.map(|index| &self.rules[index]) {
+            entry.color = rule.color;
+            entry.emphasis = rule.emphasis;
+        }
+    }
+}
+
+impl DebugTextRender {
+    /// Settings section listing the highlight rules, with controls to edit, add and remove them.
+    pub(crate) fn draw_highlight_settings(&mut self, ui: &Ui) {
+        if !ui.collapsing_header("Highlights", TreeNodeFlags::empty()) {
+            return;
+        }
+        ui.text("Text matching a pattern takes the color of the first rule it matches");
+
+        let mut rules = self.highlighter.rules().to_vec();
+        let mut removed = None;
+        for (index, rule) in rules.iter_mut().enumerate() {
+            let _id = ui.push_id_usize(index);
+            if ui.small_button("x") {
+                removed = Some(index);
+            }
+            ui.same_line();
+            ui.color_edit4_config("##color", &mut rule.color)
+                .inputs(false)
+                .build();
+            ui.same_line();
+            ui.checkbox("Bold", &mut rule.emphasis);
+            ui.same_line();
+            ui.input_text("##pattern", &mut rule.pattern).build();
+            if !rule.pattern.is_empty() && self.highlighter.regexes[index].is_none() {
+                ui.same_line();
+                ui.text_disabled("invalid");
+            }
+        }
+        if let Some(index) = removed {
+            rules.remove(index);
+        }
+        if ui.button("Add rule") {
+            rules.push(HighlightRule::default());
+        }
+        // Only recompiled when something changed
+        self.highlighter.set_rules(&rules);
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Instant,
+};
+
+use hudhook::imgui::{Condition, ListClipper, MouseButton, Ui};
+
+use crate::DebugTextRender;
+use crate::clipboard::set_clipboard_text;
+use crate::text::TextId;
+
+/// A captured text, or a run of it drawn at the same position in consecutive frames.
+struct HistoryEntry {
+    text: Arc<str>,
+    position: [f32; 2],
+    /// RVA of the game code that drew it, if callers were captured.
+    caller: Option<u32>,
+    /// Microseconds since the game started, when the run started.
+    time_us: u64,
+    /// Last frame the text was drawn in.
+    last_frame: u64,
+    /// Frames the text was drawn in, 1 for text that only showed up once.
+    repeats: u64,
+}
+
+impl HistoryEntry {
+    fn line(&self) -> String {
+        let mut line = format!(
+            "{:.3}s  {:.0},{:.0}  {}",
+            self.time_us as f64 / 1_000_000.0,
+            self.position[0],
+            self.position[1],
+            self.text
+        );
+        if self.repeats > 1 {
+            line.push_str(&format!("  (x{})", self.repeats));
+        }
+        line
+    }
+}
+
+/// The last captured text, oldest first, so text that was only on screen for a frame can still
+/// be read.
+///
+/// Text the game keeps drawing at the same position is kept as one entry with a repeat count,
+/// otherwise anything drawn every frame would push everything else out.
+pub struct TextHistory {
+    entries: VecDeque<HistoryEntry>,
+    /// Entries kept, 0 doesn't keep any.
+    capacity: usize,
+    /// Entries pushed out of the front so far, to turn the indices below into positions.
+    evicted: u64,
+    /// Entries of the runs that continued in the last frame, by absolute index.
+    runs: HashMap<TextId, u64>,
+    /// Runs that continued in this frame, they replace `runs` at the end of it.
+    pending_runs: HashMap<TextId, u64>,
+    /// Text the list is filtered by, case insensitive.
+    pub(crate) filter: String,
+}
+
+impl TextHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+            evicted: 0,
+            runs: HashMap::new(),
+            pending_runs: HashMap::new(),
+            filter: String::new(),
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    /// Records text captured in `frame`, adding to its run if it was drawn in the frame before.
+    pub fn push(
+        &mut self,
+        text: &Arc<str>,
+        position: [f32; 2],
+        caller: Option<u32>,
+        frame: u64,
+        time_us: u64,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        let id = TextId::new(text, position);
+        let run = self
+            .pending_runs
+            .get(&id)
+            .or_else(|| self.runs.get(&id))
+            .copied();
+        if let Some(index) = run
+            && let Some(entry) = self.entry_mut(index)
+            && entry.text == *text
+            && entry.position == position
+        {
+            // The same text drawn more than once in a frame is still one repeat
+            if entry.last_frame != frame {
+                entry.last_frame = frame;
+                entry.repeats += 1;
+            }
+            self.pending_runs.insert(id, index);
+            return;
+        }
+
+        self.entries.push_back(HistoryEntry {
+            text: text.clone(),
+            position,
+            caller,
+            time_us,
+            last_frame: frame,
+            repeats: 1,
+        });
+        self.pending_runs
+            .insert(id, self.evicted + self.entries.len() as u64 - 1);
+        self.trim();
+    }
+
+    /// Ends the runs that weren't continued in the frame since the last call.
+    pub fn finish_frame(&mut self) {
+        self.runs = std::mem::take(&mut self.pending_runs);
+    }
+
+    fn entry_mut(&mut self, index: u64) -> Option<&mut HistoryEntry> {
+        let index = index.checked_sub(self.evicted)?;
+        self.entries.get_mut(index as usize)
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+            self.evicted += 1;
+        }
+    }
+
+    /// Entries matching the filter, oldest first.
+    fn filtered(&self) -> Vec<&HistoryEntry> {
+        let filter = self.filter.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| filter.is_empty() || entry.text.to_lowercase().contains(&filter))
+            .collect()
+    }
+}
+
+impl DebugTextRender {
+    /// Draws the window listing the captured text, newest at the bottom.
+    pub(crate) fn draw_history(&mut self, ui: &Ui) {
+        let mut open = self.history_open;
+        let mut clicked = None;
+        ui.window("Debug Text History")
+            .opened(&mut open)
+            .position([440.0, 300.0], Condition::FirstUseEver)
+            .size([600.0, 500.0], Condition::FirstUseEver)
+            .build(|| {
+                ui.set_next_item_width(300.0);
+                ui.input_text("Filter", &mut self.history.filter).build();
+                let entries = self.history.filtered();
+                ui.same_line();
+                if ui.button("Copy visible") {
+                    let text = entries
+                        .iter()
+                        .map(|entry| entry.line())
+                        .collect::<Vec<_>>()
+                        .join("\r\n");
+                    self.notice = Some(match set_clipboard_text(&text) {
+                        Ok(()) => (format!("copied {} lines", entries.len()), Instant::now()),
+                        Err(e) => {
+                            tracing::error!("Failed to copy the text history: {}", e);
+                            ("copying failed, see the log".to_string(), Instant::now())
+                        }
+                    });
+                }
+                ui.same_line();
+                ui.text_disabled(format!(
+                    "{} of {} entries",
+                    entries.len(),
+                    self.history.entries.len()
+                ));
+                ui.separator();
+
+                ui.child_window("##entries").build(|| {
+                    // Follow new entries unless the list was scrolled up
+                    let at_bottom = ui.scroll_y() >= ui.scroll_max_y();
+                    let clipper = ListClipper::new(entries.len() as i32).begin(ui);
+                    for index in clipper.iter() {
+                        let entry = entries[index as usize];
+                        ui.text(entry.line());
+                        if ui.is_item_clicked_with_button(MouseButton::Right) {
+                            clicked = Some((entry.text.clone(), entry.caller));
+                        }
+                    }
+                    if at_bottom {
+                        ui.set_scroll_here_y_with_ratio(1.0);
+                    }
+                });
+            });
+        self.history_open = open;
+        if let Some((text, caller)) = clicked {
+            self.open_block_menu(&text, caller);
+        }
+    }
+}
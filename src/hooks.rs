@@ -0,0 +1,983 @@
+use std::{
+    cell::Cell,
+    fmt,
+    mem::{self, transmute},
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use eldenring::position::HavokPosition;
+use eldenring_util::program::Program;
+use retour::static_detour;
+
+use crate::address::{self, HookId, KnownBuild, ResolvedAddress};
+use crate::backend::hooked_backend;
+use crate::caller::caller_rva;
+use crate::command::DrawCommand;
+use crate::config::{Config, HookSettings};
+use crate::guard::DetourScope;
+use crate::intern::intern_utf16;
+use crate::logging::RateLimit;
+use crate::processor::BASE_IMGUI_FONT_SIZE_PX;
+use crate::push_command;
+use crate::{dedup, filter, guard, memory};
+
+static_detour! {
+    static DrawTextRenderRequest: unsafe extern "C" fn(usize, *mut HavokPosition, *const u16) -> ();
+    static SetFontSize: unsafe extern "C" fn(usize, f32) -> ();
+    static SetTextScale: unsafe extern "C" fn(usize, f32, f32, f32) -> ();
+    static ResetTextScale: unsafe extern "C" fn(usize) -> ();
+    static DrawTextWithSize: unsafe extern "C" fn(usize, *mut HavokPosition, *mut f32, *const u16) -> ();
+    static SetColor: unsafe extern "C" fn(usize, *const HavokPosition) -> ();
+    static DrawLine: unsafe extern "C" fn(usize, *const HavokPosition, *const HavokPosition) -> ();
+    static DrawRect: unsafe extern "C" fn(usize, *const HavokPosition, *const HavokPosition) -> ();
+    static FillRect: unsafe extern "C" fn(usize, *const HavokPosition, *const HavokPosition) -> ();
+    static DrawWorldText: unsafe extern "C" fn(usize, *mut HavokPosition, *const u16) -> ();
+    static DrawWorldLine: unsafe extern "C" fn(usize, *const HavokPosition, *const HavokPosition) -> ();
+    static DrawWorldSphere: unsafe extern "C" fn(usize, *const HavokPosition, f32) -> ();
+    static DrawWorldCapsule: unsafe extern "C" fn(usize, *const HavokPosition, *const HavokPosition, f32) -> ();
+}
+
+/// Addresses the hooks were installed at, indexed by `HookId`.
+static RESOLVED_ADDRESSES: [OnceLock<ResolvedAddress>; HookId::ALL.len()] =
+    [const { OnceLock::new() }; HookId::ALL.len()];
+
+/// Why each hook that failed to install did, indexed by `HookId`.
+static INSTALL_ERRORS: [OnceLock<String>; HookId::ALL.len()] =
+    [const { OnceLock::new() }; HookId::ALL.len()];
+
+/// Whether each hook captures the calls it sees, indexed by `HookId`. A hook that doesn't can
+/// still be installed to keep the game from running its function.
+static CAPTURING: [AtomicBool; HookId::ALL.len()] =
+    [const { AtomicBool::new(true) }; HookId::ALL.len()];
+/// Held while detours are enabled or disabled, the config watcher and the settings window can
+/// both toggle them.
+static TOGGLE_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn is_capturing(hook: HookId) -> bool {
+    CAPTURING[hook as usize].load(Ordering::Relaxed)
+}
+
+/// Hooks that panicked and disabled the overlay, indexed by `HookId`.
+static POISONED: [AtomicBool; HookId::ALL.len()] =
+    [const { AtomicBool::new(false) }; HookId::ALL.len()];
+
+thread_local! {
+    /// Hook whose part of a detour the thread is running.
+    static RUNNING_HOOK: Cell<Option<HookId>> = const { Cell::new(None) };
+}
+
+pub fn is_hook_poisoned(hook: HookId) -> bool {
+    POISONED[hook as usize].load(Ordering::Relaxed)
+}
+
+/// Marks the hook the panicking thread is running as poisoned, returning it.
+///
+/// Called by the panic hook before the crash report is written, so the report names it.
+pub fn poison_running_hook() -> Option<HookId> {
+    let hook = RUNNING_HOOK.get()?;
+    POISONED[hook as usize].store(true, Ordering::Relaxed);
+    Some(hook)
+}
+
+/// Runs the overlay's part of a detour, skipped while the hook doesn't capture and once the
+/// overlay was disabled by a panic.
+///
+/// The state setting detours call the original function either way, so the game keeps running.
+/// The drawing ones only do when `passes_through` allows it.
+fn guarded(hook: HookId, body: impl FnOnce()) {
+    if guard::is_overlay_disabled() || !is_capturing(hook) {
+        return;
+    }
+
+    let outer = RUNNING_HOOK.replace(Some(hook));
+    let result = guard::catch(body);
+    RUNNING_HOOK.set(outer);
+    if result.is_none() {
+        tracing::error!("{} hook panicked and was poisoned", hook.name());
+    }
+}
+
+/// Calls into a hook made while the same thread was already in a detour, indexed by `HookId`.
+static REENTRANT_CALLS: [AtomicU64; HookId::ALL.len()] =
+    [const { AtomicU64::new(0) }; HookId::ALL.len()];
+
+pub fn reentrant_calls(hook: HookId) -> u64 {
+    REENTRANT_CALLS[hook as usize].load(Ordering::Relaxed)
+}
+
+/// Enters the detour of `hook`, `None` if the thread already is in one, see `guard::enter_detour`.
+fn enter_detour(hook: HookId) -> Option<DetourScope> {
+    guard::enter_detour(&REENTRANT_CALLS[hook as usize])
+}
+
+#[derive(Debug)]
+pub enum HookError {
+    AddressNotFound,
+    /// The function doesn't start with the bytes listed for it, these are the ones it does.
+    PrologueMismatch(Vec<u8>),
+    Initialize(retour::Error),
+    Enable(retour::Error),
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookError::AddressNotFound => write!(f, "address not found"),
+            HookError::PrologueMismatch(found) => {
+                let found = found
+                    .iter()
+                    .map(|byte| format!("{byte:02X}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                write!(
+                    f,
+                    "prologue mismatch, unsupported game version? The function starts with {found}"
+                )
+            }
+            HookError::Initialize(e) => write!(f, "detour initialization failed: {e}"),
+            HookError::Enable(e) => write!(f, "detour enable failed: {e}"),
+        }
+    }
+}
+
+/// Whether the drawing detours also call the game's own function, set from the config.
+static PASSTHROUGH_NATIVE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_passthrough_native(passthrough: bool) {
+    PASSTHROUGH_NATIVE.store(passthrough, Ordering::Relaxed);
+}
+
+/// Whether a drawing detour should call the game's function after capturing the call.
+///
+/// Besides the config, the game draws its own debug text while the overlay isn't hooked or was
+/// disabled, so the text isn't lost. A hook that doesn't capture is only installed to swallow
+/// the calls.
+fn passes_through(hook: HookId) -> bool {
+    is_capturing(hook)
+        && (PASSTHROUGH_NATIVE.load(Ordering::Relaxed)
+            || hooked_backend().is_none()
+            || guard::is_overlay_disabled())
+}
+
+/// Code units read from a string before giving up on its terminator.
+static MAX_TEXT_LENGTH: AtomicUsize = AtomicUsize::new(4096);
+static REPLACEMENT_LOG: RateLimit = RateLimit::new(Duration::from_secs(1));
+
+pub fn set_max_text_length(max_length: usize) {
+    MAX_TEXT_LENGTH.store(max_length, Ordering::Relaxed);
+}
+
+static INVALID_POINTER_LOG: RateLimit = RateLimit::new(Duration::from_secs(1));
+
+/// Logs a call that was skipped because the game passed a pointer that can't be read.
+fn skip_invalid_pointer(hook: HookId, argument: &str) {
+    if INVALID_POINTER_LOG.allow() {
+        tracing::warn!(
+            "Skipped a {} call with an invalid {}",
+            hook.name(),
+            argument
+        );
+    }
+}
+
+/// Copies the value behind a pointer from the game, `None` if it's null or not readable.
+fn read<T: Copy>(ptr: *const T) -> Option<T> {
+    memory::is_readable(ptr.cast(), mem::size_of::<T>()).then(|| unsafe { ptr.read_unaligned() })
+}
+
+/// Decodes a null-terminated UTF-16 string, invalid code units become U+FFFD.
+///
+/// At most `MAX_TEXT_LENGTH` code units are read, and never past readable memory, a missing
+/// terminator truncates the string. Repeated strings share the allocation of the first one.
+/// `None` if the pointer is null or not readable.
+fn u16_ptr_to_string(ptr: *const u16) -> Option<Arc<str>> {
+    let max_length = MAX_TEXT_LENGTH.load(Ordering::Relaxed);
+    let readable = memory::readable_len(ptr.cast(), max_length * 2) / 2;
+    if ptr.is_null() || readable == 0 {
+        return None;
+    }
+
+    let len = (0..readable)
+        .take_while(|&i| unsafe { *ptr.add(i) } != 0)
+        .count();
+    let slice = unsafe { std::slice::from_raw_parts(ptr, len) };
+
+    let (text, decoded) = intern_utf16(slice);
+    if decoded && text.contains(char::REPLACEMENT_CHARACTER) && REPLACEMENT_LOG.allow() {
+        tracing::debug!("Text at {:p} has invalid UTF-16: {}", ptr, text);
+    }
+    Some(text)
+}
+
+/// Queues captured text unless it's filtered out or a duplicate of recent text.
+///
+/// `world` text is at a position in the world, which is projected to the screen when it's drawn.
+fn queue_text(
+    ez_draw: usize,
+    text: Arc<str>,
+    position: &HavokPosition,
+    font_size: Option<f32>,
+    box_width: Option<f32>,
+    world: bool,
+) {
+    let caller = caller_rva();
+    if !filter::allows(&text, caller) || dedup::is_duplicate(&text, position.0, position.1) {
+        return;
+    }
+
+    push_command(DrawCommand::Text {
+        ez_draw,
+        text,
+        caller,
+        x: position.0,
+        y: position.1,
+        font_size,
+        box_width,
+        world_z: world.then_some(position.2),
+    });
+}
+
+/// Reads the text box `DrawTextWithSize` gets, a height (the font size) followed by a width.
+///
+/// A height of 0 means the base font size, a width of 0 or less leaves the text unbounded. A null
+/// box leaves both to the drawing instance, `None` if the box isn't readable.
+fn read_text_box(text_box: *const f32) -> Option<(Option<f32>, Option<f32>)> {
+    if text_box.is_null() {
+        return Some((None, None));
+    }
+
+    let [height, width] = read(text_box as *const [f32; 2])?;
+    let font_size = if height == 0.0 {
+        BASE_IMGUI_FONT_SIZE_PX
+    } else {
+        height
+    };
+    Some((Some(font_size), (width > 0.0).then_some(width)))
+}
+
+/// Neither the RVA table of the game version, signatures nor the config cover the hooks the
+/// overlay needs.
+#[derive(Debug)]
+pub struct UnsupportedVersion {
+    /// `None` when the executable has no version resource.
+    pub version: Option<String>,
+    /// Name of the version's RVA table, `None` when it has none.
+    pub build: Option<&'static str>,
+    pub missing: Vec<HookId>,
+}
+
+impl fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let missing = self
+            .missing
+            .iter()
+            .map(|hook| hook.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        match (self.build, &self.version) {
+            (Some(build), _) => write!(
+                f,
+                "RVA table of {build} has no prologues recorded to check some of its RVAs with"
+            ),
+            (None, Some(version)) => write!(f, "game version {version} isn't supported yet"),
+            (None, None) => write!(f, "game version couldn't be read"),
+        }?;
+        write!(f, ", the addresses of {missing} are unknown")
+    }
+}
+
+/// Finds the address of every hooked function, `install_hooks` detours the ones found.
+///
+/// Nothing should be hooked when this fails, the addresses that were found could only be
+/// guesses for the other functions.
+pub fn resolve_hooks(program: &Program, config: &Config) -> Result<(), UnsupportedVersion> {
+    let version = address::game_version(program);
+    let build = address::known_build(program, version.as_deref());
+    let version_name = version.as_deref().unwrap_or("unknown");
+    match build {
+        Some(build) => tracing::info!(
+            "Game version {}, using the RVA table of {}",
+            version_name,
+            build.name()
+        ),
+        None => tracing::warn!(
+            "Game version {} has no RVA table, only signatures and the config are used",
+            version_name
+        ),
+    }
+
+    let overrides = match &config.rvas.version {
+        Some(expected) if version.as_ref() != Some(expected) => {
+            tracing::warn!(
+                "Ignoring the rvas settings, they're for game version {} and not {}",
+                expected,
+                version_name
+            );
+            None
+        }
+        _ => Some(&config.rvas),
+    };
+
+    let mut missing = Vec::new();
+    for hook in HookId::ALL {
+        let configured = match hook {
+            HookId::DrawWorldText => config.world_text.rva,
+            HookId::DrawWorldLine => config.world_shapes.line_rva,
+            HookId::DrawWorldSphere => config.world_shapes.sphere_rva,
+            HookId::DrawWorldCapsule => config.world_shapes.capsule_rva,
+            _ => overrides.and_then(|rvas| rvas.get(hook)),
+        };
+        match address::resolve(program, hook, configured, build) {
+            Some(resolved) => {
+                RESOLVED_ADDRESSES[hook as usize].set(resolved).ok();
+            }
+            None if !hook.is_optional() => missing.push(hook),
+            None => {}
+        }
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(UnsupportedVersion {
+            version,
+            build: build.map(KnownBuild::name),
+            missing,
+        })
+    }
+}
+
+/// One line listing where each hook was resolved to, for the startup log.
+pub fn resolved_summary() -> String {
+    HookId::ALL
+        .iter()
+        .map(|&hook| match resolved_address(hook) {
+            Some(resolved) => format!(
+                "{} at {:#x} ({})",
+                hook.name(),
+                resolved.va,
+                resolved.source
+            ),
+            None => format!("{} not resolved", hook.name()),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+unsafe fn install_draw_text_render_request(va: u64) -> Result<(), HookError> {
+    unsafe {
+        DrawTextRenderRequest
+            .initialize(
+                transmute::<u64, unsafe extern "C" fn(usize, *mut HavokPosition, *const u16)>(va),
+                |ez_draw: usize, pos: *mut HavokPosition, text: *const u16| {
+                    let Some(_detour) = enter_detour(HookId::DrawTextRenderRequest) else {
+                        return DrawTextRenderRequest.call(ez_draw, pos, text);
+                    };
+                    guarded(HookId::DrawTextRenderRequest, || {
+                        let Some(position) = read(pos) else {
+                            return skip_invalid_pointer(HookId::DrawTextRenderRequest, "position");
+                        };
+                        let Some(text) = u16_ptr_to_string(text) else {
+                            return skip_invalid_pointer(HookId::DrawTextRenderRequest, "text");
+                        };
+                        queue_text(ez_draw, text, &position, None, None, false);
+                    });
+                    if passes_through(HookId::DrawTextRenderRequest) {
+                        DrawTextRenderRequest.call(ez_draw, pos, text);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_set_font_size(va: u64) -> Result<(), HookError> {
+    unsafe {
+        SetFontSize
+            .initialize(
+                transmute::<u64, unsafe extern "C" fn(usize, f32)>(va),
+                |ez_draw: usize, font_size: f32| {
+                    let Some(_detour) = enter_detour(HookId::SetFontSize) else {
+                        return SetFontSize.call(ez_draw, font_size);
+                    };
+                    SetFontSize.call(ez_draw, font_size);
+                    guarded(HookId::SetFontSize, || {
+                        push_command(DrawCommand::SetFontSize(ez_draw, font_size));
+                    });
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_set_text_scale(va: u64) -> Result<(), HookError> {
+    unsafe {
+        SetTextScale
+            .initialize(
+                transmute::<u64, unsafe extern "C" fn(usize, f32, f32, f32)>(va),
+                |ez_draw: usize, width_scale: f32, height_scale: f32, font_size: f32| {
+                    let Some(_detour) = enter_detour(HookId::SetTextScale) else {
+                        return SetTextScale.call(ez_draw, width_scale, height_scale, font_size);
+                    };
+                    SetTextScale.call(ez_draw, width_scale, height_scale, font_size);
+                    guarded(HookId::SetTextScale, || {
+                        push_command(DrawCommand::SetTextScale(
+                            ez_draw,
+                            width_scale,
+                            height_scale,
+                            font_size,
+                        ));
+                    });
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_reset_text_scale(va: u64) -> Result<(), HookError> {
+    unsafe {
+        ResetTextScale
+            .initialize(
+                transmute::<u64, unsafe extern "C" fn(usize)>(va),
+                |ez_draw: usize| {
+                    let Some(_detour) = enter_detour(HookId::ResetTextScale) else {
+                        return ResetTextScale.call(ez_draw);
+                    };
+                    ResetTextScale.call(ez_draw);
+                    guarded(HookId::ResetTextScale, || {
+                        push_command(DrawCommand::ResetTextScale(ez_draw));
+                    });
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_draw_text_with_size(va: u64) -> Result<(), HookError> {
+    unsafe {
+        DrawTextWithSize
+            .initialize(
+                transmute::<
+                    u64,
+                    unsafe extern "C" fn(usize, *mut HavokPosition, *mut f32, *const u16),
+                >(va),
+                |ez_draw: usize, pos: *mut HavokPosition, text_box: *mut f32, text: *const u16| {
+                    let Some(_detour) = enter_detour(HookId::DrawTextWithSize) else {
+                        return DrawTextWithSize.call(ez_draw, pos, text_box, text);
+                    };
+                    guarded(HookId::DrawTextWithSize, || {
+                        let Some(position) = read(pos) else {
+                            return skip_invalid_pointer(HookId::DrawTextWithSize, "position");
+                        };
+                        let Some((font_size, box_width)) = read_text_box(text_box) else {
+                            return skip_invalid_pointer(HookId::DrawTextWithSize, "text box");
+                        };
+                        let Some(text) = u16_ptr_to_string(text) else {
+                            return skip_invalid_pointer(HookId::DrawTextWithSize, "text");
+                        };
+                        queue_text(ez_draw, text, &position, font_size, box_width, false);
+                    });
+                    if passes_through(HookId::DrawTextWithSize) {
+                        DrawTextWithSize.call(ez_draw, pos, text_box, text);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_set_color(va: u64) -> Result<(), HookError> {
+    unsafe {
+        SetColor
+            .initialize(
+                transmute::<u64, unsafe extern "C" fn(usize, *const HavokPosition)>(va),
+                |ez_draw: usize, color: *const HavokPosition| {
+                    let Some(_detour) = enter_detour(HookId::SetColor) else {
+                        return SetColor.call(ez_draw, color);
+                    };
+                    SetColor.call(ez_draw, color);
+                    guarded(HookId::SetColor, || {
+                        let Some(color) = read(color) else {
+                            return skip_invalid_pointer(HookId::SetColor, "color");
+                        };
+                        push_command(DrawCommand::SetColor(
+                            ez_draw,
+                            [color.0, color.1, color.2, color.3],
+                        ));
+                    });
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+type PrimitiveFn = unsafe extern "C" fn(usize, *const HavokPosition, *const HavokPosition);
+
+unsafe fn install_draw_line(va: u64) -> Result<(), HookError> {
+    unsafe {
+        DrawLine
+            .initialize(
+                transmute::<u64, PrimitiveFn>(va),
+                |ez_draw: usize, from: *const HavokPosition, to: *const HavokPosition| {
+                    let Some(_detour) = enter_detour(HookId::DrawLine) else {
+                        return DrawLine.call(ez_draw, from, to);
+                    };
+                    guarded(HookId::DrawLine, || {
+                        let (Some(from), Some(to)) = (read(from), read(to)) else {
+                            return skip_invalid_pointer(HookId::DrawLine, "position");
+                        };
+                        push_command(DrawCommand::Line {
+                            ez_draw,
+                            from: [from.0, from.1],
+                            to: [to.0, to.1],
+                        });
+                    });
+                    if passes_through(HookId::DrawLine) {
+                        DrawLine.call(ez_draw, from, to);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_draw_rect(va: u64) -> Result<(), HookError> {
+    unsafe {
+        DrawRect
+            .initialize(
+                transmute::<u64, PrimitiveFn>(va),
+                |ez_draw: usize, min: *const HavokPosition, max: *const HavokPosition| {
+                    let Some(_detour) = enter_detour(HookId::DrawRect) else {
+                        return DrawRect.call(ez_draw, min, max);
+                    };
+                    guarded(HookId::DrawRect, || {
+                        let (Some(min), Some(max)) = (read(min), read(max)) else {
+                            return skip_invalid_pointer(HookId::DrawRect, "position");
+                        };
+                        push_command(DrawCommand::Rect {
+                            ez_draw,
+                            min: [min.0, min.1],
+                            max: [max.0, max.1],
+                            filled: false,
+                        });
+                    });
+                    if passes_through(HookId::DrawRect) {
+                        DrawRect.call(ez_draw, min, max);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_fill_rect(va: u64) -> Result<(), HookError> {
+    unsafe {
+        FillRect
+            .initialize(
+                transmute::<u64, PrimitiveFn>(va),
+                |ez_draw: usize, min: *const HavokPosition, max: *const HavokPosition| {
+                    let Some(_detour) = enter_detour(HookId::FillRect) else {
+                        return FillRect.call(ez_draw, min, max);
+                    };
+                    guarded(HookId::FillRect, || {
+                        let (Some(min), Some(max)) = (read(min), read(max)) else {
+                            return skip_invalid_pointer(HookId::FillRect, "position");
+                        };
+                        push_command(DrawCommand::Rect {
+                            ez_draw,
+                            min: [min.0, min.1],
+                            max: [max.0, max.1],
+                            filled: true,
+                        });
+                    });
+                    if passes_through(HookId::FillRect) {
+                        FillRect.call(ez_draw, min, max);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_draw_world_text(va: u64) -> Result<(), HookError> {
+    unsafe {
+        DrawWorldText
+            .initialize(
+                transmute::<u64, unsafe extern "C" fn(usize, *mut HavokPosition, *const u16)>(va),
+                |ez_draw: usize, pos: *mut HavokPosition, text: *const u16| {
+                    let Some(_detour) = enter_detour(HookId::DrawWorldText) else {
+                        return DrawWorldText.call(ez_draw, pos, text);
+                    };
+                    guarded(HookId::DrawWorldText, || {
+                        let Some(position) = read(pos) else {
+                            return skip_invalid_pointer(HookId::DrawWorldText, "position");
+                        };
+                        let Some(text) = u16_ptr_to_string(text) else {
+                            return skip_invalid_pointer(HookId::DrawWorldText, "text");
+                        };
+                        queue_text(ez_draw, text, &position, None, None, true);
+                    });
+                    if passes_through(HookId::DrawWorldText) {
+                        DrawWorldText.call(ez_draw, pos, text);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+/// The x, y and z of a world position.
+fn world_point(position: &HavokPosition) -> [f32; 3] {
+    [position.0, position.1, position.2]
+}
+
+unsafe fn install_draw_world_line(va: u64) -> Result<(), HookError> {
+    unsafe {
+        DrawWorldLine
+            .initialize(
+                transmute::<u64, PrimitiveFn>(va),
+                |ez_draw: usize, from: *const HavokPosition, to: *const HavokPosition| {
+                    let Some(_detour) = enter_detour(HookId::DrawWorldLine) else {
+                        return DrawWorldLine.call(ez_draw, from, to);
+                    };
+                    guarded(HookId::DrawWorldLine, || {
+                        let (Some(from), Some(to)) = (read(from), read(to)) else {
+                            return skip_invalid_pointer(HookId::DrawWorldLine, "position");
+                        };
+                        push_command(DrawCommand::WorldLine {
+                            ez_draw,
+                            from: world_point(&from),
+                            to: world_point(&to),
+                        });
+                    });
+                    if passes_through(HookId::DrawWorldLine) {
+                        DrawWorldLine.call(ez_draw, from, to);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_draw_world_sphere(va: u64) -> Result<(), HookError> {
+    unsafe {
+        DrawWorldSphere
+            .initialize(
+                transmute::<u64, unsafe extern "C" fn(usize, *const HavokPosition, f32)>(va),
+                |ez_draw: usize, center: *const HavokPosition, radius: f32| {
+                    let Some(_detour) = enter_detour(HookId::DrawWorldSphere) else {
+                        return DrawWorldSphere.call(ez_draw, center, radius);
+                    };
+                    guarded(HookId::DrawWorldSphere, || {
+                        let Some(center) = read(center) else {
+                            return skip_invalid_pointer(HookId::DrawWorldSphere, "center");
+                        };
+                        push_command(DrawCommand::WorldSphere {
+                            ez_draw,
+                            center: world_point(&center),
+                            radius,
+                        });
+                    });
+                    if passes_through(HookId::DrawWorldSphere) {
+                        DrawWorldSphere.call(ez_draw, center, radius);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+unsafe fn install_draw_world_capsule(va: u64) -> Result<(), HookError> {
+    unsafe {
+        DrawWorldCapsule
+            .initialize(
+                transmute::<
+                    u64,
+                    unsafe extern "C" fn(usize, *const HavokPosition, *const HavokPosition, f32),
+                >(va),
+                |ez_draw: usize,
+                 from: *const HavokPosition,
+                 to: *const HavokPosition,
+                 radius: f32| {
+                    let Some(_detour) = enter_detour(HookId::DrawWorldCapsule) else {
+                        return DrawWorldCapsule.call(ez_draw, from, to, radius);
+                    };
+                    guarded(HookId::DrawWorldCapsule, || {
+                        let (Some(from), Some(to)) = (read(from), read(to)) else {
+                            return skip_invalid_pointer(HookId::DrawWorldCapsule, "position");
+                        };
+                        push_command(DrawCommand::WorldCapsule {
+                            ez_draw,
+                            from: world_point(&from),
+                            to: world_point(&to),
+                            radius,
+                        });
+                    });
+                    if passes_through(HookId::DrawWorldCapsule) {
+                        DrawWorldCapsule.call(ez_draw, from, to, radius);
+                    }
+                },
+            )
+            .map_err(HookError::Initialize)?
+            .enable()
+            .map_err(HookError::Enable)
+    }
+}
+
+type InstallFn = unsafe fn(u64) -> Result<(), HookError>;
+
+const HOOKS: [(HookId, InstallFn); HookId::ALL.len()] = [
+    (
+        HookId::DrawTextRenderRequest,
+        install_draw_text_render_request,
+    ),
+    (HookId::SetFontSize, install_set_font_size),
+    (HookId::SetTextScale, install_set_text_scale),
+    (HookId::ResetTextScale, install_reset_text_scale),
+    (HookId::DrawTextWithSize, install_draw_text_with_size),
+    (HookId::SetColor, install_set_color),
+    (HookId::DrawLine, install_draw_line),
+    (HookId::DrawRect, install_draw_rect),
+    (HookId::FillRect, install_fill_rect),
+    (HookId::DrawWorldText, install_draw_world_text),
+    (HookId::DrawWorldLine, install_draw_world_line),
+    (HookId::DrawWorldSphere, install_draw_world_sphere),
+    (HookId::DrawWorldCapsule, install_draw_world_capsule),
+];
+
+/// Installs every hook that can be installed, returning the ones that failed.
+///
+/// The overlay keeps working with whatever subset of hooks succeeded.
+pub fn install_hooks() -> Vec<(HookId, HookError)> {
+    let mut failures = Vec::new();
+    for (hook, install) in HOOKS {
+        let result = resolved_address(hook)
+            .ok_or(HookError::AddressNotFound)
+            .and_then(|resolved| {
+                check_prologue(resolved)?;
+                unsafe { install(resolved.va) }
+            });
+
+        match result {
+            Ok(()) => {}
+            Err(HookError::AddressNotFound) if hook.is_optional() => {
+                tracing::info!("{} not found, skipping it", hook.name());
+            }
+            Err(e) => {
+                tracing::error!("{} hook failed: {}", hook.name(), e);
+                INSTALL_ERRORS[hook as usize].set(e.to_string()).ok();
+                failures.push((hook, e));
+            }
+        }
+    }
+    failures
+}
+
+/// Compares the start of the function to the bytes listed for it, if any are.
+fn check_prologue(resolved: ResolvedAddress) -> Result<(), HookError> {
+    // Only RVA tables come with one, `address::resolve` doesn't use rows without a prologue
+    let Some(prologue) = resolved.prologue else {
+        return Ok(());
+    };
+    let len = address::prologue_len(prologue);
+    let ptr = resolved.va as *const u8;
+    if !memory::is_readable(ptr.cast(), len) {
+        return Err(HookError::PrologueMismatch(Vec::new()));
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    if address::matches_prologue(prologue, bytes) {
+        Ok(())
+    } else {
+        Err(HookError::PrologueMismatch(bytes.to_vec()))
+    }
+}
+
+/// Why a hook failed to install, `None` if it didn't fail.
+pub fn install_error(hook: HookId) -> Option<&'static str> {
+    INSTALL_ERRORS[hook as usize].get().map(String::as_str)
+}
+
+/// Address a hook was resolved to, `None` if resolving it failed.
+pub fn resolved_address(hook: HookId) -> Option<ResolvedAddress> {
+    RESOLVED_ADDRESSES[hook as usize].get().copied()
+}
+
+pub fn is_hook_enabled(hook: HookId) -> bool {
+    match hook {
+        HookId::DrawTextRenderRequest => DrawTextRenderRequest.is_enabled(),
+        HookId::SetFontSize => SetFontSize.is_enabled(),
+        HookId::SetTextScale => SetTextScale.is_enabled(),
+        HookId::ResetTextScale => ResetTextScale.is_enabled(),
+        HookId::DrawTextWithSize => DrawTextWithSize.is_enabled(),
+        HookId::SetColor => SetColor.is_enabled(),
+        HookId::DrawLine => DrawLine.is_enabled(),
+        HookId::DrawRect => DrawRect.is_enabled(),
+        HookId::FillRect => FillRect.is_enabled(),
+        HookId::DrawWorldText => DrawWorldText.is_enabled(),
+        HookId::DrawWorldLine => DrawWorldLine.is_enabled(),
+        HookId::DrawWorldSphere => DrawWorldSphere.is_enabled(),
+        HookId::DrawWorldCapsule => DrawWorldCapsule.is_enabled(),
+    }
+}
+
+/// Enables or disables an installed detour.
+///
+/// # Safety
+/// Same as `StaticDetour::enable`/`disable`, the target function must not be running a patched
+/// prologue while it's being swapped.
+pub unsafe fn set_hook_enabled(hook: HookId, enabled: bool) -> Result<(), retour::Error> {
+    unsafe {
+        match (hook, enabled) {
+            (HookId::DrawTextRenderRequest, true) => DrawTextRenderRequest.enable(),
+            (HookId::DrawTextRenderRequest, false) => DrawTextRenderRequest.disable(),
+            (HookId::SetFontSize, true) => SetFontSize.enable(),
+            (HookId::SetFontSize, false) => SetFontSize.disable(),
+            (HookId::SetTextScale, true) => SetTextScale.enable(),
+            (HookId::SetTextScale, false) => SetTextScale.disable(),
+            (HookId::ResetTextScale, true) => ResetTextScale.enable(),
+            (HookId::ResetTextScale, false) => ResetTextScale.disable(),
+            (HookId::DrawTextWithSize, true) => DrawTextWithSize.enable(),
+            (HookId::DrawTextWithSize, false) => DrawTextWithSize.disable(),
+            (HookId::SetColor, true) => SetColor.enable(),
+            (HookId::SetColor, false) => SetColor.disable(),
+            (HookId::DrawLine, true) => DrawLine.enable(),
+            (HookId::DrawLine, false) => DrawLine.disable(),
+            (HookId::DrawRect, true) => DrawRect.enable(),
+            (HookId::DrawRect, false) => DrawRect.disable(),
+            (HookId::FillRect, true) => FillRect.enable(),
+            (HookId::FillRect, false) => FillRect.disable(),
+            (HookId::DrawWorldText, true) => DrawWorldText.enable(),
+            (HookId::DrawWorldText, false) => DrawWorldText.disable(),
+            (HookId::DrawWorldLine, true) => DrawWorldLine.enable(),
+            (HookId::DrawWorldLine, false) => DrawWorldLine.disable(),
+            (HookId::DrawWorldSphere, true) => DrawWorldSphere.enable(),
+            (HookId::DrawWorldSphere, false) => DrawWorldSphere.disable(),
+            (HookId::DrawWorldCapsule, true) => DrawWorldCapsule.enable(),
+            (HookId::DrawWorldCapsule, false) => DrawWorldCapsule.disable(),
+        }
+    }
+}
+
+/// Enables or disables the installed hooks to match the config.
+pub fn apply_hook_settings(settings: &HookSettings) {
+    let _lock = TOGGLE_LOCK.lock().unwrap();
+    for hook in HookId::ALL {
+        let capturing = settings.enabled(hook);
+        CAPTURING[hook as usize].store(capturing, Ordering::Relaxed);
+        let enabled = capturing || !settings.native_when_disabled;
+        if is_hook_enabled(hook) == enabled {
+            continue;
+        }
+
+        match unsafe { set_hook_enabled(hook, enabled) } {
+            // The hook failed to install, that was reported already
+            Ok(()) | Err(retour::Error::NotInitialized) => {}
+            Err(e) => tracing::error!("Failed to toggle {} hook: {}", hook.name(), e),
+        }
+    }
+}
+
+/// Disables every detour that was enabled, so the game calls its original functions again.
+pub fn uninstall_hooks() {
+    let _lock = TOGGLE_LOCK.lock().unwrap();
+    for hook in HookId::ALL {
+        if !is_hook_enabled(hook) {
+            continue;
+        }
+
+        if let Err(e) = unsafe { set_hook_enabled(hook, false) } {
+            tracing::error!("Failed to disable {} hook: {}", hook.name(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hudhook::windows::Win32::System::Memory::{
+        MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_READWRITE, VirtualAlloc, VirtualFree,
+    };
+
+    use super::*;
+
+    #[test]
+    fn null_pointers_are_skipped() {
+        assert_eq!(read_text_box(std::ptr::null()), Some((None, None)));
+        assert!(read::<HavokPosition>(std::ptr::null()).is_none());
+        assert!(u16_ptr_to_string(std::ptr::null()).is_none());
+    }
+
+    #[test]
+    fn text_box_defaults() {
+        let text_box = [0.0f32, -1.0];
+        assert_eq!(
+            read_text_box(text_box.as_ptr()),
+            Some((Some(BASE_IMGUI_FONT_SIZE_PX), None))
+        );
+        let text_box = [24.0f32, 300.0];
+        assert_eq!(
+            read_text_box(text_box.as_ptr()),
+            Some((Some(24.0), Some(300.0)))
+        );
+    }
+
+    #[test]
+    fn text_stops_at_the_terminator() {
+        let text = "HP 100\0ignored".encode_utf16().collect::<Vec<_>>();
+        assert_eq!(u16_ptr_to_string(text.as_ptr()).as_deref(), Some("HP 100"));
+    }
+
+    #[test]
+    fn unterminated_text_stops_at_the_end_of_readable_memory() {
+        // A single committed page, the rest of the allocation granule isn't readable
+        const PAGE: usize = 4096;
+        let ptr = unsafe { VirtualAlloc(None, PAGE, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE) }
+            as *mut u16;
+        assert!(!ptr.is_null());
+        let units = PAGE / 2;
+        assert!(units < MAX_TEXT_LENGTH.load(Ordering::Relaxed));
+        unsafe { std::slice::from_raw_parts_mut(ptr, units) }.fill(u16::from(b'a'));
+
+        let text = u16_ptr_to_string(ptr);
+
+        unsafe { VirtualFree(ptr.cast(), 0, MEM_RELEASE) }.unwrap();
+        assert_eq!(text.as_deref(), Some("a".repeat(units).as_str()));
+    }
+
+    #[test]
+    fn unterminated_text_stops_at_the_max_length() {
+        let max_length = MAX_TEXT_LENGTH.load(Ordering::Relaxed);
+        let text = vec![u16::from(b'a'); max_length * 2];
+        assert_eq!(
+            u16_ptr_to_string(text.as_ptr()).map(|text| text.len()),
+            Some(max_length)
+        );
+    }
+}
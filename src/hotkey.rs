@@ -0,0 +1,74 @@
+use hudhook::windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, VIRTUAL_KEY, VK_DELETE, VK_END, VK_F1, VK_HOME, VK_INSERT, VK_NEXT, VK_PAUSE,
+    VK_PRIOR, VK_SCROLL,
+};
+
+/// A key polled once per frame that fires on the frame it goes down.
+#[derive(Debug)]
+pub struct Hotkey {
+    key: VIRTUAL_KEY,
+    was_down: bool,
+}
+
+impl Hotkey {
+    pub fn new(key: VIRTUAL_KEY) -> Self {
+        Self {
+            key,
+            was_down: false,
+        }
+    }
+
+    /// Returns `true` if the key was pressed since the last poll.
+    pub fn poll(&mut self) -> bool {
+        let state = unsafe { GetAsyncKeyState(self.key.0 as i32) };
+        let down = (state as u16) & 0x8000 != 0;
+        let pressed = down && !self.was_down;
+        self.was_down = down;
+        pressed
+    }
+}
+
+/// Parses a key name like `F10`, `Insert` or `K`, or a virtual-key code like `0x79`.
+pub fn parse_key(value: &str) -> Option<VIRTUAL_KEY> {
+    let value = value.trim();
+
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        return u16::from_str_radix(hex, 16).ok().map(VIRTUAL_KEY);
+    }
+
+    if let Some(number) = value
+        .strip_prefix(['F', 'f'])
+        .and_then(|n| n.parse::<u16>().ok())
+    {
+        return (1..=24)
+            .contains(&number)
+            .then(|| VIRTUAL_KEY(VK_F1.0 + number - 1));
+    }
+
+    let named = match value.to_ascii_lowercase().as_str() {
+        "insert" => Some(VK_INSERT),
+        "delete" => Some(VK_DELETE),
+        "home" => Some(VK_HOME),
+        "end" => Some(VK_END),
+        "pageup" => Some(VK_PRIOR),
+        "pagedown" => Some(VK_NEXT),
+        "pause" => Some(VK_PAUSE),
+        "scrolllock" => Some(VK_SCROLL),
+        _ => None,
+    };
+    if named.is_some() {
+        return named;
+    }
+
+    // Letters and digits share their virtual-key codes with their uppercase ASCII value
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphanumeric() => {
+            Some(VIRTUAL_KEY(c.to_ascii_uppercase() as u16))
+        }
+        _ => value.parse::<u16>().ok().map(VIRTUAL_KEY),
+    }
+}
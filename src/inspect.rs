@@ -0,0 +1,83 @@
+use std::time::Instant;
+
+use hudhook::imgui::{MouseButton, Ui};
+
+use crate::DebugTextRender;
+use crate::clipboard::set_clipboard_text;
+
+impl DebugTextRender {
+    /// Shows the details of the text under the mouse in a tooltip while inspecting, and copies it
+    /// to the clipboard when it's clicked.
+    pub(crate) fn draw_inspector(&mut self, ui: &Ui) {
+        // Clicking the context menu shouldn't copy the text under it
+        if self.block_target.is_some() {
+            return;
+        }
+        let mouse = ui.io().mouse_pos;
+        // The text drawn last is on top
+        let Some((entry, (min, size))) = self
+            .retained
+            .iter()
+            .rev()
+            .filter(|entry| self.categories.is_shown(entry.category))
+            .map(|entry| (entry, self.text_bounds(ui, entry)))
+            .find(|(_, (min, size))| {
+                (min[0]..=min[0] + size[0]).contains(&mouse[0])
+                    && (min[1]..=min[1] + size[1]).contains(&mouse[1])
+            })
+        else {
+            return;
+        };
+
+        ui.get_foreground_draw_list()
+            .add_rect(
+                min,
+                [min[0] + size[0], min[1] + size[1]],
+                self.color_space().rgb(255, 255, 0),
+            )
+            .build();
+        ui.tooltip(|| {
+            ui.text(&entry.text);
+            ui.separator();
+            ui.text(format!(
+                "Position: {:.1}, {:.1}",
+                entry.position[0], entry.position[1]
+            ));
+            ui.text(format!(
+                "Font size: {:.1}",
+                entry.font_size * self.font_scale
+            ));
+            match entry.caller {
+                Some(rva) => ui.text(format!("Caller: {rva:#x}")),
+                None => ui.text("Caller: not looked up"),
+            }
+            if let Some(depth) = entry.depth {
+                ui.text(format!(
+                    "Depth: {:.2}, opacity {:.2}",
+                    depth, entry.distance_alpha
+                ));
+            }
+            ui.text(format!(
+                "Captured: frame {} at {:.3}s",
+                entry.frame,
+                entry.time_us as f64 / 1_000_000.0
+            ));
+            ui.text_disabled("Click to copy, right-click to hide");
+        });
+
+        if ui.is_mouse_clicked(MouseButton::Left) {
+            let text = entry.text.clone();
+            let notice = match set_clipboard_text(&text) {
+                Ok(()) => "copied".to_string(),
+                Err(e) => {
+                    tracing::error!("Failed to copy {:?} to the clipboard: {}", text, e);
+                    "copying failed, see the log".to_string()
+                }
+            };
+            self.notice = Some((notice, Instant::now()));
+        } else if ui.is_mouse_clicked(MouseButton::Right) {
+            let (text, caller) = (entry.text.clone(), entry.caller);
+            self.open_block_menu(&text, caller);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Strings kept before the pool starts over, text still queued keeps its own reference.
+const MAX_POOL_ENTRIES: usize = 4096;
+
+/// Decoded strings by the hash of the UTF-16 they were decoded from.
+static POOL: LazyLock<Mutex<HashMap<u64, Arc<str>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Decodes UTF-16 like `String::from_utf16_lossy`, sharing the string decoded the last time the
+/// same code units came in. The game draws most of its debug text again every frame, so this
+/// mostly avoids decoding and allocating.
+///
+/// The second value is `true` when the string was decoded for this call.
+pub fn intern_utf16(units: &[u16]) -> (Arc<str>, bool) {
+    let mut hasher = DefaultHasher::new();
+    units.hash(&mut hasher);
+    let key = hasher.finish();
+
+    let mut pool = POOL.lock().unwrap();
+    // Strings with invalid UTF-16 don't encode back to the same units and are decoded every time
+    if let Some(text) = pool.get(&key)
+        && text.encode_utf16().eq(units.iter().copied())
+    {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return (text.clone(), false);
+    }
+
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    let text: Arc<str> = String::from_utf16_lossy(units).into();
+    if pool.len() >= MAX_POOL_ENTRIES {
+        pool.clear();
+    }
+    pool.insert(key, text.clone());
+    (text, true)
+}
+
+/// Strings shared from the pool and strings decoded since the game started.
+pub fn pool_stats() -> (u64, u64) {
+    (HITS.load(Ordering::Relaxed), MISSES.load(Ordering::Relaxed))
+}
@@ -0,0 +1,40 @@
+use hudhook::imgui::{DrawListMut, Ui, sys};
+use serde::{Deserialize, Serialize};
+
+/// Which of imgui's draw lists something is drawn into. Either one ends up on top of the game's
+/// frame, HUD included, since the overlay draws after the game is done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layer {
+    /// On top of everything, imgui windows included.
+    Foreground,
+    /// Under imgui windows, the overlay's own panels included. Panels on this layer stay behind
+    /// the other windows.
+    Background,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 2] = [Layer::Foreground, Layer::Background];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layer::Foreground => "foreground",
+            Layer::Background => "background",
+        }
+    }
+
+    pub fn draw_list(self, ui: &Ui) -> DrawListMut<'_> {
+        match self {
+            Layer::Foreground => ui.get_foreground_draw_list(),
+            Layer::Background => ui.get_background_draw_list(),
+        }
+    }
+
+    /// The draw list for the calls imgui-rs has no wrapper for.
+    pub fn raw_draw_list(self) -> *mut sys::ImDrawList {
+        match self {
+            Layer::Foreground => unsafe { sys::igGetForegroundDrawList() },
+            Layer::Background => unsafe { sys::igGetBackgroundDrawList() },
+        }
+    }
+}
@@ -1,8 +1,25 @@
-use hudhook::windows::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
-use tracing_panic::panic_hook;
+use std::{
+    any::Any,
+    backtrace::{Backtrace, BacktraceStatus},
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        LazyLock, Mutex, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::console::{is_console_open, open_console};
+use crate::crash::{RecentLogLine, write_crash_report};
+use crate::toast::{Severity, notify};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::{Directive, EnvFilter, LevelFilter, filter_fn};
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{Layer, layer::SubscriberExt};
+use tracing_subscriber::{Layer, Registry, layer::SubscriberExt, reload};
 
+use hudhook::windows::Win32::System::SystemInformation::GetLocalTime;
 use hudhook::windows::Win32::UI::WindowsAndMessaging::{
     MB_ICONERROR, MB_OK, MB_TASKMODAL, MessageBoxW,
 };
@@ -24,39 +41,339 @@ pub fn show_error_message_box(message: String, title: &str) {
     }
 }
 
+/// Message a panic was raised with, `panic!` with arguments and `expect` carry a `String`.
+fn panic_reason(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Unknown")
+}
+
 pub fn custom_panic_hook(panic_info: &std::panic::PanicHookInfo) {
-    let message;
-    let reason = panic_info.payload().downcast_ref::<&str>();
-
-    if let Some(location) = panic_info.location() {
-        message = format!(
-            "A panic occurred at {}:{}\nReason: {}",
-            location.file(),
-            location.line(),
-            reason.map_or("Unknown", |v| v),
-        );
-    } else {
-        message = format!(
-            "A panic occurred\nReason: {}",
-            reason.map_or("Unknown", |v| v)
-        );
+    let reason = panic_reason(panic_info.payload());
+    let location = panic_info.location().map_or_else(
+        || "an unknown location".to_string(),
+        |location| format!("{}:{}", location.file(), location.line()),
+    );
+    let thread = std::thread::current();
+    let thread_name = thread.name().unwrap_or("<unnamed>");
+
+    // Captured even without RUST_BACKTRACE, it's the most useful part of the crash report
+    let backtrace = Backtrace::force_capture();
+    let backtrace = match backtrace.status() {
+        BacktraceStatus::Captured => backtrace.to_string(),
+        _ => "unsupported".to_string(),
+    };
+    let mut panic = format!("Thread '{thread_name}' panicked at {location}: {reason}");
+    let mut message = format!("A panic occurred at {location}\nReason: {reason}");
+    // Poisoned before the crash report is written, it lists the hooks
+    if let Some(hook) = crate::hooks::poison_running_hook() {
+        panic.push_str(&format!("\nIn the {} hook", hook.name()));
+        message.push_str(&format!("\nHook: {}", hook.name()));
     }
+    tracing::error!("{panic}\n{backtrace}");
 
+    // Written next to the log, with the game version, the hooks, the config and what happened
+    // right before
+    let report = LOG_PATH
+        .get()
+        .and_then(|path| path.parent())
+        .map(|dir| write_crash_report(dir, &panic, &backtrace));
+    match report {
+        Some(Ok(path)) => {
+            tracing::error!("Wrote a crash report to {}", path.display());
+            message.push_str(&format!(
+                "\n\nA crash report was written to {}, please attach it when reporting this.",
+                path.display()
+            ));
+        }
+        Some(Err(e)) => {
+            tracing::error!("Failed to write a crash report: {}", e);
+            if let Some(path) = LOG_PATH.get() {
+                message.push_str(&format!("\n\nDetails were written to {}", path.display()));
+            }
+        }
+        None => {}
+    }
+
+    // Panics in the overlay's own code unwind to the guard around it, the game keeps running
+    // without the overlay
+    if crate::guard::in_guard() {
+        if crate::guard::disable_overlay() {
+            tracing::error!("The overlay is disabled until the game restarts");
+            message.push_str("\n\nThe overlay is disabled until the game restarts.");
+            // The overlay is gone, so this can't be a toast. The panicking thread is the game's,
+            // notify doesn't make it wait on the message box
+            notify(Severity::Fatal, message);
+        }
+        return;
+    }
+
+    // The log is written by another thread, make sure the report is in it before aborting
+    flush_logging();
+    // Shown right here, the process is gone as soon as it's closed
     show_error_message_box(message, "Debug Text View Error");
-    panic_hook(panic_info);
     std::process::abort();
 }
 
-pub fn setup_logging() {
-    // Try to attach to the parent console if it exists
-    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) }.ok();
+pub const LOG_FILE_NAME: &str = "textrender.log";
+
+/// Keeps the thread writing the log file alive, dropping it flushes what's still queued.
+static LOG_WRITER_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+/// Path of the log file, set once it's opened.
+static LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+/// Swaps the filter both the file and the console log go through.
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// `RUST_LOG` directives, then the configured level, then the configured per-target directives,
+/// so `directives` win over the level for their targets.
+fn log_filter(level: LevelFilter, directives: &[String]) -> EnvFilter {
+    directives
+        .iter()
+        .filter_map(|directive| directive.parse::<Directive>().ok())
+        .fold(
+            EnvFilter::from_default_env().add_directive(level.into()),
+            EnvFilter::add_directive,
+        )
+}
+
+/// Changes what's logged from now on, `directives` like `textrender::hooks=trace`. Invalid
+/// directives are skipped, the config checks them when it's loaded.
+pub fn set_log_filter(level: LevelFilter, directives: &[String]) {
+    if let Some(handle) = LOG_FILTER.get()
+        && let Err(e) = handle.reload(log_filter(level, directives))
+    {
+        tracing::error!("Failed to change the log filter: {}", e);
+    }
+}
+
+/// The local time as `<date>_<time>`, for file names that sort oldest first.
+pub fn local_timestamp() -> String {
+    let now = unsafe { GetLocalTime() };
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond
+    )
+}
+
+/// The log file, renamed with the time appended once it grows past `max_size` so a new one can
+/// be started.
+///
+/// Only the thread writing the log out writes to it, so a rotation happens between two lines and
+/// no line is split across files.
+struct RotatingLog {
+    path: PathBuf,
+    /// `None` only while it's being swapped.
+    file: Option<File>,
+    /// Bytes in the current file.
+    size: u64,
+    /// Bytes a file may grow to, 0 never rotates it.
+    max_size: u64,
+    /// Rotated files kept, the oldest are deleted.
+    max_files: usize,
+}
+
+impl RotatingLog {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file: Some(file),
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    /// Name the file is rotated to, the local time so the rotated files sort oldest first.
+    fn rotated_path(&self) -> PathBuf {
+        let stamp = local_timestamp();
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        // Two rotations in the same second get a counter
+        (0..)
+            .map(|index| match index {
+                0 => self.path.with_file_name(format!("{name}.{stamp}")),
+                _ => self.path.with_file_name(format!("{name}.{stamp}.{index}")),
+            })
+            .find(|path| !path.exists())
+            .unwrap()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = self.rotated_path();
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        // The file has to be closed before Windows lets it be renamed
+        let renamed = std::fs::rename(&self.path, &rotated);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = file.metadata()?.len();
+        renamed?;
+
+        let line = format!(
+            "Continued from {}, the log was rotated at {} bytes\n",
+            rotated.display(),
+            self.max_size
+        );
+        file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        self.file = Some(file);
+        self.delete_old_files();
+        Ok(())
+    }
+
+    fn delete_old_files(&self) {
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name()) else {
+            return;
+        };
+        let prefix = format!("{}.", name.to_string_lossy());
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut rotated: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|file_name| file_name.to_string_lossy().starts_with(&prefix))
+            })
+            .collect();
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for path in &rotated[..excess] {
+            if let Err(e) = std::fs::remove_file(path) {
+                eprintln!("Failed to delete {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+impl Write for RotatingLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.max_size != 0
+            && self.size > 0
+            && self.size + buf.len() as u64 > self.max_size
+            && let Err(e) = self.rotate()
+        {
+            eprintln!("Failed to rotate {}: {}", self.path.display(), e);
+            // Tried again once another max_size was written
+            self.size = 0;
+        }
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?,
+            ),
+        };
+        let written = file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().map_or(Ok(()), Write::flush)
+    }
+}
+
+/// Logs to `textrender.log` in `log_dir`, and to the console while one is open.
+///
+/// The file is appended to, the game thread only queues lines and a background thread writes
+/// them out. Past `max_size` bytes it's rotated, keeping `max_files` old ones. The console is
+/// opened right away when `console` is set. The level and directives can be changed later with
+/// [`set_log_filter`].
+pub fn setup_logging(
+    level: LevelFilter,
+    directives: &[String],
+    log_dir: Option<&Path>,
+    max_size: u64,
+    max_files: usize,
+    console: bool,
+) {
+    let file_appender = log_dir.and_then(|dir| {
+        let path = dir.join(LOG_FILE_NAME);
+        match RotatingLog::open(path.clone(), max_size, max_files) {
+            Ok(appender) => {
+                LOG_PATH.set(path).ok();
+                Some(appender)
+            }
+            Err(e) => {
+                eprintln!("Failed to open {}: {}", path.display(), e);
+                None
+            }
+        }
+    });
+    let file_log = file_appender.map(|appender| {
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        *LOG_WRITER_GUARD.lock().unwrap() = Some(guard);
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(writer)
+    });
 
-    let filter = tracing_subscriber::filter::EnvFilter::from_default_env()
-        .add_directive(tracing_subscriber::filter::LevelFilter::DEBUG.into());
+    // Installed either way, so a console opened later shows the log too
+    let console_log = tracing_subscriber::fmt::layer()
+        .pretty()
+        .with_filter(filter_fn(|_| is_console_open()));
 
-    let stdout_log = tracing_subscriber::fmt::layer().pretty();
+    let (filter, handle) = reload::Layer::new(log_filter(level, directives));
+    LOG_FILTER.set(handle).ok();
+    // The last lines go into the crash report
+    let recent_log = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(RecentLogLine::default);
 
     tracing_subscriber::registry()
-        .with(stdout_log.with_filter(filter))
+        .with(filter)
+        .with(file_log)
+        .with(console_log)
+        .with(recent_log)
         .init();
+
+    if console {
+        open_console();
+    }
+}
+
+pub fn flush_logging() {
+    LOG_WRITER_GUARD.lock().unwrap().take();
+    std::io::stdout().flush().ok();
+}
+
+/// Time rate limits are measured from.
+static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// Lets a message through at most once per interval, shared by every thread that logs it.
+pub struct RateLimit {
+    interval: Duration,
+    /// Milliseconds since `EPOCH` the last message went through at, `u64::MAX` before the first.
+    last_ms: AtomicU64,
+}
+
+impl RateLimit {
+    pub const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_ms: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    /// Returns `true` if the caller should log now.
+    pub fn allow(&self) -> bool {
+        let now = EPOCH.elapsed().as_millis() as u64;
+        let last = self.last_ms.load(Ordering::Relaxed);
+        // Only the thread that wins the exchange logs
+        (last == u64::MAX || now.saturating_sub(last) >= self.interval.as_millis() as u64)
+            && self
+                .last_ms
+                .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+    }
 }
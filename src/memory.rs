@@ -0,0 +1,51 @@
+use std::{ffi::c_void, mem};
+
+use hudhook::windows::Win32::System::Memory::{
+    MEM_COMMIT, MEMORY_BASIC_INFORMATION, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE,
+    PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS, PAGE_READONLY, PAGE_READWRITE,
+    PAGE_WRITECOPY, VirtualQuery,
+};
+
+/// Number of bytes starting at `ptr` that can be read, checked up to `max_len`.
+///
+/// Walks the committed regions following `ptr` until one isn't readable.
+pub fn readable_len(ptr: *const c_void, max_len: usize) -> usize {
+    let start = ptr as usize;
+    let mut readable = 0;
+    while readable < max_len {
+        let mut info = MEMORY_BASIC_INFORMATION::default();
+        let written = unsafe {
+            VirtualQuery(
+                Some((start + readable) as *const c_void),
+                &mut info,
+                mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+            )
+        };
+        if written == 0 || info.State != MEM_COMMIT || !is_readable_protection(info.Protect.0) {
+            break;
+        }
+
+        let region_end = info.BaseAddress as usize + info.RegionSize;
+        readable = region_end - start;
+    }
+    readable.min(max_len)
+}
+
+/// Whether `len` bytes starting at `ptr` can be read, `false` for null.
+pub fn is_readable(ptr: *const c_void, len: usize) -> bool {
+    !ptr.is_null() && readable_len(ptr, len) >= len
+}
+
+fn is_readable_protection(protect: u32) -> bool {
+    if protect & (PAGE_GUARD.0 | PAGE_NOACCESS.0) != 0 {
+        return false;
+    }
+
+    let readable = PAGE_READONLY.0
+        | PAGE_READWRITE.0
+        | PAGE_WRITECOPY.0
+        | PAGE_EXECUTE_READ.0
+        | PAGE_EXECUTE_READWRITE.0
+        | PAGE_EXECUTE_WRITECOPY.0;
+    protect & readable != 0
+}
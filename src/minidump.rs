@@ -0,0 +1,207 @@
+use std::{
+    backtrace::Backtrace,
+    fs::File,
+    os::windows::io::AsRawHandle,
+    path::{Path, PathBuf},
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use hudhook::windows::Win32::{
+    Foundation::{
+        EXCEPTION_ACCESS_VIOLATION, EXCEPTION_ILLEGAL_INSTRUCTION, EXCEPTION_INT_DIVIDE_BY_ZERO,
+        EXCEPTION_PRIV_INSTRUCTION, EXCEPTION_STACK_OVERFLOW, HANDLE, HINSTANCE,
+    },
+    System::{
+        Diagnostics::Debug::{
+            AddVectoredExceptionHandler, EXCEPTION_CONTINUE_SEARCH, EXCEPTION_POINTERS,
+            MINIDUMP_EXCEPTION_INFORMATION, MINIDUMP_TYPE, MiniDumpWithIndirectlyReferencedMemory,
+            MiniDumpWithThreadInfo, MiniDumpWithUnloadedModules, MiniDumpWriteDump,
+        },
+        Threading::{GetCurrentProcess, GetCurrentProcessId, GetCurrentThreadId},
+    },
+};
+use pelite::pe64::{Pe, PeView};
+
+use crate::crash::write_crash_report;
+use crate::logging::{flush_logging, show_error_message_box};
+
+/// Start and end of the DLL's image, faults outside of it are left to the game.
+static MODULE_RANGE: OnceLock<(usize, usize)> = OnceLock::new();
+/// Directory the dump and the report are written to.
+static DUMP_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// Only the first fault is dumped, the handler sees it again if the game rethrows it.
+static DUMPED: AtomicBool = AtomicBool::new(false);
+
+/// Writes a minidump and a crash report when code in the DLL faults, e.g. an access violation
+/// in a detour, which never reaches the panic hook.
+///
+/// The handler only looks at faults inside the DLL's own image and always passes them on, so
+/// the game's crash handling still runs after it.
+pub fn install_fault_handler(hinst: HINSTANCE, dir: PathBuf) {
+    let base = hinst.0 as usize;
+    let size = unsafe { PeView::module(base as *const u8) }
+        .optional_header()
+        .SizeOfImage as usize;
+    MODULE_RANGE.set((base, base + size)).ok();
+    DUMP_DIR.set(dir).ok();
+    let handler = unsafe { AddVectoredExceptionHandler(1, Some(fault_handler)) };
+    if handler.is_null() {
+        tracing::error!("Failed to install the fault handler");
+    } else {
+        tracing::info!(
+            "Faults in {:#x}..{:#x} are written to a minidump",
+            base,
+            base + size
+        );
+    }
+}
+
+unsafe extern "system" fn fault_handler(info: *mut EXCEPTION_POINTERS) -> i32 {
+    let Some(record) =
+        (unsafe { info.as_ref() }).and_then(|info| unsafe { info.ExceptionRecord.as_ref() })
+    else {
+        return EXCEPTION_CONTINUE_SEARCH;
+    };
+    // Breakpoints, C++ exceptions and the like are the game's or a debugger's business
+    let fatal = [
+        EXCEPTION_ACCESS_VIOLATION,
+        EXCEPTION_ILLEGAL_INSTRUCTION,
+        EXCEPTION_INT_DIVIDE_BY_ZERO,
+        EXCEPTION_PRIV_INSTRUCTION,
+        EXCEPTION_STACK_OVERFLOW,
+    ];
+    let address = record.ExceptionAddress as usize;
+    let Some(&(start, end)) = MODULE_RANGE.get() else {
+        return EXCEPTION_CONTINUE_SEARCH;
+    };
+    if !fatal.contains(&record.ExceptionCode)
+        || !(start..end).contains(&address)
+        || DUMPED.swap(true, Ordering::AcqRel)
+    {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+    let Some(dir) = DUMP_DIR.get() else {
+        return EXCEPTION_CONTINUE_SEARCH;
+    };
+
+    // The faulting thread may be out of stack, everything past this is done on another one. The
+    // backtrace can only be taken here, it's skipped when there's no stack left for it
+    let thread_id = unsafe { GetCurrentThreadId() };
+    let backtrace = if record.ExceptionCode == EXCEPTION_STACK_OVERFLOW {
+        None
+    } else {
+        Some(Backtrace::force_capture())
+    };
+    let info = info as usize;
+    let dir: &'static Path = dir;
+    std::thread::spawn(move || {
+        report_fault(
+            dir,
+            info as *mut EXCEPTION_POINTERS,
+            thread_id,
+            start,
+            backtrace,
+        )
+    })
+    .join()
+    .ok();
+    EXCEPTION_CONTINUE_SEARCH
+}
+
+/// Logs the fault, writes the crash report and the minidump and tells the player, on a thread
+/// other than the faulting one. `start` is the DLL's base address.
+fn report_fault(
+    dir: &Path,
+    info: *mut EXCEPTION_POINTERS,
+    thread_id: u32,
+    start: usize,
+    backtrace: Option<Backtrace>,
+) {
+    // The faulting thread waits for this one, the record stays valid until it returns
+    let record = unsafe { &*(*info).ExceptionRecord };
+    let address = record.ExceptionAddress as usize;
+    let mut fault = format!(
+        "Exception {:#010x} at {:#x} (textrender+{:#x}) on thread {}",
+        record.ExceptionCode.0,
+        address,
+        address - start,
+        thread_id
+    );
+    if record.ExceptionCode == EXCEPTION_ACCESS_VIOLATION && record.NumberParameters >= 2 {
+        let access = match record.ExceptionInformation[0] {
+            0 => "reading",
+            1 => "writing",
+            _ => "executing",
+        };
+        fault.push_str(&format!(
+            ", {} {:#x}",
+            access, record.ExceptionInformation[1]
+        ));
+    }
+    tracing::error!("{}", fault);
+
+    let backtrace = match backtrace {
+        Some(backtrace) => backtrace.to_string(),
+        None => "not taken, the thread ran out of stack".to_string(),
+    };
+    let written = write_crash_report(dir, &fault, &backtrace).and_then(|report| {
+        let dump = report.with_extension("dmp");
+        write_minidump(&dump, thread_id, info)?;
+        Ok((report, dump))
+    });
+
+    let message = match written {
+        Ok((report, dump)) => {
+            tracing::error!(
+                "Wrote a crash report to {} and a minidump to {}",
+                report.display(),
+                dump.display()
+            );
+            format!(
+                "The mod crashed the game.\n\nA crash report was written to {} and a minidump to \
+                 {}, please attach both when reporting this.",
+                report.display(),
+                dump.display()
+            )
+        }
+        Err(e) => {
+            tracing::error!("Failed to write the crash report: {}", e);
+            "The mod crashed the game, writing the crash report failed.".to_string()
+        }
+    };
+    flush_logging();
+    show_error_message_box(message, "Debug Text View Error");
+}
+
+fn write_minidump(
+    path: &Path,
+    thread_id: u32,
+    exception: *mut EXCEPTION_POINTERS,
+) -> std::io::Result<()> {
+    let file = File::create(path)?;
+    let exception = MINIDUMP_EXCEPTION_INFORMATION {
+        ThreadId: thread_id,
+        ExceptionPointers: exception,
+        ClientPointers: false.into(),
+    };
+    let dump_type = MINIDUMP_TYPE(
+        MiniDumpWithIndirectlyReferencedMemory.0
+            | MiniDumpWithThreadInfo.0
+            | MiniDumpWithUnloadedModules.0,
+    );
+    unsafe {
+        MiniDumpWriteDump(
+            GetCurrentProcess(),
+            GetCurrentProcessId(),
+            HANDLE(file.as_raw_handle() as isize),
+            dump_type,
+            Some(&exception),
+            None,
+            None,
+        )
+    }
+    .map_err(std::io::Error::from)
+}
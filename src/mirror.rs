@@ -0,0 +1,107 @@
+use std::{
+    ffi::c_void,
+    sync::atomic::{AtomicU32, Ordering, fence},
+    time::{Duration, Instant},
+};
+
+use hudhook::windows::Win32::{
+    Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE},
+    System::Memory::{
+        CreateFileMappingW, FILE_MAP_WRITE, MEMORY_MAPPED_VIEW_ADDRESS, MapViewOfFile,
+        PAGE_READWRITE, UnmapViewOfFile,
+    },
+};
+use hudhook::windows::core::HSTRING;
+
+/// Bytes in front of the text: the sequence number and the length of the text.
+const HEADER_SIZE: usize = 8;
+
+/// The text shown on screen, copied into a named shared memory region for other programs like
+/// OBS to read.
+///
+/// The region starts with a little endian `u32` sequence number that's odd while the snapshot is
+/// written, followed by a `u32` length and that many bytes of UTF-8, one line per text. A reader
+/// retries when the sequence is odd or changed while it copied the text.
+pub struct SharedTextMirror {
+    mapping: HANDLE,
+    /// Address of the mapped view, kept as a number like the fonts so the render loop stays `Send`.
+    view: usize,
+    size: usize,
+    last_update: Option<Instant>,
+}
+
+impl SharedTextMirror {
+    /// Creates the mapping `name` of `size` bytes, header included, or opens it if another
+    /// process already did.
+    pub fn create(name: &str, size: usize) -> hudhook::windows::core::Result<Self> {
+        let size = size.max(HEADER_SIZE);
+        let mapping = unsafe {
+            CreateFileMappingW(
+                INVALID_HANDLE_VALUE,
+                None,
+                PAGE_READWRITE,
+                (size as u64 >> 32) as u32,
+                size as u32,
+                &HSTRING::from(name),
+            )?
+        };
+        let view = unsafe { MapViewOfFile(mapping, FILE_MAP_WRITE, 0, 0, size) };
+        if view.Value.is_null() {
+            let e = hudhook::windows::core::Error::from_win32();
+            unsafe { CloseHandle(mapping).ok() };
+            return Err(e);
+        }
+        Ok(Self {
+            mapping,
+            view: view.Value as usize,
+            size,
+            last_update: None,
+        })
+    }
+
+    /// Writes `texts` as the current snapshot, at most once per `interval`. Text that doesn't fit
+    /// is cut off at a character boundary.
+    pub fn update<'a>(&mut self, texts: impl Iterator<Item = &'a str>, interval: Duration) {
+        if self
+            .last_update
+            .is_some_and(|last_update| last_update.elapsed() < interval)
+        {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+
+        let mut snapshot = texts.collect::<Vec<_>>().join("\n");
+        let capacity = self.size - HEADER_SIZE;
+        if snapshot.len() > capacity {
+            let mut end = capacity;
+            while !snapshot.is_char_boundary(end) {
+                end -= 1;
+            }
+            snapshot.truncate(end);
+        }
+
+        let base = self.view as *mut u8;
+        // The mapping is page aligned, the header fields are too
+        let sequence = unsafe { &*(base as *const AtomicU32) };
+        let length = unsafe { &*(base.add(4) as *const AtomicU32) };
+        sequence.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        length.store(snapshot.len() as u32, Ordering::Relaxed);
+        unsafe {
+            std::ptr::copy_nonoverlapping(snapshot.as_ptr(), base.add(HEADER_SIZE), snapshot.len())
+        };
+        sequence.fetch_add(1, Ordering::Release);
+    }
+}
+
+impl Drop for SharedTextMirror {
+    fn drop(&mut self) {
+        unsafe {
+            UnmapViewOfFile(MEMORY_MAPPED_VIEW_ADDRESS {
+                Value: self.view as *mut c_void,
+            })
+            .ok();
+            CloseHandle(self.mapping).ok();
+        }
+    }
+}
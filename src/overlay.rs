@@ -0,0 +1,2547 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    ffi::c_char,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, LazyLock, Once, RwLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use eldenring::cs::{CSWindowImp, CSWindowType};
+use eldenring_util::{singleton::get_instance, system::wait_for_system_init};
+
+use crate::address::HookId;
+use crate::alert::AlertList;
+use crate::aspect::AspectMode;
+use crate::autohide::{GameState, hiding_state};
+use crate::backend::{GraphicsApi, InitTimeout, set_hooked_backend};
+use crate::blocklist::BlockTarget;
+use crate::caller::set_capture_callers;
+use crate::category::Categories;
+use crate::command::DrawCommand;
+use crate::config::{
+    AutoHideSettings, CONFIG_FILE_NAME, Config, ConfigWatcher, ENABLED_ENV_VAR, HookSettings,
+    LayerSettings, WorldShapeSettings, dll_directory,
+};
+use crate::conflicts::find_hudhook_overlays;
+use crate::diff::{Change, TextDiff};
+use crate::dump::{DumpFormat, TextDump, dump_without_renderer};
+use crate::fade::TextFades;
+use crate::filter::set_filter;
+use crate::flags::{FlagMode, GameFlags};
+use crate::focus::{UnfocusedMode, is_game_focused};
+use crate::glyphs::{GlyphRange, custom_ranges};
+use crate::hdr::{ColorSpace, HdrMode, HdrOutput, detect_output};
+use crate::highlight::Highlighter;
+use crate::history::TextHistory;
+use crate::hooks::{
+    apply_hook_settings, install_hooks, is_hook_enabled, resolve_hooks, resolved_summary,
+    set_max_text_length, set_passthrough_native, uninstall_hooks,
+};
+use crate::hotkey::{Hotkey, parse_key};
+use crate::logging::{RateLimit, custom_panic_hook, flush_logging, set_log_filter, setup_logging};
+use crate::mirror::SharedTextMirror;
+use crate::panel::{Presentation, SourcePanel};
+use crate::pipe::PipeServer;
+use crate::placement::{OffscreenMode, TextAnchor};
+use crate::processor::{
+    BASE_IMGUI_FONT_SIZE_PX, CommandProcessor, ScreenInfo, ShapeEntry, ShapeKind, TextEntry,
+    TextOrigin, WindowMetrics, baked_font_index, stack_overlapping,
+};
+use crate::stats::SourceStats;
+use crate::target::{is_target_loaded, select_target, target_program};
+use crate::text::TextId;
+use crate::toast::{Severity, Toasts, notify};
+use crate::watch::WatchList;
+use crate::world::{
+    Camera, WorldShape, WorldSmoothing, distance_alpha, distance_scale, project_shape,
+};
+use crate::{console, crash, dedup, guard, minidump, replay};
+use crossbeam_queue::ArrayQueue;
+use hudhook::{
+    Hudhook, ImguiRenderLoop, MessageFilter, RenderContext,
+    imgui::{
+        self, DrawListMut, FontGlyphRanges, FontId, ImColor32, Io, Ui, internal::RawCast, sys,
+    },
+    windows::Win32::{
+        Foundation::{HINSTANCE, HWND},
+        System::{
+            LibraryLoader::DisableThreadLibraryCalls,
+            SystemServices::{DLL_PROCESS_ATTACH, DLL_PROCESS_DETACH},
+        },
+        UI::{
+            Input::KeyboardAndMouse::{
+                VK_F1, VK_F2, VK_F3, VK_F4, VK_F5, VK_F6, VK_F7, VK_F8, VK_F9, VK_F10, VK_F11,
+                VK_PAUSE,
+            },
+            WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN},
+        },
+    },
+};
+use hudhook::{
+    hooks::{dx11::ImguiDx11Hooks, dx12::ImguiDx12Hooks},
+    imgui::Context,
+    mh::{MH_ApplyQueued, MH_QueueEnableHook, MH_STATUS, MH_Uninitialize},
+};
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::filter::{Directive, LevelFilter};
+
+/// Capacity of `TEXT_RENDER_QUEUE`, set from the config before any hook can push to it.
+static QUEUE_CAPACITY: AtomicUsize = AtomicUsize::new(10000);
+pub(crate) static TEXT_RENDER_QUEUE: LazyLock<ArrayQueue<QueuedCommand>> =
+    LazyLock::new(|| ArrayQueue::new(QUEUE_CAPACITY.load(Ordering::Relaxed)));
+/// Render frames started so far, commands are stamped with it when they're queued.
+pub(crate) static FRAME_COUNTER: AtomicU64 = AtomicU64::new(0);
+/// Sequence number of the next queued command, the order the game submitted them in.
+static NEXT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+/// Time the capture timestamps count from, set when the DLL is loaded.
+static CAPTURE_EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+/// Set once the detours are installed, so bringing the overlay up lazily can't do it twice.
+static CAPTURE_INSTALLED: AtomicBool = AtomicBool::new(false);
+/// Commands evicted from a full `TEXT_RENDER_QUEUE` since the game started.
+static DROPPED_COMMANDS: AtomicU64 = AtomicU64::new(0);
+static DROP_WARNING: RateLimit = RateLimit::new(Duration::from_secs(1));
+/// The toast for dropped commands is shown less often than they're logged.
+static DROP_TOAST: RateLimit = RateLimit::new(Duration::from_secs(30));
+
+/// How often the config file is checked for changes.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the activation key is checked while waiting to bring the overlay up lazily.
+const ACTIVATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Time between attempts to hook the renderer, another overlay can hold the swapchain hooks
+/// for a while.
+const RENDER_HOOK_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// Attempts to hook the renderer before giving up, a minute with the interval above.
+const RENDER_HOOK_ATTEMPTS: u32 = 12;
+/// Shortest time between two rebuilds of the font atlas for glyphs seen in captured text.
+const GLYPH_REBUILD_INTERVAL: Duration = Duration::from_secs(3);
+/// Largest texture dimension D3D12 supports, a bigger font atlas can't be uploaded.
+const MAX_TEXTURE_SIZE: i32 = 16384;
+/// Space between text and the edge of its background plate at the base font size.
+const PLATE_PADDING: f32 = 2.0;
+const DEFAULT_FONT_PATH: &str = "C:\\Windows\\Fonts\\msgothic.ttc";
+/// Font used when neither the configured font nor MS Gothic can be read, e.g. under Wine. It has
+/// no CJK glyphs, Japanese text needs one of the other two.
+const EMBEDDED_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+const EMBEDDED_FONT_NAME: &str = "DejaVuSans.ttf (embedded)";
+
+/// A command with the frame it was captured in and when.
+#[derive(Debug, Clone)]
+pub(crate) struct QueuedCommand {
+    /// `FRAME_COUNTER` when the command was queued, the frame the game drew it for.
+    pub(crate) frame: u64,
+    /// Microseconds since the DLL was loaded.
+    pub(crate) time_us: u64,
+    /// Position in the game's submission order, later commands draw on top of earlier ones.
+    pub(crate) sequence: u64,
+    pub(crate) command: DrawCommand,
+}
+
+impl WindowMetrics {
+    fn current() -> Self {
+        Self {
+            window_size: DebugTextRender::window_size(),
+            screen_size: DebugTextRender::get_screen_size(),
+        }
+    }
+}
+
+/// Extra copies of each string drawn behind it to keep it readable on bright backgrounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TextOutline {
+    None,
+    /// A single copy offset down and to the right.
+    Shadow,
+    /// Copies offset in the four straight directions.
+    Outline4,
+    /// Copies offset in the four straight and four diagonal directions.
+    Outline8,
+}
+
+impl TextOutline {
+    pub(crate) const ALL: [TextOutline; 4] = [
+        TextOutline::None,
+        TextOutline::Shadow,
+        TextOutline::Outline4,
+        TextOutline::Outline8,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            TextOutline::None => "none",
+            TextOutline::Shadow => "shadow",
+            TextOutline::Outline4 => "outline4",
+            TextOutline::Outline8 => "outline8",
+        }
+    }
+
+    /// Offsets of the copies at the base font size.
+    fn offsets(self) -> &'static [[f32; 2]] {
+        match self {
+            TextOutline::None => &[],
+            TextOutline::Shadow => &[[1.0, 1.0]],
+            TextOutline::Outline4 => &[[-1.0, 0.0], [1.0, 0.0], [0.0, -1.0], [0.0, 1.0]],
+            TextOutline::Outline8 => &[
+                [-1.0, 0.0],
+                [1.0, 0.0],
+                [0.0, -1.0],
+                [0.0, 1.0],
+                [-1.0, -1.0],
+                [1.0, -1.0],
+                [-1.0, 1.0],
+                [1.0, 1.0],
+            ],
+        }
+    }
+}
+
+/// Queues a command stamped with the current frame and time, evicting the oldest one when the
+/// queue is full.
+pub(crate) fn push_command(command: DrawCommand) {
+    let queued = QueuedCommand {
+        frame: FRAME_COUNTER.load(Ordering::Relaxed),
+        time_us: CAPTURE_EPOCH.elapsed().as_micros() as u64,
+        sequence: NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        command,
+    };
+    if TEXT_RENDER_QUEUE.force_push(queued).is_none() {
+        return;
+    }
+
+    let dropped = DROPPED_COMMANDS.fetch_add(1, Ordering::Relaxed) + 1;
+    if DROP_WARNING.allow() {
+        tracing::warn!(
+            "Queue is full, {} commands dropped so far, consider raising queue_capacity",
+            dropped
+        );
+    }
+    if DROP_TOAST.allow() {
+        notify(
+            Severity::Warning,
+            format!("{dropped} commands dropped, consider raising queue_capacity"),
+        );
+    }
+}
+
+pub(crate) fn dropped_count() -> u64 {
+    DROPPED_COMMANDS.load(Ordering::Relaxed)
+}
+
+/// Splits the filter patterns edited in the settings window into one pattern per line.
+fn split_patterns(patterns: &str) -> Vec<String> {
+    patterns
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Adds text to the foreground draw list in `font` at an explicit font size.
+///
+/// `DrawListMut::add_text` always uses the current font and size, so this goes through imgui-sys.
+fn add_text_with_size(
+    draw_list: *mut sys::ImDrawList,
+    font: *mut sys::ImFont,
+    position: [f32; 2],
+    font_size: f32,
+    color: ImColor32,
+    text: &str,
+    wrap_width: Option<f32>,
+) {
+    let start = text.as_ptr() as *const c_char;
+    unsafe {
+        sys::ImDrawList_AddText_FontPtr(
+            draw_list,
+            font,
+            font_size,
+            position.into(),
+            color.to_bits(),
+            start,
+            start.add(text.len()),
+            wrap_width.unwrap_or(0.0),
+            std::ptr::null(),
+        );
+    }
+}
+
+/// Logs a state command for `log_captured_text`, text is logged with its caller instead.
+fn trace_state_command(command: &DrawCommand) {
+    match command {
+        DrawCommand::SetFontSize(ez_draw, font_size) => {
+            tracing::trace!("Font size: {} ({:#x})", font_size, ez_draw);
+        }
+        DrawCommand::SetTextScale(ez_draw, width_scale, height_scale, font_size) => {
+            tracing::trace!(
+                "Width scale: {}, Height scale: {}, Font size: {} ({:#x})",
+                width_scale,
+                height_scale,
+                font_size,
+                ez_draw
+            );
+        }
+        DrawCommand::ResetTextScale(ez_draw) => {
+            tracing::trace!("Reset text scale ({:#x})", ez_draw);
+        }
+        _ => {}
+    }
+}
+
+/// Size of text drawn in `font` at `font_size`, wrapped at `wrap_width` like
+/// `add_text_with_size` does.
+fn text_size(
+    font: *mut sys::ImFont,
+    text: &str,
+    font_size: f32,
+    wrap_width: Option<f32>,
+) -> [f32; 2] {
+    let start = text.as_ptr() as *const c_char;
+    let mut size = sys::ImVec2::zero();
+    unsafe {
+        sys::ImFont_CalcTextSizeA(
+            &mut size,
+            font,
+            font_size,
+            f32::MAX,
+            wrap_width.unwrap_or(0.0),
+            start,
+            start.add(text.len()),
+            std::ptr::null_mut(),
+        );
+    }
+    // Like ImGui::CalcTextSize, so sizes don't flicker between fractional pixels
+    [size.x.ceil(), size.y]
+}
+
+/// Number of faces in a font file, more than one for a TrueType collection (`.ttc`).
+fn font_face_count(data: &[u8]) -> u32 {
+    match data {
+        [b't', b't', b'c', b'f', _, _, _, _, a, b, c, d, ..] => {
+            u32::from_be_bytes([*a, *b, *c, *d])
+        }
+        _ => 1,
+    }
+}
+
+/// Address of the `ImFont` behind a font added to the atlas.
+fn font_address(ctx: &mut Context, id: FontId) -> usize {
+    ctx.fonts().get_font(id).map_or(0, |font| unsafe {
+        font.raw() as *const sys::ImFont as usize
+    })
+}
+
+/// Settings of the imgui context that don't come from the config.
+fn configure_context(ctx: &mut Context) {
+    // imgui.ini would end up in the game directory, none of the windows need their state kept
+    ctx.set_ini_filename(None);
+}
+
+/// Size of the primary monitor, used while the game's window singleton can't be found.
+fn fallback_screen_size() -> [f32; 2] {
+    static LOGGED: Once = Once::new();
+    LOGGED.call_once(|| {
+        tracing::warn!("CSWindowImp not found, falling back to the primary monitor resolution");
+    });
+
+    let size = unsafe { [GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)] };
+    if size[0] > 0 && size[1] > 0 {
+        [size[0] as f32, size[1] as f32]
+    } else {
+        [1920.0, 1080.0]
+    }
+}
+
+/// Draws a triangle at `position` pointing in `direction`, sized to text of `font_size`.
+fn draw_offscreen_arrow(
+    draw_list: &DrawListMut,
+    position: [f32; 2],
+    direction: [f32; 2],
+    font_size: f32,
+    color: ImColor32,
+) {
+    let length = (direction[0] * direction[0] + direction[1] * direction[1]).sqrt();
+    let (dx, dy) = (direction[0] / length, direction[1] / length);
+    let size = font_size * 0.5;
+    // The tip touches the edge the position was clamped to, the base is inside the screen
+    let tip = position;
+    let base = [tip[0] - dx * size, tip[1] - dy * size];
+    let half_width = size * 0.5;
+    draw_list
+        .add_triangle(
+            tip,
+            [base[0] - dy * half_width, base[1] + dx * half_width],
+            [base[0] + dy * half_width, base[1] - dx * half_width],
+            color,
+        )
+        .filled(true)
+        .build();
+}
+
+/// Makes sure the frame has at least one draw list, even when no text is drawn.
+///
+/// With nothing drawn imgui leaves `ImDrawData::CmdLists` as a null pointer, which
+/// `DrawData::draw_lists` turns into a slice in hudhook's renderer. With hudhook 0.8.1 that's
+/// undefined behavior and aborts the game on a frame without any text, e.g. right after loading
+/// into a save with the overlay enabled and nothing submitting debug text. A callback that does
+/// nothing isn't dropped as an unused command, so the foreground draw list always makes it into
+/// the draw data.
+fn keep_draw_data_non_empty() {
+    unsafe extern "C" fn noop(_: *const sys::ImDrawList, _: *const sys::ImDrawCmd) {}
+
+    unsafe {
+        sys::ImDrawList_AddCallback(
+            sys::igGetForegroundDrawList(),
+            Some(noop),
+            std::ptr::null_mut(),
+        );
+    }
+}
+
+/// Reads the overlay font, trying the configured path first and MS Gothic after it, followed by
+/// the fallback fonts that could be read.
+fn load_font_files(configured: Option<&Path>, fallbacks: &[PathBuf]) -> Vec<(PathBuf, Vec<u8>)> {
+    let mut files = vec![load_font_data(configured)];
+    for path in fallbacks {
+        match std::fs::read(path) {
+            Ok(data) => files.push((path.clone(), data)),
+            Err(e) => tracing::warn!("Skipping fallback font {}: {}", path.display(), e),
+        }
+    }
+    files
+}
+
+/// Reads the first of the configured font and MS Gothic that can be read, the embedded font if
+/// neither can.
+fn load_font_data(configured: Option<&Path>) -> (PathBuf, Vec<u8>) {
+    for path in configured
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain([PathBuf::from(DEFAULT_FONT_PATH)])
+    {
+        match std::fs::read(&path) {
+            Ok(data) => return (path, data),
+            Err(e) => tracing::warn!("Failed to read font file {}: {}", path.display(), e),
+        }
+    }
+    (PathBuf::from(EMBEDDED_FONT_NAME), EMBEDDED_FONT.to_vec())
+}
+
+pub(crate) struct DebugTextRender {
+    /// Scale state of every `CSEzDraw` instance, and the window it's corrected for.
+    pub(crate) processor: CommandProcessor,
+    /// Size the overlay draws at, the upscaled output size with an upscaler.
+    pub(crate) display_size: [f32; 2],
+    /// Ratio of the output size to the size the game positions text in.
+    pub(crate) render_scale: [f32; 2],
+    /// Configured render scale, measured every frame when `None`.
+    pub(crate) render_scale_override: Option<f32>,
+    pub(crate) aspect_mode: AspectMode,
+    pub(crate) aspect_min: f32,
+    pub(crate) aspect_max: f32,
+    /// Address of the `ImFont` baked at each of the configured sizes, sorted by size. The first
+    /// one added is imgui's default font.
+    ///
+    /// Kept as addresses since `FontId` isn't `Send`, the fonts live as long as the atlas.
+    pub(crate) fonts: Vec<(f32, usize)>,
+    /// Path and contents of the font file followed by the fallback fonts merged into it, kept to
+    /// rebuild the atlas. Empty until the files are read.
+    pub(crate) font_data: Vec<(PathBuf, Vec<u8>)>,
+    /// Face of the font file used when it's a collection, checked against the file.
+    pub(crate) font_face: u32,
+    /// Character sets from the config the atlas was built with, or the fallback ones if the
+    /// configured atlas couldn't be built.
+    pub(crate) glyph_ranges: Vec<GlyphRange>,
+    pub(crate) custom_glyph_ranges: Vec<[u32; 2]>,
+    /// Characters seen in captured text that the configured ranges didn't load.
+    pub(crate) required_glyphs: BTreeSet<u32>,
+    pub(crate) glyph_rebuild_pending: bool,
+    pub(crate) last_glyph_rebuild: Instant,
+    /// Display size and window the font atlas was last uploaded for. Either changing means the
+    /// swapchain was recreated, and the texture may have been lost with it.
+    pub(crate) atlas_surface: ([f32; 2], WindowMetrics),
+    /// Set to build and upload the atlas again before the next frame.
+    pub(crate) atlas_reload_pending: bool,
+    /// Commands collected for the frame currently being rendered.
+    pub(crate) frame: Vec<QueuedCommand>,
+    /// Text resolved from the current frame.
+    pub(crate) texts: Vec<TextEntry>,
+    /// Lines and rectangles resolved from the current frame.
+    pub(crate) shapes: Vec<ShapeEntry>,
+    /// Last non-empty set of text and shapes, drawn until it's replaced or goes stale.
+    pub(crate) retained: Vec<TextEntry>,
+    pub(crate) retained_shapes: Vec<ShapeEntry>,
+    pub(crate) retained_at: Instant,
+    /// Shapes are retained on their own, a frame with only text or only shapes keeps the other.
+    pub(crate) retained_shapes_at: Instant,
+    /// Text of the retained frame that wasn't drawn because of `max_texts_per_frame`.
+    pub(crate) retained_capped: usize,
+    pub(crate) retain_timeout: Duration,
+    /// Text drawn where the game positions it per frame, 0 draws all of it.
+    pub(crate) max_texts_per_frame: usize,
+    /// Text queued longer ago than this is discarded, zero keeps it.
+    pub(crate) max_command_age: Duration,
+    /// Identical text at the same position is dropped for this long after it was queued.
+    pub(crate) duplicate_window: Duration,
+    pub(crate) default_font_size: f32,
+    /// Multiplier applied to every font size on top of the game's own.
+    pub(crate) font_scale: f32,
+    pub(crate) default_color: [f32; 4],
+    pub(crate) outline: TextOutline,
+    pub(crate) outline_color: [f32; 4],
+    /// Whether a plate is drawn behind each text.
+    pub(crate) background: bool,
+    pub(crate) background_color: [f32; 4],
+    pub(crate) background_rounding: f32,
+    /// Whether text fades in and out instead of appearing and vanishing at once.
+    pub(crate) fade: bool,
+    pub(crate) fades: TextFades,
+    /// Whether text that changed since the previous frame is drawn in a highlight color.
+    pub(crate) diff: bool,
+    pub(crate) text_diff: TextDiff,
+    /// Positions world text is eased between.
+    pub(crate) world_smoothing: WorldSmoothing,
+    /// Which world shapes are drawn, edited in the settings window.
+    pub(crate) world_shapes: WorldShapeSettings,
+    pub(crate) highlighter: Highlighter,
+    pub(crate) categories: Categories,
+    pub(crate) alerts: AlertList,
+    pub(crate) anchor: TextAnchor,
+    pub(crate) offscreen: OffscreenMode,
+    /// Whether text clamped to the screen edge gets an arrow pointing to where it really is.
+    pub(crate) offscreen_indicator: bool,
+    /// What happens to the overlay while the game's window isn't focused.
+    pub(crate) unfocused: UnfocusedMode,
+    pub(crate) unfocused_alpha: f32,
+    pub(crate) hdr_mode: HdrMode,
+    pub(crate) hdr_white_nits: f32,
+    /// Color space of the display the game is on, checked again when the swapchain changes.
+    pub(crate) detected_output: HdrOutput,
+    /// Whether the game's window was focused when the frame started.
+    pub(crate) focused: bool,
+    /// Filter patterns being edited in the settings window, one per line.
+    pub(crate) include_patterns: String,
+    pub(crate) exclude_patterns: String,
+    pub(crate) log_level: LevelFilter,
+    /// Log directives being edited in the settings window, one per line.
+    pub(crate) log_directives: String,
+    /// Whether every drawn command is logged at the trace level.
+    pub(crate) log_captured_text: bool,
+    /// Whether text is shown with the RVA of the game code that drew it.
+    pub(crate) show_callers: bool,
+    /// Whether text is prefixed with the frame and time it was captured at.
+    pub(crate) show_stamps: bool,
+    pub(crate) presentation: Presentation,
+    /// Text shown in the source panel.
+    pub(crate) source_panel: SourcePanel,
+    pub(crate) watches: WatchList,
+    pub(crate) watch_stale_after: Duration,
+    /// Recently captured text, listed in the history window.
+    pub(crate) history: TextHistory,
+    pub(crate) history_open: bool,
+    pub(crate) history_key: Hotkey,
+    /// How much text each source drew recently, shown in the statistics window.
+    pub(crate) stats: SourceStats,
+    pub(crate) stats_open: bool,
+    pub(crate) stats_key: Hotkey,
+    /// Pattern being typed in the settings window.
+    pub(crate) watch_input: String,
+    /// Hook switches being edited in the settings window, applied as they change.
+    pub(crate) hook_settings: HookSettings,
+    /// Spaces a tab in captured text is replaced with.
+    pub(crate) tab_width: usize,
+    /// Whether text wraps at the right edge of the screen.
+    pub(crate) wrap_to_screen: bool,
+    /// Pixels within which text counts as drawn at the same position, 0 disables stacking.
+    pub(crate) stack_tolerance: f32,
+    /// Whether text is drawn at all, commands are still drained while hidden.
+    pub(crate) enabled: bool,
+    /// Game states the overlay hides itself in, like `enabled` being off.
+    pub(crate) auto_hide: AutoHideSettings,
+    /// Looked up while drawing, so entries kept across frames follow a switch right away.
+    pub(crate) layers: LayerSettings,
+    /// The state the overlay is hidden in this frame.
+    pub(crate) hidden_in: Option<GameState>,
+    pub(crate) toggle_key: Hotkey,
+    pub(crate) settings_open: bool,
+    pub(crate) settings_key: Hotkey,
+    pub(crate) diagnostics_open: bool,
+    pub(crate) diagnostics_key: Hotkey,
+    pub(crate) console_key: Hotkey,
+    pub(crate) dump_key: Hotkey,
+    pub(crate) copy_key: Hotkey,
+    /// Whether the text on screen is kept as it is while new commands are ignored.
+    pub(crate) frozen: bool,
+    /// Commands ignored since the overlay was frozen.
+    pub(crate) frozen_commands: u64,
+    pub(crate) freeze_key: Hotkey,
+    /// Whether the mouse is captured to inspect the text under it.
+    pub(crate) inspecting: bool,
+    /// Text the context menu for hiding text was opened on.
+    pub(crate) block_target: Option<BlockTarget>,
+    pub(crate) inspect_key: Hotkey,
+    /// Message shown briefly at the top of the screen, e.g. the result of the last copy to the
+    /// clipboard, and when it was set.
+    pub(crate) notice: Option<(String, Instant)>,
+    pub(crate) toasts: Toasts,
+    pub(crate) replay_pause_key: Hotkey,
+    pub(crate) replay_step_key: Hotkey,
+    /// Dump file captured text is written to while recording.
+    pub(crate) dump: Option<TextDump>,
+    /// Streams captured commands to other programs, `None` unless `pipe.enabled` is set.
+    pub(crate) pipe: Option<PipeServer>,
+    /// Text on screen mirrored for other programs, `None` unless `shared_memory.enabled` is set.
+    pub(crate) mirror: Option<SharedTextMirror>,
+    /// Commands moved out of the queue at the start of the last frame.
+    pub(crate) drained_last_frame: usize,
+    /// Text commands discarded for being older than `max_command_age_ms` since the game started.
+    pub(crate) expired_commands: u64,
+    /// Whether commands are drawn regardless of the game's debug-draw flags.
+    pub(crate) game_flag_mode: FlagMode,
+    pub(crate) game_flags: GameFlags,
+    /// Commands discarded since the game started because its flags didn't allow them.
+    pub(crate) gated_commands: u64,
+    /// Frames that had more text than `max_texts_per_frame`.
+    pub(crate) cap_hits: u64,
+    /// Config the current settings came from, the settings window edits copies of its values.
+    pub(crate) config: Config,
+    /// Latest config, replaced by the watcher thread when the file changes.
+    pub(crate) shared_config: Arc<RwLock<Config>>,
+    /// Where the settings window saves to, `None` if the DLL path couldn't be found.
+    pub(crate) config_path: Option<PathBuf>,
+}
+impl DebugTextRender {
+    fn new(shared_config: Arc<RwLock<Config>>, config_path: Option<PathBuf>) -> Self {
+        let config = shared_config.read().unwrap().clone();
+        Self {
+            processor: CommandProcessor::new(WindowMetrics::current()),
+            fonts: Vec::new(),
+            font_data: Vec::new(),
+            font_face: 0,
+            glyph_ranges: Vec::new(),
+            custom_glyph_ranges: Vec::new(),
+            required_glyphs: BTreeSet::new(),
+            glyph_rebuild_pending: false,
+            last_glyph_rebuild: Instant::now(),
+            atlas_surface: ([0.0, 0.0], WindowMetrics::current()),
+            atlas_reload_pending: false,
+            display_size: Self::get_screen_size(),
+            render_scale: [1.0, 1.0],
+            render_scale_override: config.text.render_scale,
+            aspect_mode: config.text.aspect_mode,
+            aspect_min: config.text.aspect_min,
+            aspect_max: config.text.aspect_max,
+            frame: Vec::new(),
+            texts: Vec::new(),
+            shapes: Vec::new(),
+            retained: Vec::new(),
+            retained_shapes: Vec::new(),
+            retained_at: Instant::now(),
+            retained_shapes_at: Instant::now(),
+            retained_capped: 0,
+            retain_timeout: Duration::from_millis(config.text.retain_timeout_ms),
+            max_texts_per_frame: config.text.max_texts_per_frame,
+            max_command_age: Duration::from_millis(config.max_command_age_ms),
+            duplicate_window: Duration::from_millis(config.duplicate_window_ms),
+            default_font_size: config.font.size,
+            font_scale: config.font.scale,
+            default_color: config.text.color,
+            outline: config.text.outline,
+            outline_color: config.text.outline_color,
+            background: config.text.background,
+            background_color: config.text.background_color,
+            background_rounding: config.text.background_rounding,
+            fade: config.text.fade,
+            fades: TextFades::new(),
+            diff: config.text.diff,
+            text_diff: TextDiff::new(),
+            world_smoothing: WorldSmoothing::new(),
+            world_shapes: config.world_shapes.clone(),
+            highlighter: Highlighter::new(&config.highlights),
+            categories: Categories::new(&config.categories),
+            alerts: AlertList::new(
+                &config.alerts,
+                config_path.as_deref().and_then(Path::parent),
+            ),
+            anchor: config.text.anchor,
+            offscreen: config.text.offscreen,
+            offscreen_indicator: config.text.offscreen_indicator,
+            unfocused: config.text.unfocused,
+            unfocused_alpha: config.text.unfocused_alpha,
+            hdr_mode: config.text.hdr,
+            hdr_white_nits: config.text.hdr_white_nits,
+            detected_output: HdrOutput::Sdr,
+            focused: true,
+            stack_tolerance: config.text.stack_tolerance_px,
+            wrap_to_screen: config.text.wrap,
+            tab_width: config.text.tab_width,
+            include_patterns: config.filter.include.join("\n"),
+            exclude_patterns: config.filter.exclude.join("\n"),
+            log_level: config.log_level(),
+            log_directives: config.log_directives.join("\n"),
+            log_captured_text: config.log_captured_text,
+            hook_settings: config.hooks.clone(),
+            show_callers: config.text.show_callers,
+            show_stamps: config.text.show_stamps,
+            presentation: config.text.presentation,
+            source_panel: SourcePanel::new(),
+            watches: WatchList::new(&config.watch.patterns, config.watch.history),
+            watch_stale_after: Duration::from_millis(config.watch.stale_after_ms),
+            watch_input: String::new(),
+            history: TextHistory::new(config.history.capacity),
+            history_open: false,
+            history_key: Hotkey::new(parse_key(&config.hotkeys.history).unwrap_or(VK_F2)),
+            stats: SourceStats::new(),
+            stats_open: false,
+            stats_key: Hotkey::new(parse_key(&config.hotkeys.stats).unwrap_or(VK_F1)),
+            enabled: true,
+            auto_hide: config.auto_hide.clone(),
+            layers: config.layers,
+            hidden_in: None,
+            toggle_key: Hotkey::new(parse_key(&config.hotkeys.toggle).unwrap_or(VK_F10)),
+            settings_open: false,
+            settings_key: Hotkey::new(parse_key(&config.hotkeys.settings).unwrap_or(VK_F11)),
+            diagnostics_open: config.diagnostics,
+            diagnostics_key: Hotkey::new(parse_key(&config.hotkeys.diagnostics).unwrap_or(VK_F9)),
+            console_key: Hotkey::new(parse_key(&config.hotkeys.console).unwrap_or(VK_F8)),
+            dump_key: Hotkey::new(parse_key(&config.hotkeys.dump).unwrap_or(VK_F7)),
+            copy_key: Hotkey::new(parse_key(&config.hotkeys.copy).unwrap_or(VK_F5)),
+            notice: None,
+            toasts: Toasts::default(),
+            frozen: false,
+            frozen_commands: 0,
+            freeze_key: Hotkey::new(parse_key(&config.hotkeys.freeze).unwrap_or(VK_F3)),
+            inspecting: false,
+            block_target: None,
+            inspect_key: Hotkey::new(parse_key(&config.hotkeys.inspect).unwrap_or(VK_F4)),
+            replay_pause_key: Hotkey::new(
+                parse_key(&config.hotkeys.replay_pause).unwrap_or(VK_PAUSE),
+            ),
+            replay_step_key: Hotkey::new(parse_key(&config.hotkeys.replay_step).unwrap_or(VK_F6)),
+            dump: None,
+            pipe: None,
+            mirror: None,
+            drained_last_frame: 0,
+            expired_commands: 0,
+            game_flag_mode: config.game_flags.mode,
+            game_flags: GameFlags::new(),
+            gated_commands: 0,
+            cap_hits: 0,
+            config,
+            shared_config,
+            config_path,
+        }
+    }
+
+    /// Applies a reloaded config, replacing whatever was changed in the settings window.
+    fn apply_config(&mut self, config: Config) {
+        self.retain_timeout = Duration::from_millis(config.text.retain_timeout_ms);
+        self.max_texts_per_frame = config.text.max_texts_per_frame;
+        self.max_command_age = Duration::from_millis(config.max_command_age_ms);
+        self.duplicate_window = Duration::from_millis(config.duplicate_window_ms);
+        self.default_font_size = config.font.size;
+        self.font_scale = config.font.scale;
+        self.default_color = config.text.color;
+        self.outline = config.text.outline;
+        self.outline_color = config.text.outline_color;
+        self.background = config.text.background;
+        self.background_color = config.text.background_color;
+        self.background_rounding = config.text.background_rounding;
+        self.fade = config.text.fade;
+        self.diff = config.text.diff;
+        self.world_shapes = config.world_shapes.clone();
+        self.game_flag_mode = config.game_flags.mode;
+        self.auto_hide = config.auto_hide.clone();
+        self.layers = config.layers;
+        self.highlighter.set_rules(&config.highlights);
+        self.categories.set(&config.categories);
+        self.alerts.set_rules(
+            &config.alerts,
+            self.config_path.as_deref().and_then(Path::parent),
+        );
+        self.anchor = config.text.anchor;
+        self.render_scale_override = config.text.render_scale;
+        self.aspect_mode = config.text.aspect_mode;
+        self.aspect_min = config.text.aspect_min;
+        self.aspect_max = config.text.aspect_max;
+        self.offscreen = config.text.offscreen;
+        self.offscreen_indicator = config.text.offscreen_indicator;
+        self.unfocused = config.text.unfocused;
+        self.unfocused_alpha = config.text.unfocused_alpha;
+        self.hdr_mode = config.text.hdr;
+        self.hdr_white_nits = config.text.hdr_white_nits;
+        self.stack_tolerance = config.text.stack_tolerance_px;
+        self.wrap_to_screen = config.text.wrap;
+        self.tab_width = config.text.tab_width;
+        self.include_patterns = config.filter.include.join("\n");
+        self.exclude_patterns = config.filter.exclude.join("\n");
+        self.log_level = config.log_level();
+        self.log_directives = config.log_directives.join("\n");
+        self.log_captured_text = config.log_captured_text;
+        self.hook_settings = config.hooks.clone();
+        self.show_callers = config.text.show_callers;
+        self.show_stamps = config.text.show_stamps;
+        self.presentation = config.text.presentation;
+        self.watches.set_patterns(&config.watch.patterns);
+        self.watches.set_history(config.watch.history);
+        self.watch_stale_after = Duration::from_millis(config.watch.stale_after_ms);
+        self.history.set_capacity(config.history.capacity);
+        self.history_key = Hotkey::new(parse_key(&config.hotkeys.history).unwrap_or(VK_F2));
+        self.stats_key = Hotkey::new(parse_key(&config.hotkeys.stats).unwrap_or(VK_F1));
+        self.toggle_key = Hotkey::new(parse_key(&config.hotkeys.toggle).unwrap_or(VK_F10));
+        self.settings_key = Hotkey::new(parse_key(&config.hotkeys.settings).unwrap_or(VK_F11));
+        self.diagnostics_open = config.diagnostics;
+        self.diagnostics_key = Hotkey::new(parse_key(&config.hotkeys.diagnostics).unwrap_or(VK_F9));
+        self.console_key = Hotkey::new(parse_key(&config.hotkeys.console).unwrap_or(VK_F8));
+        self.dump_key = Hotkey::new(parse_key(&config.hotkeys.dump).unwrap_or(VK_F7));
+        self.copy_key = Hotkey::new(parse_key(&config.hotkeys.copy).unwrap_or(VK_F5));
+        self.inspect_key = Hotkey::new(parse_key(&config.hotkeys.inspect).unwrap_or(VK_F4));
+        self.freeze_key = Hotkey::new(parse_key(&config.hotkeys.freeze).unwrap_or(VK_F3));
+        self.replay_pause_key =
+            Hotkey::new(parse_key(&config.hotkeys.replay_pause).unwrap_or(VK_PAUSE));
+        self.replay_step_key = Hotkey::new(parse_key(&config.hotkeys.replay_step).unwrap_or(VK_F6));
+        self.config = config;
+        set_capture_callers(self.needs_callers());
+    }
+
+    /// Whether the detours look up callers, for the config or the open statistics window.
+    pub(crate) fn needs_callers(&self) -> bool {
+        self.stats_open || self.current_config().needs_callers()
+    }
+
+    /// Drops text commands queued more than `max_command_age` ago, left over from frames the
+    /// render loop stalled on. The other commands are still applied so the state they set stays
+    /// in sync with the game.
+    fn expire_stale_text(&mut self) {
+        if self.max_command_age.is_zero() {
+            return;
+        }
+        let max_age_us = self.max_command_age.as_micros() as u64;
+        let now_us = CAPTURE_EPOCH.elapsed().as_micros() as u64;
+        let before = self.frame.len();
+        self.frame.retain(|queued| {
+            !matches!(queued.command, DrawCommand::Text { .. })
+                || now_us.saturating_sub(queued.time_us) <= max_age_us
+        });
+        let expired = (before - self.frame.len()) as u64;
+        if expired > 0 {
+            self.expired_commands += expired;
+            tracing::info!(
+                "Discarded {} text commands older than {}ms, the render loop stalled",
+                expired,
+                self.max_command_age.as_millis()
+            );
+        }
+    }
+
+    /// Moves text the game still draws but whose copies dedup suppresses from the retained set to
+    /// the new one, so other text arriving doesn't make it flicker.
+    fn keep_suppressed_text(&mut self) {
+        let drawn: HashSet<TextId> = self.texts.iter().filter_map(|entry| entry.source).collect();
+        let (kept, gone) = std::mem::take(&mut self.retained)
+            .into_iter()
+            .partition(|entry| {
+                entry
+                    .source
+                    .is_some_and(|source| !drawn.contains(&source) && dedup::is_suppressing(source))
+            });
+        self.texts.extend::<Vec<_>>(kept);
+        self.retained = gone;
+    }
+
+    /// Picks up a config the watcher thread reloaded, without waiting on it.
+    fn sync_config(&mut self) {
+        let reloaded = match self.shared_config.try_read() {
+            Ok(shared) if *shared != self.config => shared.clone(),
+            _ => return,
+        };
+        self.apply_config(reloaded);
+    }
+
+    /// Whether a window that needs the mouse and keyboard is open.
+    fn takes_input(&self) -> bool {
+        // The prefix in the context menu is typed in, even while inspecting
+        self.settings_open || self.history_open || self.stats_open || self.block_target.is_some()
+    }
+
+    /// The loaded config with the values changed in the settings window applied.
+    pub(crate) fn current_config(&self) -> Config {
+        let mut config = self.config.clone();
+        config.font.size = self.default_font_size;
+        config.font.scale = self.font_scale;
+        config.text.color = self.default_color;
+        config.text.outline = self.outline;
+        config.text.outline_color = self.outline_color;
+        config.text.background = self.background;
+        config.text.background_color = self.background_color;
+        config.text.background_rounding = self.background_rounding;
+        config.text.fade = self.fade;
+        config.text.diff = self.diff;
+        config.world_shapes = self.world_shapes.clone();
+        config.game_flags.mode = self.game_flag_mode;
+        config.auto_hide = self.auto_hide.clone();
+        config.layers = self.layers;
+        config.highlights = self.highlighter.rules().to_vec();
+        config.categories = self.categories.settings().clone();
+        config.text.anchor = self.anchor;
+        config.text.render_scale = self.render_scale_override;
+        config.text.aspect_mode = self.aspect_mode;
+        config.text.aspect_min = self.aspect_min;
+        config.text.aspect_max = self.aspect_max;
+        config.text.offscreen = self.offscreen;
+        config.text.offscreen_indicator = self.offscreen_indicator;
+        config.text.unfocused = self.unfocused;
+        config.text.unfocused_alpha = self.unfocused_alpha;
+        config.text.hdr = self.hdr_mode;
+        config.text.hdr_white_nits = self.hdr_white_nits;
+        config.text.stack_tolerance_px = self.stack_tolerance;
+        config.text.wrap = self.wrap_to_screen;
+        config.text.tab_width = self.tab_width;
+        config.filter.include = split_patterns(&self.include_patterns);
+        config.filter.exclude = split_patterns(&self.exclude_patterns);
+        config.log_level = self.log_level.to_string().to_lowercase();
+        // An invalid one would keep the saved config from loading
+        config.log_directives = split_patterns(&self.log_directives)
+            .into_iter()
+            .filter(|directive| directive.parse::<Directive>().is_ok())
+            .collect();
+        config.log_captured_text = self.log_captured_text;
+        config.hooks = self.hook_settings.clone();
+        config.text.show_callers = self.show_callers;
+        config.text.show_stamps = self.show_stamps;
+        config.text.presentation = self.presentation;
+        config.watch.patterns = self.watches.patterns();
+        config.text.retain_timeout_ms = self.retain_timeout.as_millis() as u64;
+        config.text.max_texts_per_frame = self.max_texts_per_frame;
+        config.max_command_age_ms = self.max_command_age.as_millis() as u64;
+        config.duplicate_window_ms = self.duplicate_window.as_millis() as u64;
+        config
+    }
+
+    /// Moves everything that was queued when the frame started into `self.frame`.
+    ///
+    /// Commands pushed while draining are left for the next frame, so a frame is
+    /// never split between two render passes.
+    fn collect_frame(&mut self) {
+        self.frame.clear();
+        let pending = TEXT_RENDER_QUEUE.len();
+        self.frame
+            .extend(std::iter::from_fn(|| TEXT_RENDER_QUEUE.pop()).take(pending));
+        self.drained_last_frame = self.frame.len();
+        self.expire_stale_text();
+        self.follow_game_flags();
+    }
+
+    /// Drops the commands the game's debug-draw flags don't allow in `follow` mode, like the
+    /// game does when it checks them before drawing.
+    fn follow_game_flags(&mut self) {
+        let flags = &self.config.game_flags.flags;
+        self.game_flags.update(self.game_flag_mode, flags);
+        if self.game_flag_mode == FlagMode::Always {
+            return;
+        }
+        let before = self.frame.len();
+        self.frame
+            .retain(|queued| self.game_flags.allows(flags, &queued.command));
+        self.gated_commands += (before - self.frame.len()) as u64;
+    }
+
+    /// Projects a world shape with the camera, which is read for the first one in the frame.
+    fn push_world_shape(
+        &mut self,
+        camera: &mut Option<Option<Camera>>,
+        ez_draw: usize,
+        shape: WorldShape,
+    ) {
+        if !self.world_shapes.enabled {
+            return;
+        }
+        let Some(camera) = camera.get_or_insert_with(Camera::current) else {
+            return;
+        };
+        let color = self.processor.color(&self.screen(), ez_draw);
+        let shapes = project_shape(camera, shape, self.display_size);
+        self.shapes
+            .extend(shapes.into_iter().map(|(kind, from, to)| ShapeEntry {
+                kind,
+                from,
+                to,
+                color,
+            }));
+    }
+
+    /// What the command processor needs to know about the display and the text settings.
+    fn screen(&self) -> ScreenInfo {
+        ScreenInfo {
+            display_size: self.display_size,
+            render_scale: self.render_scale,
+            font_scale: self.font_scale,
+            default_font_size: self.default_font_size,
+            default_color: self.default_color,
+            offscreen: self.offscreen,
+            tab_width: self.tab_width,
+            wrap_to_screen: self.wrap_to_screen,
+        }
+    }
+
+    /// Converts colors for the game's output, used for everything the overlay draws on the game.
+    pub(crate) fn color_space(&self) -> ColorSpace {
+        ColorSpace {
+            output: self.hdr_mode.output(self.detected_output),
+            white_nits: self.hdr_white_nits,
+        }
+    }
+
+    /// Checks the color space of the display the game is on, HDR is switched by recreating the
+    /// swapchain.
+    fn detect_output(&mut self) {
+        let window = match unsafe { get_instance::<CSWindowImp>() } {
+            Ok(Some(window)) => HWND(window.window_handle),
+            // The primary display
+            _ => HWND(0),
+        };
+        let output = detect_output(window);
+        if output != self.detected_output {
+            tracing::info!("The game's display outputs {}", output.name());
+        }
+        self.detected_output = output;
+    }
+
+    /// Opacity everything is drawn at, lowered while the game's window isn't focused.
+    fn overlay_alpha(&self) -> f32 {
+        if self.unfocused == UnfocusedMode::Dim && !self.focused {
+            self.unfocused_alpha
+        } else {
+            1.0
+        }
+    }
+
+    fn draw_shapes(&self, ui: &Ui) {
+        let draw_list = self.layers.shapes.draw_list(ui);
+        let alpha = self.overlay_alpha();
+        let color_space = self.color_space();
+        for shape in &self.retained_shapes {
+            let [r, g, b, a] = shape.color;
+            let color = color_space.color32([r, g, b, a * alpha]);
+            match shape.kind {
+                ShapeKind::Line => draw_list.add_line(shape.from, shape.to, color).build(),
+                ShapeKind::Rect => draw_list.add_rect(shape.from, shape.to, color).build(),
+                ShapeKind::FilledRect => draw_list
+                    .add_rect(shape.from, shape.to, color)
+                    .filled(true)
+                    .build(),
+                ShapeKind::Circle => {
+                    let radius = (shape.to[0] - shape.from[0]).hypot(shape.to[1] - shape.from[1]);
+                    draw_list
+                        .add_circle(shape.from, radius, color)
+                        .num_segments(self.world_shapes.circle_segments)
+                        .build()
+                }
+            }
+        }
+    }
+
+    /// Replaces the fonts in the atlas with the font at every baked size, loaded with the glyphs
+    /// of `ranges`, and builds it.
+    ///
+    /// Returns `false` if the atlas couldn't be built or is too large for a texture.
+    fn build_fonts(&mut self, ctx: &mut Context, ranges: &[FontGlyphRanges]) -> bool {
+        ctx.fonts().clear();
+        self.fonts.clear();
+
+        let font_config = |size_pixels: f32, glyph_ranges: FontGlyphRanges| imgui::FontConfig {
+            size_pixels,
+            // Oversampling sharpens small text, at larger sizes it would only grow the atlas
+            oversample_h: if size_pixels > BASE_IMGUI_FONT_SIZE_PX {
+                1
+            } else {
+                3
+            },
+            oversample_v: 1,
+            pixel_snap_h: true,
+            glyph_ranges,
+            ..Default::default()
+        };
+
+        // The files are read before the first build, there always is at least one
+        let Some((path, _)) = self.font_data.first() else {
+            tracing::error!("No font was read");
+            return false;
+        };
+        // The base size goes first, it's the default font the settings window uses
+        let mut sizes = vec![BASE_IMGUI_FONT_SIZE_PX];
+        sizes.extend(
+            self.config
+                .font
+                .baked_sizes
+                .iter()
+                .filter(|size| **size > 0.0 && **size != BASE_IMGUI_FONT_SIZE_PX),
+        );
+        tracing::info!("Using font {} at {:?} px", path.display(), sizes);
+        for (path, _) in &self.font_data[1..] {
+            tracing::info!("Merging fallback font {}", path.display());
+        }
+        for size in sizes {
+            // Every source after the first is merged into the same font, a glyph comes
+            // from the first file that has it
+            let sources: Vec<_> = self
+                .font_data
+                .iter()
+                .flat_map(|(_, data)| {
+                    ranges.iter().map(move |ranges| imgui::FontSource::TtfData {
+                        data,
+                        size_pixels: size,
+                        config: Some(font_config(size, ranges.clone())),
+                    })
+                })
+                .collect();
+            let id = ctx.fonts().add_font(&sources);
+            if self.font_face != 0 {
+                // imgui-rs has no setting for the face, the primary font's sources are
+                // the first of the ones just added
+                let atlas = unsafe { ctx.fonts().raw_mut() };
+                let configs = unsafe {
+                    std::slice::from_raw_parts_mut(
+                        atlas.ConfigData.Data,
+                        atlas.ConfigData.Size as usize,
+                    )
+                };
+                let added = configs.len() - sources.len();
+                for config in &mut configs[added..added + ranges.len()] {
+                    config.FontNo = self.font_face as i32;
+                }
+            }
+            self.fonts.push((size, font_address(ctx, id)));
+        }
+        self.fonts.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self.fonts.dedup_by(|a, b| a.0 == b.0);
+
+        let atlas = unsafe { ctx.fonts().raw_mut() };
+        if !unsafe { sys::ImFontAtlas_Build(atlas) } {
+            tracing::error!("Failed to build the font atlas");
+            return false;
+        }
+        let (width, height) = (atlas.TexWidth, atlas.TexHeight);
+        tracing::info!(
+            "Font atlas is {}x{} ({} MiB as RGBA)",
+            width,
+            height,
+            width as usize * height as usize * 4 / (1024 * 1024)
+        );
+        if width > MAX_TEXTURE_SIZE || height > MAX_TEXTURE_SIZE {
+            tracing::error!(
+                "Font atlas exceeds the {}px texture limit",
+                MAX_TEXTURE_SIZE
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Glyph ranges to build the atlas with, the configured ones and, with `with_required`,
+    /// every glyph found missing in captured text.
+    fn atlas_ranges(&self, with_required: bool) -> Vec<FontGlyphRanges> {
+        let mut custom = self.custom_glyph_ranges.clone();
+        if with_required {
+            custom.extend(
+                self.required_glyphs
+                    .iter()
+                    .map(|&codepoint| [codepoint, codepoint]),
+            );
+        }
+        let mut ranges: Vec<FontGlyphRanges> = self
+            .glyph_ranges
+            .iter()
+            .map(|range| range.ranges())
+            .collect();
+        ranges.extend(custom_ranges(&custom));
+        ranges
+    }
+
+    /// Remembers the characters of `text` the atlas has no glyph for, so the next rebuild adds
+    /// them. Until then imgui draws its fallback glyph in their place.
+    fn note_missing_glyphs(&mut self, text: &str) {
+        // Every baked size is loaded with the same glyphs
+        let Some(&(_, font)) = self.fonts.first() else {
+            return;
+        };
+        if font == 0 {
+            return;
+        }
+
+        for c in text.chars() {
+            let codepoint = c as u32;
+            if c.is_whitespace() || self.required_glyphs.contains(&codepoint) {
+                continue;
+            }
+            let glyph = unsafe {
+                sys::ImFont_FindGlyphNoFallback(font as *mut sys::ImFont, codepoint as sys::ImWchar)
+            };
+            if glyph.is_null() {
+                self.required_glyphs.insert(codepoint);
+                self.glyph_rebuild_pending = true;
+            }
+        }
+    }
+
+    /// Reads the font files from the config, kept in `font_data` for every later build of the
+    /// atlas.
+    fn read_font_files(&mut self) {
+        self.font_data = load_font_files(
+            self.config.font.path.as_deref(),
+            &self.config.font.fallback_paths,
+        );
+        self.font_face = self.config.font.face_index;
+        if let Some((path, data)) = self.font_data.first() {
+            let faces = font_face_count(data);
+            if self.font_face >= faces {
+                tracing::error!(
+                    "font.face_index is {} but {} only has {} face(s), using the first one",
+                    self.font_face,
+                    path.display(),
+                    faces
+                );
+                self.font_face = 0;
+            }
+        }
+        self.glyph_ranges = self.config.font.glyph_ranges.clone();
+        self.custom_glyph_ranges = self.config.font.custom_glyph_ranges.clone();
+        if self.glyph_ranges.is_empty() && custom_ranges(&self.custom_glyph_ranges).is_none() {
+            self.glyph_ranges.push(GlyphRange::Default);
+        }
+    }
+
+    /// Builds the atlas from the font files read at startup, with the glyphs found missing in
+    /// captured text. Falls back to fewer and fewer glyphs if it can't be built.
+    fn load_fonts(&mut self, ctx: &mut Context) {
+        if self.build_fonts(ctx, &self.atlas_ranges(true)) {
+            return;
+        }
+        if !self.required_glyphs.is_empty() {
+            tracing::error!("Building the font atlas without the glyphs seen in captured text");
+            if self.build_fonts(ctx, &self.atlas_ranges(false)) {
+                return;
+            }
+        }
+        tracing::error!("Falling back to the default and Japanese glyph ranges");
+        self.glyph_ranges = vec![GlyphRange::Default, GlyphRange::Japanese];
+        self.custom_glyph_ranges.clear();
+        self.build_fonts(ctx, &self.atlas_ranges(false));
+    }
+
+    /// Rebuilds the atlas with the glyphs found missing since the last build and uploads it.
+    ///
+    /// A glyph the font doesn't have stays required without triggering more rebuilds, it's only
+    /// noted as missing once.
+    fn rebuild_font_atlas(&mut self, ctx: &mut Context, render_context: &mut dyn RenderContext) {
+        self.glyph_rebuild_pending = false;
+        self.last_glyph_rebuild = Instant::now();
+        tracing::info!(
+            "Rebuilding the font atlas with {} glyphs seen in captured text",
+            self.required_glyphs.len()
+        );
+
+        let old_texture = ctx.fonts().tex_id;
+        let old_size = {
+            let atlas = unsafe { ctx.fonts().raw() };
+            (atlas.TexWidth as u32, atlas.TexHeight as u32)
+        };
+        self.load_fonts(ctx);
+
+        let fonts = ctx.fonts();
+        let texture = fonts.build_rgba32_texture();
+        let uploaded = if (texture.width, texture.height) == old_size {
+            render_context
+                .replace_texture(old_texture, texture.data, texture.width, texture.height)
+                .map(|()| old_texture)
+        } else {
+            // hudhook can't resize a texture, the old one is left unused
+            render_context.load_texture(texture.data, texture.width, texture.height)
+        };
+        match uploaded {
+            Ok(texture_id) => fonts.tex_id = texture_id,
+            Err(e) => tracing::error!("Failed to upload the font atlas: {:?}", e),
+        }
+    }
+
+    /// Watches for the swapchain being recreated, e.g. when switching to fullscreen, which can
+    /// take the atlas texture with it. A device reset without a new size isn't seen here, the
+    /// settings window has a button for it.
+    fn track_atlas_surface(&mut self, ctx: &Context) {
+        let surface = (ctx.io().display_size, WindowMetrics::current());
+        // Minimized, the atlas is reloaded once the window is back
+        if surface == self.atlas_surface || surface.0[0] <= 0.0 || surface.0[1] <= 0.0 {
+            return;
+        }
+        self.detect_output();
+        tracing::info!(
+            "Swapchain changed from {:?} to {:?}, reloading the font atlas",
+            self.atlas_surface,
+            surface
+        );
+        self.atlas_surface = surface;
+        self.atlas_reload_pending = true;
+    }
+
+    /// Builds the atlas again and uploads it as a new texture, since the old one may belong to
+    /// a device that's gone. Text measured against the old swapchain is measured again.
+    fn reload_font_atlas(&mut self, ctx: &mut Context, render_context: &mut dyn RenderContext) {
+        self.atlas_reload_pending = false;
+        // The reload includes the glyphs a pending rebuild would have added
+        self.glyph_rebuild_pending = false;
+        self.last_glyph_rebuild = Instant::now();
+        self.load_fonts(ctx);
+
+        let fonts = ctx.fonts();
+        let texture = fonts.build_rgba32_texture();
+        // hudhook can't free a texture, the old one is left unused
+        match render_context.load_texture(texture.data, texture.width, texture.height) {
+            Ok(texture_id) => fonts.tex_id = texture_id,
+            Err(e) => tracing::error!("Failed to upload the font atlas: {:?}", e),
+        }
+
+        self.processor
+            .set_window(WindowMetrics::current(), self.aspect_bounds());
+        // Laid out for the old display size
+        self.retained.clear();
+    }
+
+    /// Font baked closest to `font_size`, imgui scales it the rest of the way.
+    fn font(&self, font_size: f32) -> *mut sys::ImFont {
+        match self.fonts.get(baked_font_index(&self.fonts, font_size)) {
+            Some(&(_, font)) if font != 0 => font as *mut sys::ImFont,
+            _ => unsafe { sys::igGetFont() },
+        }
+    }
+
+    /// Top left corner and size of a text on screen.
+    pub(crate) fn text_bounds(&self, ui: &Ui, entry: &TextEntry) -> ([f32; 2], [f32; 2]) {
+        let font_size = entry.font_size * self.font_scale;
+        let size = text_size(
+            self.font(font_size),
+            &entry.text,
+            font_size,
+            self.screen().wrap_width(entry),
+        );
+        // Text used to be drawn inside a window, top-left anchoring keeps its padding so
+        // placement doesn't change
+        let padding = ui.clone_style().window_padding;
+        (self.anchor.origin(entry.position, size, padding), size)
+    }
+
+    /// Draws a text with its colors, outline and plate included, at `alpha` times their opacity.
+    fn draw_text(&self, ui: &Ui, entry: &TextEntry, alpha: f32) {
+        let alpha = alpha * entry.distance_alpha * self.overlay_alpha();
+        let color_space = self.color_space();
+        let faded =
+            |color: [f32; 4]| color_space.color32([color[0], color[1], color[2], color[3] * alpha]);
+        let font_size = entry.font_size * self.font_scale;
+        let font = self.font(font_size);
+        let wrap_width = self.screen().wrap_width(entry);
+        let (position, size) = self.text_bounds(ui, entry);
+
+        // One pixel at the base font size, growing with the text so the outline stays visible
+        let offset_scale = (font_size / BASE_IMGUI_FONT_SIZE_PX).max(1.0);
+
+        // Goes into the same draw list first, so the text ends up on top of it
+        let layer = self
+            .categories
+            .layer(entry.category)
+            .unwrap_or(self.layers.text);
+        if self.background {
+            let padding = PLATE_PADDING * offset_scale;
+            layer
+                .draw_list(ui)
+                .add_rect(
+                    [position[0] - padding, position[1] - padding],
+                    [
+                        position[0] + size[0] + padding,
+                        position[1] + size[1] + padding,
+                    ],
+                    faded(self.background_color),
+                )
+                .filled(true)
+                .rounding(self.background_rounding)
+                .build();
+        }
+        for offset in self.outline.offsets() {
+            add_text_with_size(
+                layer.raw_draw_list(),
+                font,
+                [
+                    position[0] + offset[0] * offset_scale,
+                    position[1] + offset[1] * offset_scale,
+                ],
+                font_size,
+                faded(self.outline_color),
+                &entry.text,
+                wrap_width,
+            );
+        }
+
+        // Emphasized text is drawn a second time one pixel to the right, thickening it
+        let passes = if entry.emphasis { 2 } else { 1 };
+        for pass in 0..passes {
+            add_text_with_size(
+                layer.raw_draw_list(),
+                font,
+                [position[0] + pass as f32 * offset_scale, position[1]],
+                font_size,
+                faded(entry.color),
+                &entry.text,
+                wrap_width,
+            );
+        }
+
+        if self.offscreen_indicator && entry.offscreen_direction != [0.0, 0.0] {
+            draw_offscreen_arrow(
+                &layer.draw_list(ui),
+                entry.position,
+                entry.offscreen_direction,
+                font_size,
+                faded(entry.color),
+            );
+        }
+    }
+
+    /// Range the aspect correction is kept in, `None` in exact mode.
+    fn aspect_bounds(&self) -> Option<(f32, f32)> {
+        match self.aspect_mode {
+            AspectMode::Clamp => Some((self.aspect_min, self.aspect_max.max(self.aspect_min))),
+            AspectMode::Exact => None,
+        }
+    }
+
+    /// Measures the ratio between the size the overlay draws at and the game's render size.
+    ///
+    /// With an upscaler the game positions text in its lower internal resolution while the
+    /// overlay draws on the upscaled output, whose size hudhook takes from the swapchain.
+    fn update_render_scale(&mut self, ui: &Ui) {
+        let internal_size = Self::get_screen_size();
+        let display_size = ui.io().display_size;
+        self.display_size = if display_size[0] > 0.0 && display_size[1] > 0.0 {
+            display_size
+        } else {
+            internal_size
+        };
+
+        let render_scale = match self.render_scale_override {
+            Some(scale) => [scale, scale],
+            None => [
+                self.display_size[0] / internal_size[0],
+                self.display_size[1] / internal_size[1],
+            ],
+        };
+        if render_scale != self.render_scale {
+            tracing::info!(
+                "Render scale {:?} (output {:?}, internal {:?})",
+                render_scale,
+                self.display_size,
+                internal_size
+            );
+            self.render_scale = render_scale;
+        }
+    }
+
+    /// Corrects the stored text scales again when the window size or mode changed, or the
+    /// aspect settings did.
+    fn track_window_metrics(&mut self) {
+        let metrics = WindowMetrics::current();
+        let bounds = self.aspect_bounds();
+        let applied = self.processor.window_metrics();
+        if metrics == applied && bounds == self.processor.aspect_bounds() {
+            return;
+        }
+
+        if metrics != applied {
+            tracing::info!(
+                "Window changed from {:?} to {:?}, rescaling text",
+                applied,
+                metrics
+            );
+        }
+        self.processor.set_window(metrics, bounds);
+    }
+
+    fn get_screen_size() -> [f32; 2] {
+        if let Ok(Some(window)) = unsafe { get_instance::<CSWindowImp>() } {
+            [window.screen_width as f32, window.screen_height as f32]
+        } else {
+            fallback_screen_size()
+        }
+    }
+
+    fn window_size() -> [f32; 2] {
+        if let Ok(Some(window)) = unsafe { get_instance::<CSWindowImp>() } {
+            match window.persistent_window_config.window_type {
+                CSWindowType::Windowed => [
+                    window.persistent_window_config.windowed_screen_width as f32,
+                    window.persistent_window_config.windowed_screen_height as f32,
+                ],
+                CSWindowType::Fullscreen => [
+                    window.persistent_window_config.fullscreen_width as f32,
+                    window.persistent_window_config.fullscreen_height as f32,
+                ],
+                CSWindowType::Borderless => [
+                    window.persistent_window_config.borderless_screen_width as f32,
+                    window.persistent_window_config.borderless_screen_height as f32,
+                ],
+            }
+        } else {
+            fallback_screen_size()
+        }
+    }
+
+    /// Processes the commands of a frame and draws everything that's shown.
+    fn render_frame(&mut self, ui: &Ui) {
+        FRAME_COUNTER.fetch_add(1, Ordering::Relaxed);
+        self.sync_config();
+        self.update_render_scale(ui);
+        self.track_window_metrics();
+
+        self.collect_frame();
+        if self.frozen {
+            // Still drained so the queue doesn't fill up and replay stale text on unfreezing
+            self.frozen_commands += self.frame.len() as u64;
+            self.frame.clear();
+        }
+        // A replay has no game to be in a state
+        self.hidden_in = (!replay::is_replaying())
+            .then(|| hiding_state(&self.auto_hide))
+            .flatten();
+        if self.hidden_in.is_some() {
+            // Hidden text can't be inspected, and the mouse would stay captured
+            self.inspecting = false;
+        }
+        let dump_frame = self.hidden_in.is_none() || self.auto_hide.dump_while_hidden;
+        let mut frame = std::mem::take(&mut self.frame);
+        crash::record_commands(&frame);
+        self.texts.clear();
+        self.shapes.clear();
+        // Text past max_texts_per_frame is counted instead of drawn
+        let mut positional_texts = 0;
+        let mut capped_texts = 0;
+        // Read for the first world text of the frame
+        let mut camera = None;
+        let screen = self.screen();
+        for queued in frame.drain(..) {
+            if let Some(dump) = &mut self.dump
+                && dump_frame
+                && dump.format() == DumpFormat::Jsonl
+            {
+                dump.write_command(&queued);
+            }
+            if let Some(pipe) = &self.pipe
+                && (self.config.pipe.all_commands
+                    || matches!(queued.command, DrawCommand::Text { .. }))
+            {
+                pipe.send(&queued);
+            }
+            // Before callers or stamps are added to the text, it's the key dedup used
+            let mut source = None;
+            let command = match queued.command {
+                DrawCommand::Text {
+                    ez_draw,
+                    text,
+                    caller,
+                    x,
+                    y,
+                    font_size,
+                    box_width,
+                    world_z,
+                } => {
+                    source = Some(TextId::of_command(&text, x, y));
+                    self.watches.observe(&text);
+                    self.alerts.observe(&text);
+                    self.stats.observe(caller, &text);
+                    self.history
+                        .push(&text, [x, y], caller, queued.frame, queued.time_us);
+                    if let Some(dump) = &mut self.dump
+                        && dump_frame
+                        && dump.format() == DumpFormat::Text
+                    {
+                        let font_size =
+                            font_size.unwrap_or_else(|| self.processor.font_size(&screen, ez_draw));
+                        dump.write_text(
+                            queued.frame,
+                            queued.time_us,
+                            [x, y],
+                            font_size,
+                            caller,
+                            &text,
+                        );
+                    }
+                    if self.presentation.is_panel() {
+                        self.source_panel.push(caller, text.clone());
+                    }
+                    if !self.presentation.is_positional() {
+                        continue;
+                    }
+                    let max_texts = self.max_texts_per_frame;
+                    if max_texts != 0 && positional_texts >= max_texts {
+                        capped_texts += 1;
+                        continue;
+                    }
+                    positional_texts += 1;
+                    let text = match caller {
+                        Some(rva) if self.show_callers => {
+                            if self.log_captured_text {
+                                tracing::trace!(
+                                    "Text: {} at ({}, {}), from {:#x}",
+                                    text,
+                                    x,
+                                    y,
+                                    rva
+                                );
+                            }
+                            Arc::from(format!("[{rva:#x}] {text}"))
+                        }
+                        _ => {
+                            if self.log_captured_text {
+                                tracing::trace!("Text: {} at ({}, {})", text, x, y);
+                            }
+                            text
+                        }
+                    };
+                    let text = if self.show_stamps {
+                        Arc::from(format!(
+                            "[#{} {:.3}s] {}",
+                            queued.frame,
+                            queued.time_us as f64 / 1_000_000.0,
+                            text
+                        ))
+                    } else {
+                        text
+                    };
+                    match world_z {
+                        None => DrawCommand::Text {
+                            ez_draw,
+                            text,
+                            caller,
+                            x,
+                            y,
+                            font_size,
+                            box_width,
+                            world_z,
+                        },
+                        Some(z) => {
+                            let camera = camera.get_or_insert_with(Camera::current);
+                            // Behind the camera, too far away or drawn before there's a camera
+                            let world_text = &self.config.world_text;
+                            let Some((projection, scale)) = camera.as_ref().and_then(|camera| {
+                                let projection = camera.project([x, y, z], self.display_size)?;
+                                let scale = distance_scale(projection.distance, world_text)?;
+                                Some((projection, scale))
+                            }) else {
+                                continue;
+                            };
+                            let font_size = font_size
+                                .unwrap_or_else(|| self.processor.font_size(&screen, ez_draw));
+                            let position = self.world_smoothing.smooth(
+                                caller,
+                                &text,
+                                projection.position,
+                                world_text,
+                            );
+                            let alpha = distance_alpha(projection.distance, world_text);
+                            let entries = self.processor.resolve_text(
+                                &screen,
+                                ez_draw,
+                                &text,
+                                TextOrigin::Display(position),
+                                Some(font_size * scale),
+                                box_width,
+                            );
+                            self.texts
+                                .extend(entries.into_iter().map(|entry| TextEntry {
+                                    caller,
+                                    sequence: queued.sequence,
+                                    frame: queued.frame,
+                                    time_us: queued.time_us,
+                                    depth: Some(projection.depth),
+                                    distance_alpha: alpha,
+                                    source,
+                                    ..entry
+                                }));
+                            continue;
+                        }
+                    }
+                }
+                DrawCommand::WorldLine { ez_draw, from, to } => {
+                    if self.world_shapes.lines {
+                        let shape = WorldShape::Line { from, to };
+                        self.push_world_shape(&mut camera, ez_draw, shape);
+                    }
+                    continue;
+                }
+                DrawCommand::WorldSphere {
+                    ez_draw,
+                    center,
+                    radius,
+                } => {
+                    if self.world_shapes.spheres {
+                        let shape = WorldShape::Sphere { center, radius };
+                        self.push_world_shape(&mut camera, ez_draw, shape);
+                    }
+                    continue;
+                }
+                DrawCommand::WorldCapsule {
+                    ez_draw,
+                    from,
+                    to,
+                    radius,
+                } => {
+                    if self.world_shapes.capsules {
+                        let shape = WorldShape::Capsule { from, to, radius };
+                        self.push_world_shape(&mut camera, ez_draw, shape);
+                    }
+                    continue;
+                }
+                command => {
+                    if self.log_captured_text {
+                        trace_state_command(&command);
+                    }
+                    command
+                }
+            };
+            let (texts, shapes) = self
+                .processor
+                .process(&screen, std::slice::from_ref(&command));
+            self.texts.extend(texts.into_iter().map(|entry| TextEntry {
+                sequence: queued.sequence,
+                frame: queued.frame,
+                time_us: queued.time_us,
+                source,
+                ..entry
+            }));
+            self.shapes.extend(shapes);
+        }
+        self.frame = frame;
+        if capped_texts > 0 {
+            self.cap_hits += 1;
+        }
+
+        let mut texts = std::mem::take(&mut self.texts);
+        // The queue holds the game's submission order, which shifts between frames
+        texts.sort_by(TextEntry::draw_order);
+        for entry in &mut texts {
+            self.note_missing_glyphs(&entry.text);
+            self.categories.classify(entry);
+            self.highlighter.apply(entry);
+            if self.diff {
+                // Compared before stacking, which moves text by what's drawn above it
+                match self.text_diff.observe(entry, self.config.text.diff_frames) {
+                    Some(Change::New) => entry.color = self.config.text.diff_new_color,
+                    Some(Change::Changed) => entry.color = self.config.text.diff_changed_color,
+                    None => {}
+                }
+            }
+        }
+        // A frame without text keeps the last one on screen, it's compared to the next
+        if self.diff && !texts.is_empty() {
+            self.text_diff.finish_frame();
+        }
+        stack_overlapping(&mut texts, self.stack_tolerance, |entry| {
+            let font_size = entry.font_size * self.font_scale;
+            text_size(
+                self.font(font_size),
+                &entry.text,
+                font_size,
+                screen.wrap_width(entry),
+            )[1]
+        });
+        self.texts = texts;
+
+        // The game doesn't submit text on every render frame, keep showing the last
+        // submitted set until new text arrives or it goes stale.
+        if !self.texts.is_empty() {
+            self.keep_suppressed_text();
+            std::mem::swap(&mut self.texts, &mut self.retained);
+            if self.fade {
+                self.fades.replace(self.texts.drain(..), &self.retained);
+            }
+            self.retained_capped = capped_texts;
+            self.retained_at = Instant::now();
+        } else if !self.frozen && self.retained_at.elapsed() >= self.retain_timeout {
+            if self.fade && !self.retained.is_empty() {
+                self.fades.replace(self.retained.drain(..), &[]);
+            }
+            self.retained.clear();
+            self.retained_capped = 0;
+        }
+        if !self.shapes.is_empty() {
+            std::mem::swap(&mut self.shapes, &mut self.retained_shapes);
+            self.retained_shapes_at = Instant::now();
+        } else if !self.frozen && self.retained_shapes_at.elapsed() >= self.retain_timeout {
+            self.retained_shapes.clear();
+        }
+        if self.fade {
+            self.fades
+                .prune(Duration::from_millis(self.config.text.fade_out_ms));
+        }
+        self.world_smoothing.finish_frame();
+        if !self.frozen {
+            self.world_smoothing.prune();
+        }
+        if !self.frozen {
+            self.source_panel.finish_frame(self.retain_timeout);
+            self.history.finish_frame();
+        }
+        if let Some(mirror) = &mut self.mirror {
+            mirror.update(
+                self.retained.iter().map(|entry| entry.text.as_str()),
+                Duration::from_millis(self.config.shared_memory.update_interval_ms),
+            );
+        }
+
+        if self.toggle_key.poll() {
+            self.enabled = !self.enabled;
+            // Hidden text can't be inspected, and the mouse would stay captured
+            self.inspecting &= self.enabled;
+            tracing::info!(
+                "Overlay {}",
+                if self.enabled { "enabled" } else { "disabled" }
+            );
+        }
+
+        if self.settings_key.poll() {
+            self.settings_open = !self.settings_open;
+        }
+
+        if self.diagnostics_key.poll() {
+            self.diagnostics_open = !self.diagnostics_open;
+        }
+
+        if self.history_key.poll() {
+            self.history_open = !self.history_open;
+        }
+
+        self.stats.decay();
+        if self.stats_key.poll() {
+            self.stats_open = !self.stats_open;
+            set_capture_callers(self.needs_callers());
+        }
+
+        if self.console_key.poll() {
+            console::toggle_console();
+        }
+
+        if self.dump_key.poll() {
+            self.toggle_dump();
+        }
+        if self.hidden_in.is_none() {
+            self.draw_dump_indicator(ui);
+        }
+
+        if self.copy_key.poll() {
+            self.copy_visible_text();
+        }
+        if self.inspect_key.poll() {
+            self.inspecting = !self.inspecting && self.enabled && self.hidden_in.is_none();
+        }
+        if self.inspecting {
+            self.draw_inspector(ui);
+        }
+        self.draw_notice(ui);
+        self.draw_toasts(ui);
+        self.draw_alert_flash(ui);
+
+        if replay::is_replaying() {
+            if self.replay_pause_key.poll() {
+                replay::toggle_pause();
+            }
+            if self.replay_step_key.poll() {
+                replay::step();
+            }
+        }
+        self.draw_replay_indicator(ui);
+
+        if self.freeze_key.poll() {
+            self.frozen = !self.frozen;
+            if self.frozen {
+                self.frozen_commands = 0;
+            }
+        }
+        if self.frozen {
+            // Below the recording and replay indicators
+            let y = 10.0 + ui.current_font_size() * 3.0;
+            ui.get_foreground_draw_list().add_text(
+                [10.0, y],
+                self.color_space().rgb(120, 220, 255),
+                format!("FROZEN ({} commands ignored)", self.frozen_commands),
+            );
+        }
+
+        // Only what's drawn depends on it, so the text comes back as it was on focusing again
+        self.focused = is_game_focused();
+        let shown = self.enabled
+            && self.hidden_in.is_none()
+            && (self.focused || self.unfocused != UnfocusedMode::Hide);
+        if shown {
+            // Shapes go first so the labels drawn next to them stay readable
+            self.draw_shapes(ui);
+            if self.fade {
+                let fade_out = Duration::from_millis(self.config.text.fade_out_ms);
+                for (entry, alpha) in self.fades.fading_out(fade_out) {
+                    if !self.categories.is_shown(entry.category) {
+                        continue;
+                    }
+                    self.draw_text(ui, entry, alpha);
+                }
+            }
+            let fade_in = Duration::from_millis(self.config.text.fade_in_ms);
+            for entry in &self.retained {
+                if !self.categories.is_shown(entry.category) {
+                    continue;
+                }
+                let alpha = if self.fade {
+                    self.fades.fade_in_alpha(entry, fade_in)
+                } else {
+                    1.0
+                };
+                self.draw_text(ui, entry, alpha);
+            }
+            if self.retained_capped > 0 {
+                let y = ui.io().display_size[1] - 10.0 - ui.current_font_size();
+                ui.get_foreground_draw_list().add_text(
+                    [10.0, y],
+                    self.color_space().rgb(255, 200, 80),
+                    format!("+{} more (capped)", self.retained_capped),
+                );
+            }
+        }
+
+        if shown && self.presentation.is_panel() {
+            self.draw_source_panel(ui);
+        }
+
+        if shown && !self.watches.is_empty() {
+            self.draw_watches(ui);
+        }
+
+        if self.diagnostics_open {
+            self.draw_diagnostics(ui);
+        }
+
+        if self.history_open {
+            self.draw_history(ui);
+        }
+
+        if self.stats_open {
+            self.draw_stats(ui);
+        }
+
+        if self.settings_open {
+            self.draw_settings(ui);
+        }
+
+        self.draw_block_menu(ui);
+    }
+}
+
+impl ImguiRenderLoop for DebugTextRender {
+    fn initialize(&mut self, ctx: &mut Context, _render_context: &mut dyn RenderContext) {
+        configure_context(ctx);
+
+        if self.config.dump.enabled {
+            self.start_dump();
+        }
+        if self.config.pipe.enabled {
+            match PipeServer::start(&self.config.pipe.name) {
+                Ok(pipe) => self.pipe = Some(pipe),
+                Err(e) => tracing::error!("Failed to start the pipe server: {}", e),
+            }
+        }
+        if self.config.shared_memory.enabled {
+            let settings = &self.config.shared_memory;
+            match SharedTextMirror::create(&settings.name, settings.size_kb * 1024) {
+                Ok(mirror) => self.mirror = Some(mirror),
+                Err(e) => tracing::error!(
+                    "Failed to create the shared memory {}: {}",
+                    settings.name,
+                    e
+                ),
+            }
+        }
+
+        self.read_font_files();
+        self.load_fonts(ctx);
+        self.atlas_surface = (ctx.io().display_size, WindowMetrics::current());
+        self.detect_output();
+    }
+
+    fn render(&mut self, ui: &mut Ui) {
+        keep_draw_data_non_empty();
+
+        if guard::is_overlay_disabled() {
+            return;
+        }
+
+        if guard::catch(|| self.render_frame(ui)).is_none() {
+            tracing::error!("Rendering panicked");
+        }
+    }
+
+    fn before_render(&mut self, ctx: &mut Context, render_context: &mut dyn RenderContext) {
+        // The game hides its cursor, draw imgui's own while the overlay takes input
+        ctx.io_mut().mouse_draw_cursor = self.takes_input() || self.inspecting;
+
+        // The atlas can only change outside of a frame
+        self.track_atlas_surface(ctx);
+        if self.atlas_reload_pending && !guard::is_overlay_disabled() {
+            if guard::catch(|| self.reload_font_atlas(ctx, render_context)).is_none() {
+                tracing::error!("Reloading the font atlas panicked");
+            }
+        } else if self.glyph_rebuild_pending
+            && self.last_glyph_rebuild.elapsed() >= GLYPH_REBUILD_INTERVAL
+            && !guard::is_overlay_disabled()
+            && guard::catch(|| self.rebuild_font_atlas(ctx, render_context)).is_none()
+        {
+            tracing::error!("Rebuilding the font atlas panicked");
+        }
+    }
+
+    fn message_filter(&self, _io: &Io) -> MessageFilter {
+        if self.takes_input() {
+            MessageFilter::InputMouse | MessageFilter::InputKeyboard
+        } else if self.inspecting {
+            // Only the mouse, the game keeps its keyboard
+            MessageFilter::InputMouse
+        } else {
+            MessageFilter::empty()
+        }
+    }
+}
+
+/// Applies the settings the detours read when they capture text.
+///
+/// Whether callers are looked up also depends on the windows that are open, the render loop
+/// sets it when it picks up the config.
+fn apply_capture_settings(config: &Config) {
+    set_filter(&config.filter);
+    dedup::set_window(Duration::from_millis(config.duplicate_window_ms));
+    set_max_text_length(config.max_text_length);
+    set_passthrough_native(config.passthrough_native);
+}
+
+fn init(hinst: HINSTANCE) {
+    let dll_dir = dll_directory(hinst);
+    let config_path = dll_dir.as_ref().map(|dir| dir.join(CONFIG_FILE_NAME));
+    let (config, config_error) = match config_path.as_deref().map(Config::load) {
+        Some(Ok(config)) => (config, None),
+        Some(Err(e)) => (Config::default(), Some(e)),
+        None => (Config::default(), None),
+    };
+
+    setup_logging(
+        config.log_level(),
+        &config.log_directives,
+        dll_dir.as_deref(),
+        config.log_max_size_mb * 1024 * 1024,
+        config.log_max_files,
+        config.console,
+    );
+
+    std::panic::set_hook(Box::new(custom_panic_hook));
+    crash::set_loaded_config(&config);
+    if config.minidump
+        && let Some(dir) = &dll_dir
+    {
+        minidump::install_fault_handler(hinst, dir.clone());
+    }
+
+    // Replaying doesn't need the game, the host may not even be Elden Ring
+    let replay_path = config.replay.path.as_ref().map(|path| match &dll_dir {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.clone(),
+    });
+
+    if let Some(e) = config_error {
+        let path = config_path.as_deref().unwrap_or(Path::new(""));
+        tracing::error!("{}: {}, using defaults", path.display(), e);
+        notify(
+            Severity::Error,
+            format!("{}: {}\nThe default settings are used.", path.display(), e),
+        );
+    }
+    if config_path.is_none() {
+        tracing::warn!("Couldn't find the DLL path, using the default settings");
+    }
+    QUEUE_CAPACITY.store(config.queue_capacity, Ordering::Relaxed);
+    LazyLock::force(&CAPTURE_EPOCH);
+
+    // Waiting for a module to load can't happen in DllMain, it holds the loader lock
+    let target_loaded = replay_path.is_some() || is_target_loaded(&config.target);
+    let activate_now = config.is_enabled() && !config.lazy && target_loaded;
+    if activate_now {
+        // Still in DllMain, so the game's first debug text is already captured
+        install_capture(&config, replay_path.as_deref());
+    } else if config.is_enabled() && !config.lazy {
+        tracing::info!(
+            "textrender {} loaded, nothing is hooked until {} is loaded",
+            env!("CARGO_PKG_VERSION"),
+            config.target.module.as_deref().unwrap_or_default()
+        );
+    } else if config.is_enabled() {
+        tracing::info!(
+            "textrender {} loaded, nothing is hooked until {} is pressed (lazy = true)",
+            env!("CARGO_PKG_VERSION"),
+            config.hotkeys.toggle
+        );
+    } else {
+        tracing::info!(
+            "textrender {} loaded, the overlay is disabled (enabled = false or {}), nothing is \
+             hooked until the config enables it",
+            env!("CARGO_PKG_VERSION"),
+            ENABLED_ENV_VAR
+        );
+    }
+
+    let shared_config = Arc::new(RwLock::new(config.clone()));
+    std::thread::spawn(move || {
+        let mut watcher = config_path
+            .clone()
+            .map(|path| ConfigWatcher::new(path, config));
+        let mut notice = None;
+        if !activate_now {
+            let Some(config) = wait_for_activation(watcher.as_mut(), &shared_config) else {
+                return;
+            };
+            tracing::info!("Bringing the overlay up");
+            notice = install_capture(&config, replay_path.as_deref());
+        }
+        start_renderer(hinst, &shared_config, config_path, replay_path, notice);
+
+        let Some(watcher) = watcher.as_mut() else {
+            return;
+        };
+        let mut warned_disable = false;
+        loop {
+            std::thread::sleep(CONFIG_POLL_INTERVAL);
+            if let Some(config) = watcher.poll() {
+                if !config.is_enabled() && !warned_disable {
+                    tracing::warn!(
+                        "enabled = false only keeps the overlay from coming up, it stays until \
+                         the game is restarted"
+                    );
+                    warned_disable = true;
+                }
+                apply_hook_settings(&config.hooks);
+                apply_capture_settings(&config);
+                set_log_filter(config.log_level(), &config.log_directives);
+                crash::set_loaded_config(&config);
+                *shared_config.write().unwrap() = config;
+            }
+        }
+    });
+}
+
+/// Waits until the overlay may come up: until the config enables it and, with `lazy`, until
+/// `hotkeys.toggle` is pressed. `None` when it's disabled and there's no config file to enable it.
+fn wait_for_activation(
+    mut watcher: Option<&mut ConfigWatcher>,
+    shared_config: &RwLock<Config>,
+) -> Option<Config> {
+    let mut config = shared_config.read().unwrap().clone();
+    let mut activate_key = Hotkey::new(parse_key(&config.hotkeys.toggle).unwrap_or(VK_F10));
+    let mut config_polled_at = Instant::now();
+    loop {
+        if config.is_enabled() {
+            if !config.lazy || activate_key.poll() {
+                return Some(config);
+            }
+        } else if watcher.is_none() {
+            return None;
+        }
+
+        std::thread::sleep(ACTIVATION_POLL_INTERVAL);
+        if config_polled_at.elapsed() < CONFIG_POLL_INTERVAL {
+            continue;
+        }
+        config_polled_at = Instant::now();
+        if let Some(watcher) = watcher.as_deref_mut()
+            && let Some(reloaded) = watcher.poll()
+        {
+            activate_key = Hotkey::new(parse_key(&reloaded.hotkeys.toggle).unwrap_or(VK_F10));
+            set_log_filter(reloaded.log_level(), &reloaded.log_directives);
+            crash::set_loaded_config(&reloaded);
+            *shared_config.write().unwrap() = reloaded.clone();
+            config = reloaded;
+        }
+    }
+}
+
+/// Resolves and installs the detours capturing the game's debug drawing, unless a replay
+/// provides the commands instead.
+///
+/// Only the first call installs anything. Returns a line for the screen saying how it went,
+/// `None` when it was installed already.
+fn install_capture(config: &Config, replay_path: Option<&Path>) -> Option<String> {
+    if CAPTURE_INSTALLED.swap(true, Ordering::AcqRel) {
+        return None;
+    }
+
+    if let Some(replay_path) = replay_path {
+        tracing::info!(
+            "textrender {} loaded, replaying {} without hooking the game, config: {:?}",
+            env!("CARGO_PKG_VERSION"),
+            replay_path.display(),
+            config
+        );
+    } else {
+        let program = match select_target(&config.target) {
+            Ok(program) => program,
+            Err(e) => {
+                tracing::error!("Not hooking the game: {}", e);
+                let message = format!(
+                    "{e}, so the debug text overlay doesn't hook it.\n\nUpdate or remove the \
+                     [target] settings in {CONFIG_FILE_NAME}."
+                );
+                notify(Severity::Fatal, message);
+                return Some(format!("Overlay not hooked: {e}"));
+            }
+        };
+        let resolved = resolve_hooks(&program, config);
+        tracing::info!(
+            "textrender {} loaded, hooks: {}, config: {:?}",
+            env!("CARGO_PKG_VERSION"),
+            resolved_summary(),
+            config
+        );
+        if let Err(e) = resolved {
+            tracing::error!("Not hooking the game: {}", e);
+            let message = format!(
+                "The {e}, so the debug text overlay doesn't hook the game.\n\nThe version is \
+                 listed in textrender.log, the addresses for it can be set in the [rvas] \
+                 settings in {CONFIG_FILE_NAME}."
+            );
+            notify(Severity::Fatal, message);
+            return Some(format!("Overlay not hooked: the {e}"));
+        }
+    }
+
+    // Set before the hooks go in, so the first captured text already uses them
+    apply_capture_settings(config);
+    set_capture_callers(config.needs_callers());
+
+    let failures = if replay_path.is_none() {
+        install_hooks()
+    } else {
+        Vec::new()
+    };
+    apply_hook_settings(&config.hooks);
+    if !failures.is_empty() {
+        let message = failures
+            .iter()
+            .map(|(hook, e)| format!("{} hook failed: {}", hook.name(), e))
+            .collect::<Vec<_>>()
+            .join("\n");
+        notify(Severity::Error, message);
+    }
+
+    let enabled = HookId::ALL
+        .iter()
+        .filter(|&&hook| is_hook_enabled(hook))
+        .count();
+    Some(match failures.len() {
+        _ if replay_path.is_some() => "Overlay active, replaying".to_string(),
+        0 => format!("Overlay active, {enabled} hooks enabled"),
+        failed => format!("Overlay active, {failed} hooks failed to install, see the log"),
+    })
+}
+
+/// Hooks the renderer once the game is up and starts the replay, if there is one. Runs on the
+/// config thread, after DllMain returned.
+fn start_renderer(
+    hinst: HINSTANCE,
+    shared_config: &Arc<RwLock<Config>>,
+    config_path: Option<PathBuf>,
+    replay_path: Option<PathBuf>,
+    notice: Option<String>,
+) {
+    if replay_path.is_none() && !wait_for_game(shared_config, config_path.as_deref()) {
+        return;
+    }
+
+    let overlays = find_hudhook_overlays(hinst);
+    if overlays.is_empty() {
+        hook_renderer(shared_config.clone(), config_path.clone(), notice);
+    } else if shared_config.read().unwrap().ignore_overlay_conflicts {
+        tracing::warn!(
+            "Other hudhook overlays are loaded ({}), hooking the renderer anyway since \
+                 ignore_overlay_conflicts is set",
+            overlays.join(", ")
+        );
+        hook_renderer(shared_config.clone(), config_path.clone(), notice);
+    } else {
+        tracing::error!(
+            "Other hudhook overlays are loaded ({}), the overlay is disabled",
+            overlays.join(", ")
+        );
+        // Nothing would drain the captured text
+        uninstall_hooks();
+        let message = format!(
+            "Another overlay built on hudhook is loaded: {}. Two of them hooking the same \
+                 swapchain usually crashes the game, so the debug text overlay is disabled.\n\n\
+                 Remove one of the mods, or set ignore_overlay_conflicts = true in {} to load \
+                 both anyway.",
+            overlays.join(", "),
+            CONFIG_FILE_NAME
+        );
+        notify(Severity::Fatal, message);
+    }
+
+    if let Some(replay_path) = replay_path {
+        let replay = shared_config.read().unwrap().replay.clone();
+        if let Err(e) = replay::start_replay(&replay_path, replay.speed, replay.repeat) {
+            tracing::error!("Failed to replay {}: {}", replay_path.display(), e);
+            notify(
+                Severity::Error,
+                format!("Failed to replay {}: {}", replay_path.display(), e),
+            );
+        }
+    }
+}
+
+/// Waits for the game to finish starting, `false` when the renderer shouldn't be hooked since
+/// that timed out and `on_init_timeout` is `dump`.
+fn wait_for_game(shared_config: &RwLock<Config>, config_path: Option<&Path>) -> bool {
+    let (timeout_s, on_timeout) = {
+        let config = shared_config.read().unwrap();
+        (config.init_timeout_s, config.on_init_timeout)
+    };
+    let timeout = match timeout_s {
+        0 => Duration::MAX,
+        seconds => Duration::from_secs(seconds),
+    };
+    let started = Instant::now();
+    let result = wait_for_system_init(&target_program(), timeout);
+    let elapsed = started.elapsed().as_secs_f32();
+    let e = match result {
+        Ok(()) => {
+            tracing::info!("The game finished starting after {:.1}s", elapsed);
+            return true;
+        }
+        Err(e) => e,
+    };
+    tracing::warn!(
+        "Waiting for the game to finish starting failed after {:.1}s: {}",
+        elapsed,
+        e
+    );
+    if on_timeout == InitTimeout::Hook {
+        tracing::warn!("Hooking the renderer anyway since on_init_timeout = \"hook\"");
+        return true;
+    }
+
+    let config = shared_config.read().unwrap().clone();
+    let message = match dump_without_renderer(&config, config_path) {
+        Ok(path) => {
+            tracing::info!(
+                "Not hooking the renderer, writing captured text to {}",
+                path.display()
+            );
+            format!(
+                "The game didn't finish starting within {timeout_s}s as far as the debug text \
+                 overlay can tell, so it isn't drawn. The captured text is written to {} \
+                 instead.\n\nSet on_init_timeout = \"hook\" in {CONFIG_FILE_NAME} to draw it \
+                 anyway, or raise init_timeout_s if the game takes that long to start.",
+                path.display()
+            )
+        }
+        Err(e) => {
+            tracing::error!("Failed to start the dump, the overlay is disabled: {}", e);
+            // Nothing would drain the captured text
+            uninstall_hooks();
+            format!(
+                "The game didn't finish starting within {timeout_s}s as far as the debug text \
+                 overlay can tell, and writing the captured text to the dump failed: {e}. The \
+                 overlay is disabled."
+            )
+        }
+    };
+    notify(Severity::Fatal, message);
+    false
+}
+
+/// Hooks the renderer the config selects and records which one was hooked.
+///
+/// `notice` is shown once the overlay draws, to confirm it came up after starting lazily.
+fn hook_renderer(
+    shared_config: Arc<RwLock<Config>>,
+    config_path: Option<PathBuf>,
+    notice: Option<String>,
+) {
+    let configured_api = shared_config.read().unwrap().graphics_api;
+    let api = configured_api.resolve();
+    let render_loop = DebugTextRender::new(shared_config, config_path);
+    if let Some(notice) = notice {
+        notify(Severity::Info, notice);
+    }
+    match apply_render_hooks(api, render_loop) {
+        Ok(()) => {
+            tracing::info!(
+                "Hooked the {} renderer (graphics_api = {})",
+                api.name(),
+                configured_api.name()
+            );
+            set_hooked_backend(api);
+        }
+        Err(e) => {
+            tracing::error!(
+                "Failed to apply the {} ImGui hooks after {} attempts: {:?}, giving up",
+                api.name(),
+                RENDER_HOOK_ATTEMPTS,
+                e
+            );
+            let message = format!(
+                "The overlay couldn't hook the {} renderer: {:?}\n\nAnother overlay such as the \
+                 Steam overlay, RivaTuner Statistics Server or ReShade may already hook it, try \
+                 disabling them. The game keeps running without debug text.",
+                api.name(),
+                e
+            );
+            notify(Severity::Fatal, message);
+        }
+    }
+}
+
+/// Hooks the renderer of `api`, retrying while another overlay holds on to the swapchain.
+///
+/// hudhook creates its hooks when they're added to the builder and only once, so the retries
+/// only enable them again.
+fn apply_render_hooks(api: GraphicsApi, render_loop: DebugTextRender) -> Result<(), MH_STATUS> {
+    let builder = match api {
+        GraphicsApi::Dx11 => Hudhook::builder().with::<ImguiDx11Hooks>(render_loop),
+        _ => Hudhook::builder().with::<ImguiDx12Hooks>(render_loop),
+    };
+    let mut result = builder.build().apply();
+    for attempt in 2..=RENDER_HOOK_ATTEMPTS {
+        let Err(e) = result else {
+            break;
+        };
+        tracing::warn!(
+            "Failed to apply the {} ImGui hooks on attempt {}/{}: {:?}, retrying in {:?}",
+            api.name(),
+            attempt - 1,
+            RENDER_HOOK_ATTEMPTS,
+            e,
+            RENDER_HOOK_RETRY_INTERVAL
+        );
+        std::thread::sleep(RENDER_HOOK_RETRY_INTERVAL);
+
+        // A null target is MH_ALL_HOOKS, the only minhook hooks are hudhook's
+        result = unsafe { MH_QueueEnableHook(std::ptr::null_mut()) }
+            .ok()
+            .and_then(|()| unsafe { MH_ApplyQueued() }.ok());
+        if result.is_ok() {
+            tracing::info!(
+                "Applied the {} ImGui hooks on attempt {}",
+                api.name(),
+                attempt
+            );
+        }
+    }
+    result
+}
+
+/// Removes the hooks and flushes the log when the DLL is unloaded with `FreeLibrary`.
+///
+/// Ejecting isn't supported, the threads `init` starts have no way to stop and keep running
+/// the DLL's code after it's unloaded. This only keeps the game from calling into it.
+fn shutdown() {
+    uninstall_hooks();
+
+    // hudhook keeps the applied hooks to itself, its render hooks are minhook hooks so
+    // uninitializing minhook disables them without having to spawn a thread for `eject`. It was
+    // never initialized when the renderer wasn't hooked, e.g. with the overlay disabled
+    if let Err(e) = unsafe { MH_Uninitialize() }.ok()
+        && e != MH_STATUS::MH_ERROR_NOT_INITIALIZED
+    {
+        tracing::error!("Failed to remove ImGui hooks: {:?}", e);
+    }
+
+    while TEXT_RENDER_QUEUE.pop().is_some() {}
+
+    flush_logging();
+}
+
+/// DLL entry point function.
+///
+/// # Safety
+/// This function is safe to call when it's invoked by the Windows loader with valid parameters
+/// during DLL loading, unloading, and thread attach/detach events.
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub unsafe extern "C" fn DllMain(hinst: HINSTANCE, reason: u32, reserved: usize) -> bool {
+    if reason == DLL_PROCESS_ATTACH {
+        unsafe { DisableThreadLibraryCalls(hinst).ok() };
+
+        init(hinst);
+    } else if reason == DLL_PROCESS_DETACH {
+        // The process is exiting, its other threads are gone already and waiting on them,
+        // like flushing the log does, could hang the exit
+        if reserved != 0 {
+            return true;
+        }
+        shutdown();
+    };
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// imgui has a single current context, tests creating one take turns.
+    static CONTEXT: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn context_saves_no_window_settings() {
+        let _context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        let mut ctx = Context::create();
+        assert!(ctx.ini_filename().is_some());
+
+        configure_context(&mut ctx);
+        assert_eq!(ctx.ini_filename(), None);
+    }
+
+    #[test]
+    fn embedded_font_builds() {
+        assert_eq!(font_face_count(EMBEDDED_FONT), 1);
+
+        let _context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        let mut ctx = Context::create();
+        ctx.fonts().add_font(&[imgui::FontSource::TtfData {
+            data: EMBEDDED_FONT,
+            size_pixels: BASE_IMGUI_FONT_SIZE_PX,
+            config: None,
+        }]);
+        assert!(unsafe { sys::ImFontAtlas_Build(ctx.fonts().raw_mut()) });
+    }
+
+    /// Renders a frame without any text, `keep` decides whether the draw data is kept non-empty.
+    fn empty_frame_draw_lists(keep: bool) -> usize {
+        let _context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        let mut ctx = Context::create();
+        configure_context(&mut ctx);
+        ctx.io_mut().display_size = [1920.0, 1080.0];
+        ctx.fonts().build_rgba32_texture();
+
+        ctx.new_frame();
+        if keep {
+            keep_draw_data_non_empty();
+        }
+        ctx.render().draw_lists_count()
+    }
+
+    #[test]
+    fn empty_frames_keep_a_draw_list() {
+        // The frame hudhook's renderer can't handle
+        assert_eq!(empty_frame_draw_lists(false), 0);
+        assert_eq!(empty_frame_draw_lists(true), 1);
+    }
+}
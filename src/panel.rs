@@ -0,0 +1,127 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use hudhook::imgui::{Condition, TreeNodeFlags, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::DebugTextRender;
+use crate::caller::in_ranges;
+use crate::config::CallerLabel;
+use crate::layer::Layer;
+
+/// Where captured text is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Presentation {
+    /// At the position the game draws it at.
+    Positional,
+    /// In a window, grouped by the game code that drew it.
+    Panel,
+    Both,
+}
+
+impl Presentation {
+    pub const ALL: [Presentation; 3] = [
+        Presentation::Positional,
+        Presentation::Panel,
+        Presentation::Both,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Presentation::Positional => "positional",
+            Presentation::Panel => "panel",
+            Presentation::Both => "both",
+        }
+    }
+
+    pub fn is_positional(self) -> bool {
+        self != Presentation::Panel
+    }
+
+    pub fn is_panel(self) -> bool {
+        self != Presentation::Positional
+    }
+}
+
+/// Captured text grouped by the RVA of its caller, for the source panel.
+pub struct SourcePanel {
+    /// Text captured this frame.
+    pending: BTreeMap<Option<u32>, Vec<Arc<str>>>,
+    /// Text shown, the last frame that had any.
+    shown: BTreeMap<Option<u32>, Vec<Arc<str>>>,
+    shown_at: Instant,
+}
+
+impl SourcePanel {
+    pub fn new() -> Self {
+        Self {
+            pending: BTreeMap::new(),
+            shown: BTreeMap::new(),
+            shown_at: Instant::now(),
+        }
+    }
+
+    pub fn push(&mut self, caller: Option<u32>, text: Arc<str>) {
+        self.pending.entry(caller).or_default().push(text);
+    }
+
+    /// Shows the text pushed since the last call, or keeps showing the last set until it's been
+    /// around for `retain_timeout`, like the text drawn on screen.
+    pub fn finish_frame(&mut self, retain_timeout: Duration) {
+        if !self.pending.is_empty() {
+            self.shown = std::mem::take(&mut self.pending);
+            self.shown_at = Instant::now();
+        } else if self.shown_at.elapsed() >= retain_timeout {
+            self.shown.clear();
+        }
+    }
+}
+
+/// Name text from `caller` is grouped under, the first label covering it or the RVA itself.
+pub(crate) fn source_name(caller: Option<u32>, labels: &[CallerLabel]) -> String {
+    let Some(rva) = caller else {
+        return "Unknown caller".to_string();
+    };
+    labels
+        .iter()
+        .find(|label| in_ranges(rva, &[label.range]))
+        .map_or_else(|| format!("{rva:#x}"), |label| label.name.clone())
+}
+
+impl DebugTextRender {
+    /// Draws the window listing the shown text under one header per source.
+    pub(crate) fn draw_source_panel(&self, ui: &Ui) {
+        let mut groups: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for (caller, texts) in &self.source_panel.shown {
+            groups
+                .entry(source_name(*caller, &self.config.caller_labels))
+                .or_default()
+                .extend(texts.iter().map(|text| &**text));
+        }
+
+        let foreground = self.layers.panels == Layer::Foreground;
+        ui.window("Debug Text Sources")
+            .position([10.0, 300.0], Condition::FirstUseEver)
+            .bring_to_front_on_focus(foreground)
+            .focus_on_appearing(foreground)
+            .size([420.0, 500.0], Condition::FirstUseEver)
+            .build(|| {
+                if groups.is_empty() {
+                    ui.text_disabled("No text this frame");
+                }
+                for (name, texts) in &groups {
+                    // The count changes every frame, the id after ### keeps the header's state
+                    let header = format!("{} ({})###{}", name, texts.len(), name);
+                    if ui.collapsing_header(header, TreeNodeFlags::empty()) {
+                        for text in texts {
+                            ui.text(text);
+                        }
+                    }
+                }
+            });
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, SyncSender, TrySendError, sync_channel},
+    },
+    time::Instant,
+};
+
+use hudhook::windows::Win32::{
+    Foundation::{CloseHandle, ERROR_PIPE_CONNECTED, HANDLE},
+    Storage::FileSystem::{PIPE_ACCESS_OUTBOUND, WriteFile},
+    System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    },
+};
+use hudhook::windows::core::HSTRING;
+
+use crate::QueuedCommand;
+use crate::dump::DumpRecord;
+
+/// Lines waiting to be written to a client before it's considered too slow and disconnected.
+const CLIENT_QUEUE_CAPACITY: usize = 8192;
+/// Size of the pipe's own output buffer.
+const PIPE_BUFFER_SIZE: u32 = 64 * 1024;
+
+/// A connected end of the pipe, disconnected and closed once neither the server nor the client's
+/// writer thread uses it.
+struct PipeHandle(HANDLE);
+
+// Only used through thread safe API calls
+unsafe impl Send for PipeHandle {}
+unsafe impl Sync for PipeHandle {}
+
+impl Drop for PipeHandle {
+    fn drop(&mut self) {
+        unsafe {
+            DisconnectNamedPipe(self.0).ok();
+            CloseHandle(self.0).ok();
+        }
+    }
+}
+
+struct Client {
+    sender: SyncSender<Arc<str>>,
+    handle: Arc<PipeHandle>,
+}
+
+/// Named pipe streaming captured commands as JSON Lines to every connected client.
+///
+/// Each client gets its own writer thread, one that doesn't keep up is disconnected instead of
+/// holding up the render loop.
+pub struct PipeServer {
+    clients: Arc<Mutex<Vec<Client>>>,
+    started: Instant,
+}
+
+impl PipeServer {
+    /// Starts accepting clients on `\\.\pipe\<name>`.
+    pub fn start(name: &str) -> std::io::Result<Self> {
+        let path = HSTRING::from(format!(r"\\.\pipe\{name}"));
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let listener_clients = clients.clone();
+        std::thread::Builder::new()
+            .name("textrender pipe".to_string())
+            .spawn(move || listen(&path, &listener_clients))?;
+        Ok(Self {
+            clients,
+            started: Instant::now(),
+        })
+    }
+
+    /// Queues a command for every connected client, in the format of the JSON Lines dump with
+    /// the time since the server started. Never waits on a client.
+    pub fn send(&self, queued: &QueuedCommand) {
+        let mut clients = self.clients.lock().unwrap();
+        if clients.is_empty() {
+            return;
+        }
+        let record = DumpRecord {
+            time: self.started.elapsed().as_secs_f64(),
+            frame: queued.frame,
+            time_us: queued.time_us,
+            command: &queued.command,
+        };
+        // Commands only hold numbers and strings, this doesn't fail in practice
+        let Ok(mut line) = serde_json::to_string(&record) else {
+            return;
+        };
+        line.push('\n');
+        let line: Arc<str> = line.into();
+        clients.retain(|client| match client.sender.try_send(line.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("Disconnecting a pipe client that doesn't keep up with the stream");
+                // Also fails a write the client's thread is blocked in
+                unsafe { DisconnectNamedPipe(client.handle.0).ok() };
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+}
+
+/// Creates an instance of the pipe for every client that connects, until creating one fails.
+fn listen(path: &HSTRING, clients: &Mutex<Vec<Client>>) {
+    loop {
+        let handle = unsafe {
+            CreateNamedPipeW(
+                path,
+                PIPE_ACCESS_OUTBOUND,
+                PIPE_TYPE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                PIPE_BUFFER_SIZE,
+                0,
+                0,
+                None,
+            )
+        };
+        if handle.is_invalid() {
+            tracing::error!(
+                "Failed to create the pipe {}: {}",
+                path,
+                hudhook::windows::core::Error::from_win32()
+            );
+            return;
+        }
+        let handle = Arc::new(PipeHandle(handle));
+
+        match unsafe { ConnectNamedPipe(handle.0, None) } {
+            Ok(()) => {}
+            // The client connected between creating the pipe and waiting for it
+            Err(e) if e.code() == ERROR_PIPE_CONNECTED.to_hresult() => {}
+            Err(e) => {
+                tracing::warn!("Failed to accept a client on {}: {}", path, e);
+                continue;
+            }
+        }
+
+        let (sender, receiver) = sync_channel(CLIENT_QUEUE_CAPACITY);
+        let writer_handle = handle.clone();
+        if let Err(e) = std::thread::Builder::new()
+            .name("textrender pipe client".to_string())
+            .spawn(move || write_lines(&receiver, &writer_handle))
+        {
+            tracing::error!("Failed to start a pipe client thread: {}", e);
+            continue;
+        }
+        tracing::info!("Pipe client connected to {}", path);
+        clients.lock().unwrap().push(Client { sender, handle });
+    }
+}
+
+/// Writes lines to a client until it disconnects or the server drops it.
+fn write_lines(receiver: &Receiver<Arc<str>>, handle: &PipeHandle) {
+    for line in receiver {
+        let mut written = 0;
+        if let Err(e) =
+            unsafe { WriteFile(handle.0, Some(line.as_bytes()), Some(&mut written), None) }
+        {
+            tracing::info!("Pipe client disconnected: {}", e);
+            return;
+        }
+    }
+}
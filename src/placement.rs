@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+/// What happens to text and shapes the game positions outside the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OffscreenMode {
+    /// Wrapped around, so they come back from the opposite edge.
+    Wrap,
+    /// Pinned to the nearest edge.
+    Clamp,
+    /// Not drawn.
+    Cull,
+}
+
+impl OffscreenMode {
+    pub const ALL: [OffscreenMode; 3] = [
+        OffscreenMode::Wrap,
+        OffscreenMode::Clamp,
+        OffscreenMode::Cull,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OffscreenMode::Wrap => "wrap",
+            OffscreenMode::Clamp => "clamp",
+            OffscreenMode::Cull => "cull",
+        }
+    }
+}
+
+/// Which point of the drawn text the position the game passes in ends up at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextAnchor {
+    /// The top-left corner, offset by the padding of the window text used to be drawn in.
+    TopLeft,
+    Center,
+    /// The bottom-left corner, which lines up with the baseline CSEzDraw positions text on.
+    BottomLeft,
+}
+
+impl TextAnchor {
+    pub const ALL: [TextAnchor; 3] = [
+        TextAnchor::TopLeft,
+        TextAnchor::Center,
+        TextAnchor::BottomLeft,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TextAnchor::TopLeft => "top-left",
+            TextAnchor::Center => "center",
+            TextAnchor::BottomLeft => "bottom-left",
+        }
+    }
+
+    /// Top-left corner of text of `size` drawn at `position`.
+    pub fn origin(self, position: [f32; 2], size: [f32; 2], padding: [f32; 2]) -> [f32; 2] {
+        match self {
+            TextAnchor::TopLeft => [position[0] + padding[0], position[1] + padding[1]],
+            TextAnchor::Center => [position[0] - size[0] / 2.0, position[1] - size[1] / 2.0],
+            TextAnchor::BottomLeft => [position[0], position[1] - size[1]],
+        }
+    }
+}
+
+/// Where a point ends up on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub position: [f32; 2],
+    /// Direction of the original point from the screen on each axis, -1, 0 or 1. Only set when
+    /// the point was clamped.
+    pub direction: [f32; 2],
+}
+
+/// Places a point in screen space on a screen of `screen_size`, `None` if it's culled.
+pub fn place_on_screen(
+    position: [f32; 2],
+    screen_size: [f32; 2],
+    mode: OffscreenMode,
+) -> Option<Placement> {
+    let on_screen = |axis: usize| (0.0..=screen_size[axis]).contains(&position[axis]);
+
+    match mode {
+        OffscreenMode::Wrap => Some(Placement {
+            position: [
+                wrap(position[0], screen_size[0]),
+                wrap(position[1], screen_size[1]),
+            ],
+            direction: [0.0, 0.0],
+        }),
+        OffscreenMode::Clamp => {
+            let direction = |axis: usize| {
+                if on_screen(axis) {
+                    0.0
+                } else {
+                    position[axis].signum()
+                }
+            };
+            Some(Placement {
+                position: [
+                    position[0].clamp(0.0, screen_size[0]),
+                    position[1].clamp(0.0, screen_size[1]),
+                ],
+                direction: [direction(0), direction(1)],
+            })
+        }
+        OffscreenMode::Cull => (on_screen(0) && on_screen(1)).then_some(Placement {
+            position,
+            direction: [0.0, 0.0],
+        }),
+    }
+}
+
+/// Wraps a coordinate into `0..size`, negative ones included.
+fn wrap(value: f32, size: f32) -> f32 {
+    (value % size + size) % size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: [f32; 2] = [1920.0, 1080.0];
+
+    fn place(position: [f32; 2], mode: OffscreenMode) -> Option<Placement> {
+        place_on_screen(position, SCREEN, mode)
+    }
+
+    #[test]
+    fn wrap_moves_points_to_the_other_side() {
+        let wrapped = |position| place(position, OffscreenMode::Wrap).unwrap().position;
+
+        assert_eq!(wrapped([100.0, 200.0]), [100.0, 200.0]);
+        assert_eq!(wrapped([2000.0, 1100.0]), [80.0, 20.0]);
+        assert_eq!(wrapped([-0.5, -20.0]), [1919.5, 1060.0]);
+        assert_eq!(wrapped([1920.0, 1080.0]), [0.0, 0.0]);
+        assert_eq!(wrapped([-3840.0, 0.0]), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn clamp_keeps_the_direction_of_the_point() {
+        let cases = [
+            ([-0.5, 500.0], [0.0, 500.0], [-1.0, 0.0]),
+            ([2500.0, 500.0], [1920.0, 500.0], [1.0, 0.0]),
+            ([500.0, -0.25], [500.0, 0.0], [0.0, -1.0]),
+            ([500.0, 1200.0], [500.0, 1080.0], [0.0, 1.0]),
+            ([-10.0, 2000.0], [0.0, 1080.0], [-1.0, 1.0]),
+            ([1920.0, 0.0], [1920.0, 0.0], [0.0, 0.0]),
+        ];
+        for (position, expected, direction) in cases {
+            assert_eq!(
+                place(position, OffscreenMode::Clamp),
+                Some(Placement {
+                    position: expected,
+                    direction
+                }),
+                "{position:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cull_drops_points_past_any_edge() {
+        for position in [
+            [-0.5, 500.0],
+            [1920.5, 500.0],
+            [500.0, -0.001],
+            [500.0, 1080.5],
+        ] {
+            assert_eq!(place(position, OffscreenMode::Cull), None, "{position:?}");
+        }
+        for position in [[0.0, 0.0], [-0.0, 500.0], [1920.0, 1080.0], [960.0, 540.0]] {
+            assert_eq!(
+                place(position, OffscreenMode::Cull).map(|placement| placement.position),
+                Some(position),
+                "{position:?}"
+            );
+        }
+    }
+}
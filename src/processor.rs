@@ -0,0 +1,849 @@
+use std::collections::HashMap;
+
+use crate::aspect::aspect_ratios;
+use crate::command::DrawCommand;
+use crate::placement::{OffscreenMode, place_on_screen};
+use crate::text::{TextId, normalize_lines};
+
+/// Size of imgui's default font, the game's font size is relative to it.
+pub const BASE_IMGUI_FONT_SIZE_PX: f32 = 13.0;
+
+/// Default `font.baked_sizes`, the pixel sizes the font is rendered at.
+pub const DEFAULT_BAKED_SIZES_PX: [f32; 5] = [13.0, 18.0, 24.0, 32.0, 48.0];
+
+/// Default `text.stack_tolerance_px`, see `stack_overlapping`.
+pub const DEFAULT_STACK_TOLERANCE_PX: f32 = 1.0;
+
+/// Most a baked size is scaled up to draw bigger text, past it the next bigger size is used.
+const MAX_BAKED_UPSCALE: f32 = 1.2;
+
+/// Narrowest width text wraps at, so text starting near the right edge doesn't turn into a column.
+const MIN_WRAP_WIDTH: f32 = 64.0;
+
+/// Where a text command puts its text.
+#[derive(Debug, Clone, Copy)]
+pub enum TextOrigin {
+    /// In the game's screen coordinates, which are scaled like the rest of its UI.
+    Game([f32; 2]),
+    /// In pixels on the display, for world text projected to it.
+    Display([f32; 2]),
+}
+
+/// Window and screen size the game renders at, the text scale is corrected for their ratio.
+///
+/// Switching between windowed, fullscreen and borderless changes the window size the game
+/// reports, so comparing sizes also catches a change of window mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowMetrics {
+    pub window_size: [f32; 2],
+    pub screen_size: [f32; 2],
+}
+
+impl WindowMetrics {
+    pub fn aspect_ratios(self, bounds: Option<(f32, f32)>) -> (f32, f32) {
+        aspect_ratios(self.window_size, self.screen_size, bounds)
+    }
+}
+
+/// A text command with the text scale and font size of its frame applied.
+#[derive(Debug)]
+pub struct TextEntry {
+    pub text: String,
+    /// Position in screen space.
+    pub position: [f32; 2],
+    pub font_size: f32,
+    /// Width in screen space the text wraps at, unbounded if `None`.
+    pub wrap_width: Option<f32>,
+    pub color: [f32; 4],
+    /// Direction the game's position lies in when it was clamped to the screen, zero otherwise.
+    pub offscreen_direction: [f32; 2],
+    /// RVA of the game code that drew the text, when it was looked up.
+    pub caller: Option<u32>,
+    /// Sequence number of the command the text came from.
+    pub sequence: u64,
+    /// Drawn twice one pixel apart, set by highlight rules.
+    pub emphasis: bool,
+    /// Index of the category rule the text matched, `None` for `other`.
+    pub category: Option<usize>,
+    /// Frame and time the command was captured at.
+    pub frame: u64,
+    pub time_us: u64,
+    /// Distance in front of the camera of text drawn at a world position, `None` for text on
+    /// the screen.
+    pub depth: Option<f32>,
+    /// Opacity the text is drawn with for its distance from the camera.
+    pub distance_alpha: f32,
+    /// Key duplicates of the captured command are suppressed by, see `TextId::of_command`.
+    /// `None` for text that wasn't captured.
+    pub source: Option<TextId>,
+}
+
+impl TextEntry {
+    /// Identity of the text across frames, for fading it in and out.
+    pub fn id(&self) -> TextId {
+        TextId::new(&self.text, self.position)
+    }
+
+    /// Order text is stacked and drawn in: top to bottom, left to right, then by the code that
+    /// drew it, with later submissions drawn over earlier ones at the same spot.
+    ///
+    /// World text goes after the text on the screen, from far to near so nearer labels end up
+    /// on top.
+    pub fn draw_order(&self, other: &Self) -> std::cmp::Ordering {
+        let depth = match (self.depth, other.depth) {
+            (Some(depth), Some(other)) => other.total_cmp(&depth),
+            (depth, other) => depth.is_some().cmp(&other.is_some()),
+        };
+        depth
+            .then(self.position[1].total_cmp(&other.position[1]))
+            .then(self.position[0].total_cmp(&other.position[0]))
+            .then(self.caller.cmp(&other.caller))
+            .then(self.sequence.cmp(&other.sequence))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeKind {
+    Line,
+    Rect,
+    FilledRect,
+    /// A circle around `from` passing through `to`.
+    Circle,
+}
+
+/// A line or rectangle command with the text scale of its frame applied.
+#[derive(Debug)]
+pub struct ShapeEntry {
+    pub kind: ShapeKind,
+    /// Start of a line or corner of a rectangle, in screen space.
+    pub from: [f32; 2],
+    /// End of a line or the opposite corner of a rectangle.
+    pub to: [f32; 2],
+    pub color: [f32; 4],
+}
+
+/// Where the text scale of a `CSEzDraw` instance came from.
+#[derive(Debug, Clone, Copy)]
+enum TextScale {
+    /// The game hasn't set one.
+    Unset,
+    /// Passed to `SetTextScale`, before the aspect correction.
+    Game(f32, f32),
+    /// Set back by `ResetTextScale`.
+    Reset,
+}
+
+/// Text scale, font size and primitive color of a single `CSEzDraw` instance.
+#[derive(Debug, Clone, Copy)]
+struct EzDrawState {
+    scale: TextScale,
+    /// `scale` corrected for the window it's drawn in.
+    text_scale: (f32, f32),
+    font_size: f32,
+    /// Set by `SetColor`, primitives use the default text color until then.
+    color: Option<[f32; 4]>,
+}
+
+impl EzDrawState {
+    fn new(font_size: f32) -> Self {
+        Self {
+            scale: TextScale::Unset,
+            text_scale: (1.0, 1.0),
+            font_size,
+            color: None,
+        }
+    }
+
+    /// Recomputes `text_scale` from the scale the game set, for a window of `metrics`.
+    ///
+    /// `bounds` limits the aspect correction of scales the game set, a reset scale always uses
+    /// the exact ratio.
+    fn update_text_scale(&mut self, metrics: WindowMetrics, bounds: Option<(f32, f32)>) {
+        self.text_scale = match self.scale {
+            TextScale::Unset => (1.0, 1.0),
+            TextScale::Game(width_scale, height_scale) => {
+                let (aspect_w, aspect_h) = metrics.aspect_ratios(bounds);
+                (width_scale * aspect_w, height_scale * aspect_h)
+            }
+            TextScale::Reset => metrics.aspect_ratios(None),
+        };
+    }
+}
+
+/// The display and text settings commands are resolved against, taken from the overlay for
+/// every frame.
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenInfo {
+    /// Size the overlay draws at, the upscaled output size with an upscaler.
+    pub display_size: [f32; 2],
+    /// Ratio of the output size to the size the game positions text in.
+    pub render_scale: [f32; 2],
+    pub font_scale: f32,
+    pub default_font_size: f32,
+    pub default_color: [f32; 4],
+    pub offscreen: OffscreenMode,
+    pub tab_width: usize,
+    /// Whether text also wraps at the right edge of the screen.
+    pub wrap_to_screen: bool,
+}
+
+impl ScreenInfo {
+    /// Width the text wraps at, the narrower of its own box and the space left to the screen edge.
+    pub fn wrap_width(&self, entry: &TextEntry) -> Option<f32> {
+        if !self.wrap_to_screen {
+            return entry.wrap_width;
+        }
+
+        let available = (self.display_size[0] - entry.position[0]).max(MIN_WRAP_WIDTH);
+        Some(
+            entry
+                .wrap_width
+                .map_or(available, |width| width.min(available)),
+        )
+    }
+}
+
+/// Tracks the state commands of every `CSEzDraw` instance and places the text and primitives
+/// drawn with it on the screen.
+///
+/// It doesn't touch the game or imgui, the window size and everything else it needs is passed
+/// in.
+pub struct CommandProcessor {
+    /// Scale state of every `CSEzDraw` instance seen so far, keyed by its address.
+    states: HashMap<usize, EzDrawState>,
+    /// Window the stored text scales were corrected for.
+    window_metrics: WindowMetrics,
+    /// Aspect bounds the stored text scales were corrected with.
+    aspect_bounds: Option<(f32, f32)>,
+}
+
+impl CommandProcessor {
+    pub fn new(window_metrics: WindowMetrics) -> Self {
+        Self {
+            states: HashMap::new(),
+            window_metrics,
+            aspect_bounds: None,
+        }
+    }
+
+    pub fn window_metrics(&self) -> WindowMetrics {
+        self.window_metrics
+    }
+
+    pub fn aspect_bounds(&self) -> Option<(f32, f32)> {
+        self.aspect_bounds
+    }
+
+    /// Corrects the stored text scales for another window or other aspect bounds.
+    pub fn set_window(&mut self, metrics: WindowMetrics, bounds: Option<(f32, f32)>) {
+        self.window_metrics = metrics;
+        self.aspect_bounds = bounds;
+        for state in self.states.values_mut() {
+            state.update_text_scale(metrics, bounds);
+        }
+    }
+
+    fn state(&mut self, screen: &ScreenInfo, ez_draw: usize) -> &mut EzDrawState {
+        self.states
+            .entry(ez_draw)
+            .or_insert_with(|| EzDrawState::new(screen.default_font_size))
+    }
+
+    /// The instance's state, or the one it starts out with if it wasn't seen yet.
+    fn state_or_default(&self, screen: &ScreenInfo, ez_draw: usize) -> EzDrawState {
+        self.states
+            .get(&ez_draw)
+            .copied()
+            .unwrap_or(EzDrawState::new(screen.default_font_size))
+    }
+
+    /// Font size text of the instance is drawn at without an explicit one.
+    pub fn font_size(&self, screen: &ScreenInfo, ez_draw: usize) -> f32 {
+        self.states
+            .get(&ez_draw)
+            .map_or(screen.default_font_size, |state| state.font_size)
+    }
+
+    /// Color primitives of the instance are drawn in.
+    pub fn color(&self, screen: &ScreenInfo, ez_draw: usize) -> [f32; 4] {
+        self.states
+            .get(&ez_draw)
+            .and_then(|state| state.color)
+            .unwrap_or(screen.default_color)
+    }
+
+    /// `SetFontSize`, zero stands for the base font size.
+    pub fn set_font_size(&mut self, screen: &ScreenInfo, ez_draw: usize, font_size: f32) {
+        self.state(screen, ez_draw).font_size = if font_size == 0.0 {
+            BASE_IMGUI_FONT_SIZE_PX
+        } else {
+            font_size
+        };
+    }
+
+    /// `SetTextScale`, which also sets the font size.
+    pub fn set_text_scale(
+        &mut self,
+        screen: &ScreenInfo,
+        ez_draw: usize,
+        width_scale: f32,
+        height_scale: f32,
+        font_size: f32,
+    ) {
+        self.update_scale(screen, ez_draw, TextScale::Game(width_scale, height_scale));
+        self.set_font_size(screen, ez_draw, font_size);
+    }
+
+    pub fn reset_text_scale(&mut self, screen: &ScreenInfo, ez_draw: usize) {
+        self.update_scale(screen, ez_draw, TextScale::Reset);
+    }
+
+    fn update_scale(&mut self, screen: &ScreenInfo, ez_draw: usize, scale: TextScale) {
+        let (metrics, bounds) = (self.window_metrics, self.aspect_bounds);
+        let state = self.state(screen, ez_draw);
+        state.scale = scale;
+        state.update_text_scale(metrics, bounds);
+    }
+
+    pub fn set_color(&mut self, screen: &ScreenInfo, ez_draw: usize, color: [f32; 4]) {
+        self.state(screen, ez_draw).color = Some(color);
+    }
+
+    /// Applies the state commands and places the text and 2D primitives of `commands`, in the
+    /// order they were submitted. The sequence of an entry is the index of its command.
+    ///
+    /// Text and primitives at world positions need the game's camera, they're left to the
+    /// caller.
+    pub fn process(
+        &mut self,
+        screen: &ScreenInfo,
+        commands: &[DrawCommand],
+    ) -> (Vec<TextEntry>, Vec<ShapeEntry>) {
+        let mut texts = Vec::new();
+        let mut shapes = Vec::new();
+        for (sequence, command) in commands.iter().enumerate() {
+            match command {
+                DrawCommand::Text {
+                    ez_draw,
+                    text,
+                    caller,
+                    x,
+                    y,
+                    font_size,
+                    box_width,
+                    world_z: None,
+                } => {
+                    let entries = self.resolve_text(
+                        screen,
+                        *ez_draw,
+                        text,
+                        TextOrigin::Game([*x, *y]),
+                        *font_size,
+                        *box_width,
+                    );
+                    texts.extend(entries.into_iter().map(|entry| TextEntry {
+                        caller: *caller,
+                        sequence: sequence as u64,
+                        ..entry
+                    }));
+                }
+                DrawCommand::SetFontSize(ez_draw, font_size) => {
+                    self.set_font_size(screen, *ez_draw, *font_size);
+                }
+                DrawCommand::SetTextScale(ez_draw, width_scale, height_scale, font_size) => {
+                    self.set_text_scale(screen, *ez_draw, *width_scale, *height_scale, *font_size);
+                }
+                DrawCommand::ResetTextScale(ez_draw) => self.reset_text_scale(screen, *ez_draw),
+                DrawCommand::SetColor(ez_draw, color) => self.set_color(screen, *ez_draw, *color),
+                DrawCommand::Line { ez_draw, from, to } => {
+                    shapes.extend(self.resolve_shape(
+                        screen,
+                        *ez_draw,
+                        ShapeKind::Line,
+                        *from,
+                        *to,
+                    ));
+                }
+                DrawCommand::Rect {
+                    ez_draw,
+                    min,
+                    max,
+                    filled,
+                } => {
+                    let kind = if *filled {
+                        ShapeKind::FilledRect
+                    } else {
+                        ShapeKind::Rect
+                    };
+                    shapes.extend(self.resolve_shape(screen, *ez_draw, kind, *min, *max));
+                }
+                DrawCommand::Text { .. }
+                | DrawCommand::WorldLine { .. }
+                | DrawCommand::WorldSphere { .. }
+                | DrawCommand::WorldCapsule { .. } => {}
+            }
+        }
+        (texts, shapes)
+    }
+
+    /// Applies the text scale and font size of the drawing instance to a text command.
+    ///
+    /// Every line of the text becomes its own entry, each one a line height further down in the
+    /// game's coordinates so the step scales like the position does.
+    pub fn resolve_text(
+        &self,
+        screen: &ScreenInfo,
+        ez_draw: usize,
+        text: &str,
+        origin: TextOrigin,
+        font_size: Option<f32>,
+        box_width: Option<f32>,
+    ) -> Vec<TextEntry> {
+        let state = self.state_or_default(screen, ez_draw);
+        let font_size = font_size.unwrap_or(state.font_size);
+        let line_height = font_size * screen.font_scale * state.text_scale.1;
+
+        // Lines follow the first one even if it was moved to another part of the screen
+        let position = match origin {
+            TextOrigin::Game(position) => [
+                position[0] * screen.render_scale[0] * state.text_scale.0,
+                position[1] * screen.render_scale[1] * state.text_scale.1,
+            ],
+            TextOrigin::Display(position) => position,
+        };
+        let Some(first_line) = place_on_screen(position, screen.display_size, screen.offscreen)
+        else {
+            return Vec::new();
+        };
+        normalize_lines(text, screen.tab_width)
+            .into_iter()
+            .enumerate()
+            .map(|(index, line)| TextEntry {
+                text: line,
+                position: [
+                    first_line.position[0],
+                    first_line.position[1] + index as f32 * line_height,
+                ],
+                font_size,
+                wrap_width: box_width.map(|width| width * state.text_scale.0),
+                color: screen.default_color,
+                offscreen_direction: if index == 0 {
+                    first_line.direction
+                } else {
+                    [0.0, 0.0]
+                },
+                // Filled in from the command by the caller
+                caller: None,
+                sequence: 0,
+                emphasis: false,
+                category: None,
+                frame: 0,
+                time_us: 0,
+                depth: None,
+                distance_alpha: 1.0,
+                source: None,
+            })
+            .collect()
+    }
+
+    /// Applies the text scale of the drawing instance to a primitive.
+    ///
+    /// The first point is placed like text and the second one keeps its offset from it, so
+    /// shapes stay whole and line up with the labels drawn next to them.
+    pub fn resolve_shape(
+        &self,
+        screen: &ScreenInfo,
+        ez_draw: usize,
+        kind: ShapeKind,
+        from: [f32; 2],
+        to: [f32; 2],
+    ) -> Option<ShapeEntry> {
+        let state = self.state_or_default(screen, ez_draw);
+        let scale = |point: [f32; 2]| {
+            [
+                point[0] * screen.render_scale[0] * state.text_scale.0,
+                point[1] * screen.render_scale[1] * state.text_scale.1,
+            ]
+        };
+        let (scaled_from, scaled_to) = (scale(from), scale(to));
+        let placed_from =
+            place_on_screen(scaled_from, screen.display_size, screen.offscreen)?.position;
+        Some(ShapeEntry {
+            kind,
+            from: placed_from,
+            to: [
+                placed_from[0] + scaled_to[0] - scaled_from[0],
+                placed_from[1] + scaled_to[1] - scaled_from[1],
+            ],
+            color: state.color.unwrap_or(screen.default_color),
+        })
+    }
+}
+
+/// Index of the font text of `font_size` is drawn with, `fonts` sorted ascending by baked size.
+///
+/// The nearest baked size, the bigger one of two equally near. Upscaled glyphs blur, so a
+/// smaller size is only scaled up by `MAX_BAKED_UPSCALE`, bigger text uses the next size up
+/// while there is one.
+pub fn baked_font_index<T>(fonts: &[(f32, T)], font_size: f32) -> usize {
+    let Some(bigger) = fonts.iter().position(|(size, _)| *size >= font_size) else {
+        return fonts.len().saturating_sub(1);
+    };
+    let Some(smaller) = bigger.checked_sub(1) else {
+        return bigger;
+    };
+
+    let smaller_size = fonts[smaller].0;
+    let nearer = font_size - smaller_size < fonts[bigger].0 - font_size;
+    if nearer && font_size <= smaller_size * MAX_BAKED_UPSCALE {
+        smaller
+    } else {
+        bigger
+    }
+}
+
+/// Moves text drawn at the same position below the text drawn there before it.
+///
+/// Positions within `tolerance` pixels of each other share a group, `line_height` returns the
+/// height each entry takes up on screen. Positions are in screen space with the text scale already
+/// applied, so the offsets match the rendered text at every resolution.
+pub fn stack_overlapping(
+    texts: &mut [TextEntry],
+    tolerance: f32,
+    line_height: impl Fn(&TextEntry) -> f32,
+) {
+    if tolerance <= 0.0 {
+        return;
+    }
+
+    // Bottom of the text stacked so far in each group
+    let mut bottoms: HashMap<(i32, i32), f32> = HashMap::new();
+    for entry in texts {
+        let group = (
+            (entry.position[0] / tolerance).round() as i32,
+            (entry.position[1] / tolerance).round() as i32,
+        );
+        let height = line_height(entry);
+        let bottom = bottoms.entry(group).or_insert(entry.position[1]);
+        entry.position[1] = *bottom;
+        *bottom += height;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EZ_DRAW: usize = 0x1000;
+
+    fn screen(offscreen: OffscreenMode) -> ScreenInfo {
+        ScreenInfo {
+            display_size: [1920.0, 1080.0],
+            render_scale: [1.0, 1.0],
+            font_scale: 1.0,
+            default_font_size: 24.0,
+            default_color: [1.0, 1.0, 1.0, 1.0],
+            offscreen,
+            tab_width: 4,
+            wrap_to_screen: true,
+        }
+    }
+
+    /// A processor for a 1920x1080 window on a screen of `screen_size`.
+    fn processor(screen_size: [f32; 2], bounds: Option<(f32, f32)>) -> CommandProcessor {
+        let metrics = WindowMetrics {
+            window_size: [1920.0, 1080.0],
+            screen_size,
+        };
+        let mut processor = CommandProcessor::new(metrics);
+        processor.set_window(metrics, bounds);
+        processor
+    }
+
+    fn text(text: &str, x: f32, y: f32) -> DrawCommand {
+        DrawCommand::Text {
+            ez_draw: EZ_DRAW,
+            text: text.into(),
+            caller: Some(0x1234),
+            x,
+            y,
+            font_size: None,
+            box_width: None,
+            world_z: None,
+        }
+    }
+
+    fn positions(texts: &[TextEntry]) -> Vec<[f32; 2]> {
+        texts.iter().map(|entry| entry.position).collect()
+    }
+
+    #[test]
+    fn text_scale_is_corrected_for_the_screen() {
+        let screen = screen(OffscreenMode::Wrap);
+        let mut processor = processor([3840.0, 2160.0], None);
+        let (texts, _) = processor.process(
+            &screen,
+            &[
+                DrawCommand::SetTextScale(EZ_DRAW, 0.5, 0.25, 20.0),
+                text("first\nsecond", 100.0, 100.0),
+            ],
+        );
+
+        assert_eq!(positions(&texts), [[100.0, 50.0], [100.0, 60.0]]);
+        assert!(texts.iter().all(|entry| entry.font_size == 20.0));
+    }
+
+    #[test]
+    fn game_scales_are_clamped_and_reset_scales_are_not() {
+        let screen = screen(OffscreenMode::Wrap);
+        let mut processor = processor([3840.0, 2160.0], Some((0.8, 1.5)));
+        let (texts, _) = processor.process(
+            &screen,
+            &[
+                DrawCommand::SetTextScale(EZ_DRAW, 1.0, 1.0, 24.0),
+                text("game", 100.0, 100.0),
+                DrawCommand::ResetTextScale(EZ_DRAW),
+                text("reset", 100.0, 100.0),
+            ],
+        );
+
+        assert_eq!(positions(&texts), [[150.0, 150.0], [200.0, 200.0]]);
+    }
+
+    #[test]
+    fn stored_scales_follow_the_window() {
+        let screen = screen(OffscreenMode::Wrap);
+        let mut processor = processor([1920.0, 1080.0], None);
+        processor.process(
+            &screen,
+            &[DrawCommand::SetTextScale(EZ_DRAW, 1.0, 1.0, 24.0)],
+        );
+        processor.set_window(
+            WindowMetrics {
+                window_size: [1920.0, 1080.0],
+                screen_size: [2560.0, 1080.0],
+            },
+            None,
+        );
+        let (texts, _) = processor.process(&screen, &[text("moved", 300.0, 100.0)]);
+
+        assert_eq!(positions(&texts), [[400.0, 100.0]]);
+    }
+
+    #[test]
+    fn zero_font_size_is_the_base_size() {
+        let screen = screen(OffscreenMode::Wrap);
+        let mut processor = processor([1920.0, 1080.0], None);
+        processor.process(&screen, &[DrawCommand::SetFontSize(EZ_DRAW, 0.0)]);
+
+        assert_eq!(
+            processor.font_size(&screen, EZ_DRAW),
+            BASE_IMGUI_FONT_SIZE_PX
+        );
+        assert_eq!(processor.font_size(&screen, EZ_DRAW + 1), 24.0);
+    }
+
+    #[test]
+    fn offscreen_text_is_wrapped_clamped_or_culled() {
+        let commands = [text("right", 2000.0, 100.0), text("above", 100.0, -20.0)];
+        let resolve = |mode| processor([1920.0, 1080.0], None).process(&screen(mode), &commands);
+
+        let (wrapped, _) = resolve(OffscreenMode::Wrap);
+        assert_eq!(positions(&wrapped), [[80.0, 100.0], [100.0, 1060.0]]);
+
+        let (clamped, _) = resolve(OffscreenMode::Clamp);
+        assert_eq!(positions(&clamped), [[1920.0, 100.0], [100.0, 0.0]]);
+        let directions = clamped
+            .iter()
+            .map(|entry| entry.offscreen_direction)
+            .collect::<Vec<_>>();
+        assert_eq!(directions, [[1.0, 0.0], [0.0, -1.0]]);
+
+        let (culled, _) = resolve(OffscreenMode::Cull);
+        assert!(culled.is_empty());
+    }
+
+    #[test]
+    fn process_numbers_entries_and_skips_world_commands() {
+        let screen = screen(OffscreenMode::Wrap);
+        let mut processor = processor([1920.0, 1080.0], None);
+        let (texts, shapes) = processor.process(
+            &screen,
+            &[
+                DrawCommand::SetColor(EZ_DRAW, [1.0, 0.0, 0.0, 1.0]),
+                DrawCommand::Text {
+                    ez_draw: EZ_DRAW,
+                    text: "world".into(),
+                    caller: None,
+                    x: 1.0,
+                    y: 2.0,
+                    font_size: None,
+                    box_width: None,
+                    world_z: Some(5.0),
+                },
+                text("screen", 10.0, 20.0),
+                DrawCommand::Line {
+                    ez_draw: EZ_DRAW,
+                    from: [10.0, 10.0],
+                    to: [20.0, 30.0],
+                },
+                DrawCommand::WorldLine {
+                    ez_draw: EZ_DRAW,
+                    from: [0.0; 3],
+                    to: [1.0; 3],
+                },
+            ],
+        );
+
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].text, "screen");
+        assert_eq!(texts[0].sequence, 2);
+        assert_eq!(texts[0].caller, Some(0x1234));
+        // Text keeps the default color, SetColor only applies to primitives
+        assert_eq!(texts[0].color, [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].kind, ShapeKind::Line);
+        assert_eq!(shapes[0].color, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn overlapping_text_is_stacked() {
+        let screen = screen(OffscreenMode::Wrap);
+        let mut processor = processor([1920.0, 1080.0], None);
+        let (mut texts, _) = processor.process(
+            &screen,
+            &[
+                text("first", 100.0, 100.0),
+                text("second", 100.25, 100.0),
+                text("elsewhere", 300.0, 100.0),
+                text("third", 100.0, 100.0),
+            ],
+        );
+        stack_overlapping(&mut texts, 1.0, |_| 20.0);
+
+        assert_eq!(
+            positions(&texts),
+            [
+                [100.0, 100.0],
+                [100.25, 120.0],
+                [300.0, 100.0],
+                [100.0, 140.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_tolerance_draws_text_on_top() {
+        let screen = screen(OffscreenMode::Wrap);
+        let mut processor = processor([1920.0, 1080.0], None);
+        let (mut texts, _) = processor.process(
+            &screen,
+            &[text("first", 100.0, 100.0), text("second", 100.0, 100.0)],
+        );
+        stack_overlapping(&mut texts, 0.0, |_| 20.0);
+
+        assert_eq!(positions(&texts), [[100.0, 100.0], [100.0, 100.0]]);
+    }
+
+    /// Text at `x` with a box of `box_width`, on a screen as big as the window.
+    fn boxed_text(x: f32, box_width: Option<f32>) -> TextEntry {
+        let mut processor = processor([1920.0, 1080.0], None);
+        let command = DrawCommand::Text {
+            ez_draw: EZ_DRAW,
+            text: "boxed".into(),
+            caller: None,
+            x,
+            y: 100.0,
+            font_size: None,
+            box_width,
+            world_z: None,
+        };
+        let (mut texts, _) = processor.process(&screen(OffscreenMode::Wrap), &[command]);
+        texts.remove(0)
+    }
+
+    #[test]
+    fn wrap_width_is_the_narrower_of_box_and_screen() {
+        let screen = screen(OffscreenMode::Wrap);
+
+        // Box narrower than the space left
+        assert_eq!(
+            screen.wrap_width(&boxed_text(1000.0, Some(300.0))),
+            Some(300.0)
+        );
+        // Box wider than the space left
+        assert_eq!(
+            screen.wrap_width(&boxed_text(1000.0, Some(1500.0))),
+            Some(920.0)
+        );
+        // No box at all
+        assert_eq!(screen.wrap_width(&boxed_text(1000.0, None)), Some(920.0));
+    }
+
+    #[test]
+    fn wrap_width_has_a_floor_at_the_screen_edge() {
+        let screen = screen(OffscreenMode::Wrap);
+
+        assert_eq!(
+            screen.wrap_width(&boxed_text(1900.0, None)),
+            Some(MIN_WRAP_WIDTH)
+        );
+        assert_eq!(
+            screen.wrap_width(&boxed_text(1900.0, Some(300.0))),
+            Some(MIN_WRAP_WIDTH)
+        );
+        assert_eq!(
+            screen.wrap_width(&boxed_text(1900.0, Some(40.0))),
+            Some(40.0)
+        );
+    }
+
+    #[test]
+    fn wrap_width_ignores_the_screen_when_disabled() {
+        let screen = ScreenInfo {
+            wrap_to_screen: false,
+            ..screen(OffscreenMode::Wrap)
+        };
+
+        assert_eq!(
+            screen.wrap_width(&boxed_text(1900.0, Some(300.0))),
+            Some(300.0)
+        );
+        assert_eq!(screen.wrap_width(&boxed_text(1900.0, None)), None);
+    }
+
+    #[test]
+    fn nearest_baked_size() {
+        let fonts = DEFAULT_BAKED_SIZES_PX.map(|size| (size, ()));
+
+        // Exact sizes
+        assert_eq!(baked_font_index(&fonts, 13.0), 0);
+        assert_eq!(baked_font_index(&fonts, 24.0), 2);
+        assert_eq!(baked_font_index(&fonts, 48.0), 4);
+        // Between two sizes, the nearer one
+        assert_eq!(baked_font_index(&fonts, 24.01), 2);
+        assert_eq!(baked_font_index(&fonts, 27.0), 2);
+        assert_eq!(baked_font_index(&fonts, 30.0), 3);
+        assert_eq!(baked_font_index(&fonts, 33.0), 3);
+        // Midpoints, the bigger one
+        assert_eq!(baked_font_index(&fonts, 15.5), 1);
+        assert_eq!(baked_font_index(&fonts, 40.0), 4);
+        // Below the smallest and above the largest
+        assert_eq!(baked_font_index(&fonts, 6.0), 0);
+        assert_eq!(baked_font_index(&fonts, 96.0), 4);
+    }
+
+    #[test]
+    fn baked_sizes_are_only_scaled_up_a_little() {
+        let fonts = [(13.0, ()), (48.0, ())];
+
+        assert_eq!(baked_font_index(&fonts, 15.0), 0);
+        // Nearer to 13 but more than 20% bigger
+        assert_eq!(baked_font_index(&fonts, 20.0), 1);
+    }
+
+    #[test]
+    fn no_baked_sizes() {
+        assert_eq!(baked_font_index::<()>(&[], 24.0), 0);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+
+use hudhook::imgui::Ui;
+
+use crate::command::DrawCommand;
+use crate::dump::DumpRecord;
+use crate::{DebugTextRender, push_command};
+
+/// How often the replay thread checks whether the next command is due.
+const REPLAY_TICK: Duration = Duration::from_millis(1);
+
+/// Whether a recording is being replayed instead of capturing from the game.
+static REPLAYING: AtomicBool = AtomicBool::new(false);
+static PAUSED: AtomicBool = AtomicBool::new(false);
+/// Recorded frames to play while paused.
+static PENDING_STEPS: AtomicU32 = AtomicU32::new(0);
+
+pub fn is_replaying() -> bool {
+    REPLAYING.load(Ordering::Relaxed)
+}
+
+pub fn toggle_pause() {
+    PAUSED.fetch_xor(true, Ordering::Relaxed);
+}
+
+/// Plays the next recorded frame, only while paused.
+pub fn step() {
+    if PAUSED.load(Ordering::Relaxed) {
+        PENDING_STEPS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Reads a JSON Lines dump, lines that don't parse are skipped and counted in the log.
+fn read_recording(path: &Path) -> io::Result<Vec<DumpRecord<DrawCommand>>> {
+    let mut records = Vec::new();
+    let mut skipped = 0;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(_) => skipped += 1,
+        }
+    }
+    if skipped > 0 {
+        tracing::warn!(
+            "Skipped {} lines of {} that aren't commands",
+            skipped,
+            path.display()
+        );
+    }
+    Ok(records)
+}
+
+/// Feeds the commands recorded in a JSON Lines dump into the queue from a background thread,
+/// with their recorded timing scaled by `speed`. Starts over at the end with `repeat`.
+pub fn start_replay(path: &Path, speed: f32, repeat: bool) -> io::Result<()> {
+    let records = read_recording(path)?;
+    if records.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the file has no recorded commands",
+        ));
+    }
+    tracing::info!(
+        "Replaying {} commands from {} at {}x speed",
+        records.len(),
+        path.display(),
+        speed
+    );
+    REPLAYING.store(true, Ordering::Relaxed);
+    std::thread::Builder::new()
+        .name("textrender replay".to_string())
+        .spawn(move || play(&records, speed as f64, repeat))?;
+    Ok(())
+}
+
+fn play(records: &[DumpRecord<DrawCommand>], speed: f64, repeat: bool) {
+    let mut playhead = records[0].time;
+    let mut last_tick = Instant::now();
+    let mut next = 0;
+    loop {
+        let now = Instant::now();
+        if !PAUSED.load(Ordering::Relaxed) {
+            playhead += now.duration_since(last_tick).as_secs_f64() * speed;
+        } else if PENDING_STEPS.load(Ordering::Relaxed) > 0 {
+            PENDING_STEPS.fetch_sub(1, Ordering::Relaxed);
+            // Moving the playhead to the last command of the next frame plays it below
+            let frame = records[next].frame;
+            playhead = records[next..]
+                .iter()
+                .take_while(|record| record.frame == frame)
+                .last()
+                .map_or(playhead, |record| record.time);
+        }
+        last_tick = now;
+
+        while let Some(record) = records.get(next)
+            && record.time <= playhead
+        {
+            push_command(record.command.clone());
+            next += 1;
+        }
+
+        if next == records.len() {
+            if !repeat {
+                tracing::info!("Replay finished");
+                REPLAYING.store(false, Ordering::Relaxed);
+                return;
+            }
+            next = 0;
+            playhead = records[0].time;
+        }
+        std::thread::sleep(REPLAY_TICK);
+    }
+}
+
+impl DebugTextRender {
+    /// Draws a replay indicator in the top left corner while a recording is replayed.
+    pub(crate) fn draw_replay_indicator(&self, ui: &Ui) {
+        if !is_replaying() {
+            return;
+        }
+        let label = if PAUSED.load(Ordering::Relaxed) {
+            "REPLAY (paused)"
+        } else {
+            "REPLAY"
+        };
+        // Below the recording indicator
+        let y = 10.0 + ui.current_font_size() * 1.5;
+        ui.get_foreground_draw_list().add_text(
+            [10.0, y],
+            self.color_space().rgb(80, 160, 255),
+            label,
+        );
+    }
+}
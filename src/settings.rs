@@ -0,0 +1,331 @@
+use std::time::Duration;
+
+use hudhook::imgui::{TreeNodeFlags, Ui};
+use tracing_subscriber::filter::{Directive, LevelFilter};
+
+use crate::address::HookId;
+use crate::aspect::AspectMode;
+use crate::caller::set_capture_callers;
+use crate::console::{is_console_open, toggle_console};
+use crate::dedup;
+use crate::filter::set_filter;
+use crate::flags::FlagMode;
+use crate::focus::UnfocusedMode;
+use crate::hdr::{HdrMode, HdrOutput};
+use crate::hooks::apply_hook_settings;
+use crate::layer::Layer;
+use crate::logging::set_log_filter;
+use crate::panel::Presentation;
+use crate::placement::OffscreenMode;
+use crate::placement::TextAnchor;
+use crate::watch::exact_pattern;
+use crate::{DebugTextRender, TextOutline};
+
+/// The levels `log_level` can be set to, least verbose first.
+const LOG_LEVELS: [LevelFilter; 6] = [
+    LevelFilter::OFF,
+    LevelFilter::ERROR,
+    LevelFilter::WARN,
+    LevelFilter::INFO,
+    LevelFilter::DEBUG,
+    LevelFilter::TRACE,
+];
+
+/// Draws a combo box to pick one of `all` by its name, returns whether `value` changed.
+fn enum_combo<T: Copy + PartialEq>(
+    ui: &Ui,
+    label: &str,
+    value: &mut T,
+    all: &[T],
+    name: fn(T) -> &'static str,
+) -> bool {
+    let mut index = all
+        .iter()
+        .position(|option| option == value)
+        .unwrap_or_default();
+    let names: Vec<_> = all.iter().copied().map(name).collect();
+    if !ui.combo_simple_string(label, &mut index, &names) {
+        return false;
+    }
+    *value = all[index];
+    true
+}
+
+impl DebugTextRender {
+    /// Draws the settings window, changes apply from the next frame on.
+    pub(crate) fn draw_settings(&mut self, ui: &Ui) {
+        let mut open = self.settings_open;
+        ui.window("Debug Text Settings")
+            .opened(&mut open)
+            .always_auto_resize(true)
+            .build(|| {
+                let names: Vec<_> = self
+                    .font_data
+                    .iter()
+                    .map(|(path, _)| path.file_name().unwrap_or(path.as_os_str()).display())
+                    .map(|name| name.to_string())
+                    .collect();
+                ui.text(format!("Font: {}", names.join(" + ")));
+                ui.same_line();
+                // For a device reset the overlay didn't notice, which leaves every glyph a box
+                if ui.small_button("Rebuild fonts") {
+                    self.atlas_reload_pending = true;
+                }
+                ui.slider("Default font size", 6.0, 96.0, &mut self.default_font_size);
+                ui.slider("Font scale", 0.25, 4.0, &mut self.font_scale);
+                ui.color_edit4("Text color", &mut self.default_color);
+
+                enum_combo(
+                    ui,
+                    "Outline",
+                    &mut self.outline,
+                    &TextOutline::ALL,
+                    TextOutline::name,
+                );
+                ui.color_edit4("Outline color", &mut self.outline_color);
+                ui.checkbox("Fade in and out", &mut self.fade);
+                ui.checkbox("Highlight changes", &mut self.diff);
+                enum_combo(
+                    ui,
+                    "Game flags",
+                    &mut self.game_flag_mode,
+                    &FlagMode::ALL,
+                    FlagMode::name,
+                );
+                ui.checkbox("Background", &mut self.background);
+                if self.background {
+                    ui.color_edit4("Background color", &mut self.background_color);
+                    ui.slider(
+                        "Background rounding",
+                        0.0,
+                        16.0,
+                        &mut self.background_rounding,
+                    );
+                }
+
+                enum_combo(
+                    ui,
+                    "Anchor",
+                    &mut self.anchor,
+                    &TextAnchor::ALL,
+                    TextAnchor::name,
+                );
+
+                enum_combo(
+                    ui,
+                    "Off-screen text",
+                    &mut self.offscreen,
+                    &OffscreenMode::ALL,
+                    OffscreenMode::name,
+                );
+                if self.offscreen == OffscreenMode::Clamp {
+                    ui.checkbox("Point to clamped text", &mut self.offscreen_indicator);
+                }
+                enum_combo(
+                    ui,
+                    "When unfocused",
+                    &mut self.unfocused,
+                    &UnfocusedMode::ALL,
+                    UnfocusedMode::name,
+                );
+                if self.unfocused == UnfocusedMode::Dim {
+                    ui.slider("Unfocused opacity", 0.0, 1.0, &mut self.unfocused_alpha);
+                }
+                enum_combo(ui, "HDR", &mut self.hdr_mode, &HdrMode::ALL, HdrMode::name);
+                if self.hdr_mode.output(self.detected_output) != HdrOutput::Sdr {
+                    ui.slider("HDR white (nits)", 80.0, 1000.0, &mut self.hdr_white_nits);
+                }
+
+                enum_combo(
+                    ui,
+                    "Aspect correction",
+                    &mut self.aspect_mode,
+                    &AspectMode::ALL,
+                    AspectMode::name,
+                );
+                if self.aspect_mode == AspectMode::Clamp {
+                    ui.slider("Minimum aspect", 0.1, 2.0, &mut self.aspect_min);
+                    // No maximum is `inf`, which a slider can't reach
+                    let mut limit_max = self.aspect_max.is_finite();
+                    if ui.checkbox("Limit maximum aspect", &mut limit_max) {
+                        self.aspect_max = if limit_max {
+                            self.aspect_min.max(2.0)
+                        } else {
+                            f32::INFINITY
+                        };
+                    }
+                    if limit_max {
+                        ui.slider("Maximum aspect", self.aspect_min, 4.0, &mut self.aspect_max);
+                    }
+                }
+                ui.slider("Stack tolerance (px)", 0.0, 32.0, &mut self.stack_tolerance);
+                ui.checkbox("Wrap at screen edge", &mut self.wrap_to_screen);
+                if ui.checkbox("Show callers", &mut self.show_callers) {
+                    set_capture_callers(self.needs_callers());
+                }
+                ui.checkbox("Show frame and time", &mut self.show_stamps);
+                if enum_combo(
+                    ui,
+                    "Show text",
+                    &mut self.presentation,
+                    &Presentation::ALL,
+                    Presentation::name,
+                ) {
+                    set_capture_callers(self.needs_callers());
+                }
+
+                let mut retain_timeout_ms = self.retain_timeout.as_millis() as u32;
+                if ui.slider("Retain timeout (ms)", 0, 5000, &mut retain_timeout_ms) {
+                    self.retain_timeout = Duration::from_millis(retain_timeout_ms as u64);
+                }
+
+                if ui.collapsing_header("Queue", TreeNodeFlags::empty()) {
+                    ui.slider(
+                        "Text per frame (0: all)",
+                        0,
+                        5000,
+                        &mut self.max_texts_per_frame,
+                    );
+                    let mut max_age_ms = self.max_command_age.as_millis() as u32;
+                    if ui.slider("Stale text age (ms, 0: keep)", 0, 5000, &mut max_age_ms) {
+                        self.max_command_age = Duration::from_millis(max_age_ms as u64);
+                    }
+                    let mut duplicate_ms = self.duplicate_window.as_millis() as u32;
+                    if ui.slider("Duplicate window (ms)", 0, 1000, &mut duplicate_ms) {
+                        self.duplicate_window = Duration::from_millis(duplicate_ms as u64);
+                        dedup::set_window(self.duplicate_window);
+                    }
+                    // The queue is allocated once at startup
+                    ui.text_disabled(format!(
+                        "Capacity: {} commands, changed in the config file, needs a restart",
+                        self.config.queue_capacity
+                    ));
+                }
+
+                ui.separator();
+                ui.text("Filters, one regular expression per line");
+                let include_changed = ui
+                    .input_text_multiline("Include", &mut self.include_patterns, [0.0, 60.0])
+                    .build();
+                let exclude_changed = ui
+                    .input_text_multiline("Exclude", &mut self.exclude_patterns, [0.0, 60.0])
+                    .build();
+                if include_changed || exclude_changed {
+                    set_filter(&self.current_config().filter);
+                }
+                self.draw_blocklist_settings(ui);
+
+                ui.separator();
+                ui.text("Watches, the latest match of each is pinned to the top right");
+                let mut removed = None;
+                for (index, pattern) in self.watches.patterns().iter().enumerate() {
+                    let _id = ui.push_id_usize(index);
+                    if ui.small_button("x") {
+                        removed = Some(index);
+                    }
+                    ui.same_line();
+                    ui.text(pattern);
+                }
+                if let Some(index) = removed {
+                    self.watches.remove(index);
+                }
+                ui.input_text("##watch", &mut self.watch_input).build();
+                ui.same_line();
+                if ui.button("Add") && !self.watch_input.is_empty() {
+                    self.watches.add(std::mem::take(&mut self.watch_input));
+                }
+                ui.same_line();
+                if ui.button("Add exact") && !self.watch_input.is_empty() {
+                    self.watches
+                        .add(exact_pattern(&std::mem::take(&mut self.watch_input)));
+                }
+
+                ui.separator();
+                self.draw_highlight_settings(ui);
+                self.draw_category_settings(ui);
+                if ui.collapsing_header("World shapes", TreeNodeFlags::empty()) {
+                    let shapes = &mut self.world_shapes;
+                    ui.checkbox("Draw world shapes", &mut shapes.enabled);
+                    ui.checkbox("Lines", &mut shapes.lines);
+                    ui.checkbox("Spheres", &mut shapes.spheres);
+                    ui.checkbox("Capsules", &mut shapes.capsules);
+                    ui.slider("Circle segments", 4, 64, &mut shapes.circle_segments);
+                }
+                if ui.collapsing_header("Auto-hide", TreeNodeFlags::empty()) {
+                    let auto_hide = &mut self.auto_hide;
+                    ui.checkbox("In cutscenes", &mut auto_hide.cutscenes);
+                    ui.checkbox("In the main menu", &mut auto_hide.main_menu);
+                    ui.checkbox("With the map open", &mut auto_hide.map);
+                    ui.checkbox("Dump while hidden", &mut auto_hide.dump_while_hidden);
+                }
+                if ui.collapsing_header("Layers", TreeNodeFlags::empty()) {
+                    let layers = [
+                        ("Text", &mut self.layers.text),
+                        ("Panels", &mut self.layers.panels),
+                        ("Shapes", &mut self.layers.shapes),
+                    ];
+                    for (label, layer) in layers {
+                        enum_combo(ui, label, layer, &Layer::ALL, Layer::name);
+                    }
+                }
+                if ui.collapsing_header("Logging", TreeNodeFlags::empty()) {
+                    let mut level = LOG_LEVELS
+                        .iter()
+                        .position(|level| *level == self.log_level)
+                        .unwrap_or_default();
+                    let level_names = LOG_LEVELS.map(|level| level.to_string().to_lowercase());
+                    let mut changed = ui.combo_simple_string("Level", &mut level, &level_names);
+                    if changed {
+                        self.log_level = LOG_LEVELS[level];
+                    }
+                    changed |= ui
+                        .input_text_multiline("Directives", &mut self.log_directives, [0.0, 60.0])
+                        .build();
+                    for directive in self.log_directives.lines() {
+                        if !directive.is_empty() && directive.parse::<Directive>().is_err() {
+                            ui.text_disabled(format!("Invalid, not applied: {directive}"));
+                        }
+                    }
+                    if changed {
+                        let config = self.current_config();
+                        set_log_filter(config.log_level(), &config.log_directives);
+                    }
+                    ui.checkbox("Log captured text (trace)", &mut self.log_captured_text);
+                }
+                if ui.collapsing_header("Hooks", TreeNodeFlags::empty()) {
+                    let mut changed = ui.checkbox(
+                        "Let the game draw for disabled hooks",
+                        &mut self.hook_settings.native_when_disabled,
+                    );
+                    for hook in HookId::ALL {
+                        changed |= ui.checkbox(hook.name(), self.hook_settings.enabled_mut(hook));
+                    }
+                    if changed {
+                        apply_hook_settings(&self.hook_settings);
+                    }
+                }
+
+                ui.separator();
+                let console_label = if is_console_open() {
+                    "Close console"
+                } else {
+                    "Open console"
+                };
+                if ui.button(console_label) {
+                    toggle_console();
+                }
+
+                if let Some(path) = &self.config_path {
+                    if ui.button("Save") {
+                        match self.current_config().save(path) {
+                            Ok(()) => tracing::info!("Saved settings to {}", path.display()),
+                            Err(e) => tracing::error!("{}: {}", path.display(), e),
+                        }
+                    }
+                    ui.same_line();
+                    ui.text_disabled(path.display().to_string());
+                }
+            });
+        self.settings_open = open;
+    }
+}
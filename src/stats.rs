@@ -0,0 +1,214 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use hudhook::imgui::{
+    Condition, TableColumnFlags, TableColumnSetup, TableFlags, TableSortDirection, Ui,
+};
+
+use crate::DebugTextRender;
+use crate::caller::{in_ranges, set_capture_callers};
+use crate::filter::set_filter;
+use crate::panel::source_name;
+
+/// Time constant of the decay, counts from this long ago weigh about a third as much as new
+/// ones.
+const WINDOW: Duration = Duration::from_secs(5);
+/// Sources whose decayed count falls below this are dropped.
+const MIN_STRINGS: f64 = 0.01;
+
+/// Decayed counts of the text from one caller.
+#[derive(Default, Clone, Copy)]
+struct SourceRate {
+    strings: f64,
+    bytes: f64,
+}
+
+/// How much text each caller drew recently, counted as it's drained so no history is kept.
+///
+/// The counts decay exponentially over [`WINDOW`], which makes them the rate per second over
+/// roughly the last `WINDOW` when divided by its length.
+pub struct SourceStats {
+    sources: HashMap<Option<u32>, SourceRate>,
+    decayed_at: Instant,
+    /// Column the table is sorted by and whether it's descending.
+    sort: (usize, bool),
+}
+
+impl SourceStats {
+    pub fn new() -> Self {
+        Self {
+            sources: HashMap::new(),
+            decayed_at: Instant::now(),
+            // Strings per second, most first
+            sort: (1, true),
+        }
+    }
+
+    pub fn observe(&mut self, caller: Option<u32>, text: &str) {
+        let rate = self.sources.entry(caller).or_default();
+        rate.strings += 1.0;
+        rate.bytes += text.len() as f64;
+    }
+
+    /// Decays the counts for the time since the last call.
+    pub fn decay(&mut self) {
+        let factor = (-self.decayed_at.elapsed().as_secs_f64() / WINDOW.as_secs_f64()).exp();
+        self.decayed_at = Instant::now();
+        self.sources.retain(|_, rate| {
+            rate.strings *= factor;
+            rate.bytes *= factor;
+            rate.strings >= MIN_STRINGS
+        });
+    }
+}
+
+/// A row of the table, the callers under the same label are added up.
+struct Row {
+    name: String,
+    /// RVA range excluding the row's text, `None` for text without a known caller.
+    range: Option<[u32; 2]>,
+    strings: f64,
+    bytes: f64,
+}
+
+impl DebugTextRender {
+    /// Draws the table of how much text each source drew in the last few seconds.
+    pub(crate) fn draw_stats(&mut self, ui: &Ui) {
+        let labels = &self.config.caller_labels;
+        let mut rows: BTreeMap<String, Row> = BTreeMap::new();
+        for (&caller, rate) in &self.stats.sources {
+            let name = source_name(caller, labels);
+            let range = caller.map(|rva| {
+                labels
+                    .iter()
+                    .find(|label| in_ranges(rva, &[label.range]))
+                    .map_or([rva, rva], |label| label.range)
+            });
+            let row = rows.entry(name.clone()).or_insert(Row {
+                name,
+                range,
+                strings: 0.0,
+                bytes: 0.0,
+            });
+            row.strings += rate.strings;
+            row.bytes += rate.bytes;
+        }
+        let mut rows = rows.into_values().collect::<Vec<_>>();
+        let total = rows
+            .iter()
+            .map(|row| row.strings)
+            .sum::<f64>()
+            .max(MIN_STRINGS);
+        let window = WINDOW.as_secs_f64();
+
+        let mut excluded = None;
+        let mut open = self.stats_open;
+        ui.window("Debug Text Statistics")
+            .opened(&mut open)
+            .position([440.0, 10.0], Condition::FirstUseEver)
+            .size([620.0, 300.0], Condition::FirstUseEver)
+            .build(|| {
+                ui.text_disabled(format!(
+                    "Over the last {} seconds, {:.1} strings per second in total",
+                    WINDOW.as_secs(),
+                    total / window
+                ));
+                let column = |name, flags| TableColumnSetup {
+                    flags,
+                    ..TableColumnSetup::new(name)
+                };
+                let descending = TableColumnFlags::PREFER_SORT_DESCENDING;
+                let Some(_table) = ui.begin_table_header_with_flags(
+                    "##stats",
+                    [
+                        column("Source", TableColumnFlags::WIDTH_STRETCH),
+                        column("Strings/s", descending | TableColumnFlags::DEFAULT_SORT),
+                        column("Bytes/s", descending),
+                        column("Avg length", descending),
+                        column("Share", descending),
+                        column("", TableColumnFlags::NO_SORT),
+                    ],
+                    TableFlags::SORTABLE
+                        | TableFlags::ROW_BG
+                        | TableFlags::BORDERS
+                        | TableFlags::SCROLL_Y,
+                ) else {
+                    return;
+                };
+                if let Some(specs) = ui.table_sort_specs_mut() {
+                    specs.conditional_sort(|specs| {
+                        if let Some(spec) = specs.iter().next() {
+                            self.stats.sort = (
+                                spec.column_idx(),
+                                spec.sort_direction() == Some(TableSortDirection::Descending),
+                            );
+                        }
+                    });
+                }
+                // The numbers change every frame, so the rows are sorted every frame too
+                let (sort_column, sort_descending) = self.stats.sort;
+                let key = |row: &Row| match sort_column {
+                    2 => row.bytes,
+                    3 => row.bytes / row.strings,
+                    _ => row.strings,
+                };
+                rows.sort_by(|a, b| {
+                    let ordering = match sort_column {
+                        0 => a.name.cmp(&b.name),
+                        _ => key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal),
+                    };
+                    if sort_descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                });
+
+                for (index, row) in rows.iter().enumerate() {
+                    ui.table_next_column();
+                    ui.text(&row.name);
+                    ui.table_next_column();
+                    ui.text(format!("{:.1}", row.strings / window));
+                    ui.table_next_column();
+                    ui.text(format!("{:.0}", row.bytes / window));
+                    ui.table_next_column();
+                    ui.text(format!("{:.1}", row.bytes / row.strings));
+                    ui.table_next_column();
+                    ui.text(format!("{:.1}%", row.strings / total * 100.0));
+                    ui.table_next_column();
+                    if let Some(range) = row.range {
+                        let _id = ui.push_id_usize(index);
+                        if ui.small_button("Exclude") {
+                            excluded = Some((range, row.name.clone()));
+                        }
+                    }
+                }
+            });
+        self.stats_open = open;
+        if !open {
+            set_capture_callers(self.needs_callers());
+        }
+
+        if let Some((range, name)) = excluded {
+            let exclude_callers = &mut self.config.filter.exclude_callers;
+            if !exclude_callers.contains(&range) {
+                exclude_callers.push(range);
+            }
+            // The shared config is what a frame syncs to, without this the exclusion would be
+            // gone from the config again on the next one
+            if let Ok(mut shared) = self.shared_config.write() {
+                shared.filter.exclude_callers = exclude_callers.clone();
+            }
+            set_filter(&self.current_config().filter);
+            tracing::info!(
+                "Excluded text from {} ({:#x}..={:#x})",
+                name,
+                range[0],
+                range[1]
+            );
+        }
+    }
+}
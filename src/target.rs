@@ -0,0 +1,123 @@
+use std::{fmt, sync::OnceLock, time::Duration};
+
+use eldenring_util::program::Program;
+use hudhook::windows::{Win32::System::LibraryLoader::GetModuleHandleW, core::HSTRING};
+use pelite::pe64::{Pe, PeObject, PeView};
+
+use crate::config::TargetSettings;
+
+/// How often the target module is looked for while it isn't loaded yet.
+const MODULE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The module the hooks were resolved against, set once before they're installed.
+static TARGET: OnceLock<Program<'static>> = OnceLock::new();
+
+/// Why the target module isn't hooked.
+#[derive(Debug)]
+pub enum TargetError {
+    Timestamp {
+        module: String,
+        expected: u32,
+        found: u32,
+    },
+    ImageSize {
+        module: String,
+        expected: u32,
+        found: u32,
+    },
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetError::Timestamp {
+                module,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{module} has the timestamp {found:#x} instead of {expected:#x}"
+            ),
+            TargetError::ImageSize {
+                module,
+                expected,
+                found,
+            } => write!(f, "{module} is {found:#x} bytes instead of {expected:#x}"),
+        }
+    }
+}
+
+/// The module the game's code is in, the main executable until [`select_target`] picked one.
+pub fn target_program() -> Program<'static> {
+    TARGET.get().copied().unwrap_or_else(Program::current)
+}
+
+/// Whether the module the config names is loaded, always `true` for the main executable.
+pub fn is_target_loaded(settings: &TargetSettings) -> bool {
+    settings
+        .module
+        .as_deref()
+        .is_none_or(|name| find_module(name).is_some())
+}
+
+/// Picks the module the hooks go into, waiting for it to be loaded if the config names one,
+/// and checks it's the build the config expects.
+pub fn select_target(settings: &TargetSettings) -> Result<Program<'static>, TargetError> {
+    let (program, module) = match settings.module.as_deref() {
+        Some(name) => (wait_for_module(name), name.to_string()),
+        None => (Program::current(), "the main executable".to_string()),
+    };
+
+    let timestamp = program.file_header().TimeDateStamp;
+    let image_size = program.optional_header().SizeOfImage;
+    tracing::info!(
+        "Hooking {} at {:#x}, timestamp {:#x}, image size {:#x}",
+        module,
+        program.image().as_ptr() as usize,
+        timestamp,
+        image_size
+    );
+    if let Some(expected) = settings.timestamp
+        && expected != timestamp
+    {
+        return Err(TargetError::Timestamp {
+            module,
+            expected,
+            found: timestamp,
+        });
+    }
+    if let Some(expected) = settings.image_size
+        && expected != image_size
+    {
+        return Err(TargetError::ImageSize {
+            module,
+            expected,
+            found: image_size,
+        });
+    }
+
+    TARGET.set(program).ok();
+    Ok(program)
+}
+
+fn find_module(name: &str) -> Option<Program<'static>> {
+    let module = unsafe { GetModuleHandleW(&HSTRING::from(name)) }.ok()?;
+    Some(Program::Mapping(unsafe {
+        PeView::module(module.0 as *const u8)
+    }))
+}
+
+/// Polls for `name` until it's loaded, it can be loaded well after the DLL when a wrapper
+/// starts the game.
+fn wait_for_module(name: &str) -> Program<'static> {
+    if let Some(program) = find_module(name) {
+        return program;
+    }
+    tracing::info!("Waiting for {} to be loaded", name);
+    loop {
+        std::thread::sleep(MODULE_POLL_INTERVAL);
+        if let Some(program) = find_module(name) {
+            return program;
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/// Splits captured text into the lines it's drawn as.
+///
+/// Lines end at `\n` or `\r\n`, tabs become `tab_width` spaces and any other control character
+/// is dropped, imgui would draw those as missing glyphs.
+pub fn normalize_lines(text: &str, tab_width: usize) -> Vec<String> {
+    text.split('\n')
+        .map(|line| {
+            let mut normalized = String::with_capacity(line.len());
+            for c in line.chars() {
+                if c == '\t' {
+                    normalized.extend(std::iter::repeat_n(' ', tab_width));
+                } else if !c.is_control() {
+                    normalized.push(c);
+                }
+            }
+            normalized
+        })
+        .collect()
+}
+
+/// Identity of a text at a position, stable across runs and Rust versions.
+///
+/// Hashes the length and bytes of the text with 64-bit FNV-1a together with the exact bits of
+/// the position, so text at positions a fraction of a pixel apart gets different IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextId(u64);
+
+impl TextId {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    pub fn new(text: &str, position: [f32; 2]) -> Self {
+        // -0.0 and 0.0 are the same position
+        let position = position.map(|coordinate| (coordinate + 0.0).to_bits());
+        // The length keeps where the text ends from depending on the bytes of the position
+        let bytes = (text.len() as u64)
+            .to_le_bytes()
+            .into_iter()
+            .chain(text.bytes())
+            .chain(position[0].to_le_bytes())
+            .chain(position[1].to_le_bytes());
+        let hash = bytes.fold(Self::FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(Self::FNV_PRIME)
+        });
+        Self(hash)
+    }
+
+    /// Identity of the text a command draws at `x`, `y` in the game's coordinates, rounded to
+    /// whole pixels so jitter doesn't make it different text.
+    pub fn of_command(text: &str, x: f32, y: f32) -> Self {
+        Self::new(text, [x.round(), y.round()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_ids_ignore_jitter() {
+        assert_eq!(
+            TextId::of_command("HP 100", 10.2, 4.8),
+            TextId::of_command("HP 100", 9.9, 5.1)
+        );
+        assert_ne!(
+            TextId::of_command("HP 100", 10.2, 5.0),
+            TextId::of_command("HP 100", 10.7, 5.0)
+        );
+    }
+
+    #[test]
+    fn ids_depend_on_the_exact_position() {
+        assert_ne!(
+            TextId::new("HP 100", [10.2, 5.0]),
+            TextId::new("HP 100", [10.7, 5.0])
+        );
+        assert_ne!(
+            TextId::new("HP 100", [10.0, 5.0]),
+            TextId::new("HP 100", [5.0, 10.0])
+        );
+        assert_eq!(
+            TextId::new("HP 100", [10.2, 5.0]),
+            TextId::new("HP 100", [10.2, 5.0])
+        );
+    }
+
+    #[test]
+    fn negative_zero_is_zero() {
+        assert_eq!(
+            TextId::new("origin", [-0.0, 0.0]),
+            TextId::new("origin", [0.0, -0.0])
+        );
+    }
+
+    #[test]
+    fn ids_depend_on_the_text() {
+        assert_ne!(
+            TextId::new("HP 100", [10.0, 5.0]),
+            TextId::new("HP 101", [10.0, 5.0])
+        );
+        assert_ne!(TextId::new("", [0.0, 0.0]), TextId::new("\0", [0.0, 0.0]));
+    }
+
+    #[test]
+    fn ids_are_stable() {
+        // FNV-1a of the length, the text and the position bits, worked out independently
+        assert_eq!(
+            TextId::new("HP 100", [10.0, 5.0]),
+            TextId(0x42eb488918a1a1ab)
+        );
+    }
+
+    #[test]
+    fn crlf_ends_a_line() {
+        assert_eq!(
+            normalize_lines("first\r\nsecond\nthird", 4),
+            ["first", "second", "third"]
+        );
+    }
+
+    #[test]
+    fn tabs_become_spaces() {
+        assert_eq!(normalize_lines("a\tb", 4), ["a    b"]);
+        assert_eq!(normalize_lines("a\tb", 0), ["ab"]);
+    }
+
+    #[test]
+    fn control_characters_are_dropped() {
+        assert_eq!(normalize_lines("a\u{7}b\u{1b}[0m\rc", 4), ["ab[0mc"]);
+        // Not a control character, imgui draws it if the font has it
+        assert_eq!(normalize_lines("a\u{a0}b", 4), ["a\u{a0}b"]);
+    }
+
+    #[test]
+    fn trailing_newline_leaves_an_empty_line() {
+        assert_eq!(normalize_lines("line\n", 4), ["line", ""]);
+        assert_eq!(normalize_lines("", 4), [""]);
+    }
+}
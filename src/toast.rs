@@ -0,0 +1,118 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use hudhook::imgui::Ui;
+
+use crate::DebugTextRender;
+use crate::logging::show_error_message_box;
+
+/// How long a toast stays on screen, the last second of it fading out.
+const TOAST_DURATION: Duration = Duration::from_secs(6);
+/// Toasts on screen at once, older ones make room for new ones.
+const MAX_TOASTS: usize = 5;
+/// Toasts waiting for the overlay to draw them, more are dropped while it isn't up.
+const MAX_PENDING: usize = 32;
+/// Space between the toasts and from the screen edge.
+const MARGIN: f32 = 10.0;
+const PADDING: f32 = 6.0;
+
+/// Messages raised from any thread, until the next frame takes them.
+static PENDING: Mutex<VecDeque<(Severity, String)>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+    /// The overlay can't work at all, or won't be around to show a toast.
+    Fatal,
+}
+
+impl Severity {
+    /// sRGB color of the toast's border.
+    fn color(self) -> [f32; 3] {
+        match self {
+            Severity::Info => [0.47, 0.71, 1.0],
+            Severity::Warning => [1.0, 0.78, 0.31],
+            Severity::Error | Severity::Fatal => [1.0, 0.35, 0.35],
+        }
+    }
+}
+
+/// Tells the user about something without stopping the game.
+///
+/// Non-fatal messages become a toast in the bottom right corner the next time the overlay draws.
+/// Fatal ones get a message box, opened on a thread of its own so neither the render thread nor
+/// the loader lock waits on it.
+pub fn notify(severity: Severity, message: impl Into<String>) {
+    let message = message.into();
+    if severity == Severity::Fatal {
+        std::thread::spawn(move || show_error_message_box(message, "Debug Text View Error"));
+        return;
+    }
+    let mut pending = PENDING.lock().unwrap();
+    if pending.len() < MAX_PENDING {
+        pending.push_back((severity, message));
+    }
+}
+
+/// The toasts on screen, oldest first.
+#[derive(Default)]
+pub struct Toasts {
+    shown: VecDeque<(Severity, String, Instant)>,
+}
+
+impl DebugTextRender {
+    /// Draws the toasts stacked up from the bottom right corner, newest at the bottom.
+    pub(crate) fn draw_toasts(&mut self, ui: &Ui) {
+        // Shown from the first frame they're drawn in, however long they waited for it
+        if let Ok(mut pending) = PENDING.try_lock() {
+            let now = Instant::now();
+            for (severity, message) in pending.drain(..) {
+                self.toasts.shown.push_back((severity, message, now));
+            }
+        }
+        let color_space = self.color_space();
+        let toasts = &mut self.toasts.shown;
+        toasts.retain(|(_, _, shown_at)| shown_at.elapsed() < TOAST_DURATION);
+        let excess = toasts.len().saturating_sub(MAX_TOASTS);
+        toasts.drain(..excess);
+
+        let draw_list = ui.get_foreground_draw_list();
+        let display_size = ui.io().display_size;
+        let mut bottom = display_size[1] - MARGIN;
+        for (severity, message, shown_at) in toasts.iter().rev() {
+            let remaining = TOAST_DURATION.saturating_sub(shown_at.elapsed());
+            let alpha = remaining.as_secs_f32().min(1.0);
+            let size = ui.calc_text_size(message);
+            let max = [display_size[0] - MARGIN, bottom];
+            let min = [
+                max[0] - size[0] - 2.0 * PADDING,
+                max[1] - size[1] - 2.0 * PADDING,
+            ];
+            let [r, g, b] = severity.color();
+            draw_list
+                .add_rect(
+                    min,
+                    max,
+                    color_space.color32([0.05, 0.05, 0.05, 0.85 * alpha]),
+                )
+                .filled(true)
+                .rounding(4.0)
+                .build();
+            draw_list
+                .add_rect(min, max, color_space.color32([r, g, b, alpha]))
+                .rounding(4.0)
+                .build();
+            draw_list.add_text(
+                [min[0] + PADDING, min[1] + PADDING],
+                color_space.color32([1.0, 1.0, 1.0, alpha]),
+                message,
+            );
+            bottom = min[1] - MARGIN;
+        }
+    }
+}
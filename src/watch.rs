@@ -0,0 +1,196 @@
+use std::time::Instant;
+
+use hudhook::imgui::{Condition, StyleColor, Ui};
+use regex::Regex;
+
+use crate::DebugTextRender;
+use crate::layer::Layer;
+
+/// Size of the plot of a watch's values.
+const PLOT_SIZE: [f32; 2] = [240.0, 40.0];
+/// Name of the capture group a watch takes its value from.
+const VALUE_GROUP: &str = "value";
+
+/// A pattern captured text is matched against, with the last text that matched it.
+struct Watch {
+    pattern: String,
+    /// `None` if the pattern doesn't compile, it's kept so it can be fixed or removed.
+    regex: Option<Regex>,
+    latest: Option<(String, Instant)>,
+    /// Values parsed from the `value` group of the latest matches, oldest first.
+    values: Vec<f32>,
+    /// Matches whose `value` group wasn't a number.
+    parse_failures: u64,
+}
+
+impl Watch {
+    fn new(pattern: String) -> Self {
+        let regex = match Regex::new(&pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                tracing::error!("Invalid watch pattern {:?}: {}", pattern, e);
+                None
+            }
+        };
+        Self {
+            pattern,
+            regex,
+            latest: None,
+            values: Vec::new(),
+            parse_failures: 0,
+        }
+    }
+}
+
+/// Watched patterns, each showing the latest captured text that matched it.
+///
+/// A pattern with a `value` group, e.g. `fps: (?P<value>[0-9.]+)`, also keeps the last values
+/// it captured.
+pub struct WatchList {
+    watches: Vec<Watch>,
+    /// Values kept per watch.
+    history: usize,
+}
+
+impl WatchList {
+    pub fn new(patterns: &[String], history: usize) -> Self {
+        Self {
+            watches: patterns.iter().cloned().map(Watch::new).collect(),
+            history,
+        }
+    }
+
+    pub fn set_history(&mut self, history: usize) {
+        self.history = history;
+        for watch in &mut self.watches {
+            trim_front(&mut watch.values, history);
+        }
+    }
+
+    /// Replaces the watched patterns, the ones that stay keep their latest match.
+    pub fn set_patterns(&mut self, patterns: &[String]) {
+        let mut old = std::mem::take(&mut self.watches);
+        self.watches = patterns
+            .iter()
+            .map(
+                |pattern| match old.iter().position(|watch| watch.pattern == *pattern) {
+                    Some(index) => old.swap_remove(index),
+                    None => Watch::new(pattern.clone()),
+                },
+            )
+            .collect();
+    }
+
+    pub fn patterns(&self) -> Vec<String> {
+        self.watches
+            .iter()
+            .map(|watch| watch.pattern.clone())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+
+    pub fn add(&mut self, pattern: String) {
+        if !self.watches.iter().any(|watch| watch.pattern == pattern) {
+            self.watches.push(Watch::new(pattern));
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.watches.remove(index);
+    }
+
+    /// Records `text` as the latest match of every watch it matches, and the value it captured.
+    ///
+    /// A value that isn't a number is only counted, it would be logged for every frame.
+    pub fn observe(&mut self, text: &str) {
+        for watch in &mut self.watches {
+            let Some(captures) = watch.regex.as_ref().and_then(|regex| regex.captures(text)) else {
+                continue;
+            };
+            if let Some(value) = captures.name(VALUE_GROUP) {
+                match value.as_str().trim().parse::<f32>() {
+                    Ok(value) => {
+                        watch.values.push(value);
+                        trim_front(&mut watch.values, self.history);
+                    }
+                    Err(_) => watch.parse_failures += 1,
+                }
+            }
+            watch.latest = Some((text.to_string(), Instant::now()));
+        }
+    }
+}
+
+/// Drops the oldest values until at most `len` are left.
+fn trim_front(values: &mut Vec<f32>, len: usize) {
+    if values.len() > len {
+        values.drain(..values.len() - len);
+    }
+}
+
+impl DebugTextRender {
+    /// Draws the latest match of every watch in the top right corner, with a plot of the values
+    /// of watches that capture one.
+    ///
+    /// Watches without a match for `watch_stale_after` are dimmed.
+    pub(crate) fn draw_watches(&self, ui: &Ui) {
+        let display_width = ui.io().display_size[0];
+        let foreground = self.layers.panels == Layer::Foreground;
+        ui.window("Debug Text Watches")
+            .position([display_width - 10.0, 10.0], Condition::Always)
+            .bring_to_front_on_focus(foreground)
+            .focus_on_appearing(foreground)
+            .position_pivot([1.0, 0.0])
+            .always_auto_resize(true)
+            .no_decoration()
+            .no_inputs()
+            .bg_alpha(0.6)
+            .build(|| {
+                for (index, watch) in self.watches.watches.iter().enumerate() {
+                    let stale = watch
+                        .latest
+                        .as_ref()
+                        .is_none_or(|(_, seen)| seen.elapsed() >= self.watch_stale_after);
+                    let _color = ui.push_style_color(
+                        StyleColor::Text,
+                        [1.0, 1.0, 1.0, if stale { 0.4 } else { 1.0 }],
+                    );
+                    match (&watch.regex, &watch.latest) {
+                        (None, _) => ui.text(format!("{}: invalid pattern", watch.pattern)),
+                        (Some(_), None) => ui.text(format!("{}: no match yet", watch.pattern)),
+                        (Some(_), Some((text, seen))) => ui.text(format!(
+                            "{}  ({:.1}s ago)",
+                            text,
+                            seen.elapsed().as_secs_f32()
+                        )),
+                    }
+
+                    let Some(&current) = watch.values.last() else {
+                        continue;
+                    };
+                    let min = watch.values.iter().copied().fold(f32::INFINITY, f32::min);
+                    let max = watch
+                        .values
+                        .iter()
+                        .copied()
+                        .fold(f32::NEG_INFINITY, f32::max);
+                    let mut overlay = format!("{current} (min {min}, max {max})");
+                    if watch.parse_failures > 0 {
+                        overlay.push_str(&format!(", {} unparsed", watch.parse_failures));
+                    }
+                    ui.plot_lines(format!("##plot{index}"), &watch.values)
+                        .overlay_text(overlay)
+                        .graph_size([PLOT_SIZE[0], PLOT_SIZE[1]])
+                        .build();
+                }
+            });
+    }
+}
+
+/// Pattern matching `text` exactly, for watches added from the settings window.
+pub fn exact_pattern(text: &str) -> String {
+    format!("^{}$", regex::escape(text))
+}
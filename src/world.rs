@@ -0,0 +1,307 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use eldenring::cs::CSCamera;
+use eldenring_util::singleton::get_instance;
+
+use crate::config::WorldTextSettings;
+use crate::processor::ShapeKind;
+use crate::text::TextId;
+
+/// Labels whose position is remembered before the map starts over.
+const MAX_TRACKED: usize = 4096;
+/// Labels that weren't drawn for this long are forgotten, and appear at their new position.
+const LABEL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A point in the world as the camera sees it.
+pub struct Projection {
+    /// Position on the screen.
+    pub position: [f32; 2],
+    /// Distance in front of the camera along its view direction.
+    pub depth: f32,
+    /// Distance from the camera.
+    pub distance: f32,
+}
+
+/// The game's camera, read once per frame to project world text to the screen.
+pub struct Camera {
+    position: [f32; 3],
+    right: [f32; 3],
+    up: [f32; 3],
+    forward: [f32; 3],
+    /// Tangent of half the vertical field of view.
+    tan_half_fov: f32,
+    aspect_ratio: f32,
+    near_plane: f32,
+}
+
+impl Camera {
+    /// The camera the game renders with, `None` before the game set it up.
+    pub fn current() -> Option<Self> {
+        let camera = unsafe { get_instance::<CSCamera>() }.ok()??;
+        let cam = &*camera.pers_cam_1;
+        // Rows of the world transform: the right, up and forward axes, then the position
+        let matrix = &cam.matrix;
+        Some(Self {
+            right: [matrix.0.0, matrix.0.1, matrix.0.2],
+            up: [matrix.1.0, matrix.1.1, matrix.1.2],
+            forward: [matrix.2.0, matrix.2.1, matrix.2.2],
+            position: [matrix.3.0, matrix.3.1, matrix.3.2],
+            tan_half_fov: (cam.fov / 2.0).tan(),
+            aspect_ratio: cam.aspect_ratio,
+            near_plane: cam.near_plane,
+        })
+    }
+
+    /// Where `point` in the world ends up on a screen of `screen_size`, `None` behind the camera.
+    pub fn project(&self, point: [f32; 3], screen_size: [f32; 2]) -> Option<Projection> {
+        let view = self.to_view(point);
+        if view[2] <= self.near_plane {
+            return None;
+        }
+        Some(Projection {
+            position: self.view_to_screen(view, screen_size),
+            depth: view[2],
+            distance: dot(view, view).sqrt(),
+        })
+    }
+
+    /// Both ends of a line on the screen, cut off where it goes behind the camera. `None` if
+    /// it's behind the camera entirely.
+    pub fn project_line(
+        &self,
+        from: [f32; 3],
+        to: [f32; 3],
+        screen_size: [f32; 2],
+    ) -> Option<([f32; 2], [f32; 2])> {
+        let (mut from, mut to) = (self.to_view(from), self.to_view(to));
+        if from[2] <= self.near_plane && to[2] <= self.near_plane {
+            return None;
+        }
+        // Moves the end behind the camera to where the line crosses the near plane
+        let clip = |behind: [f32; 3], front: [f32; 3]| {
+            let t = (self.near_plane - behind[2]) / (front[2] - behind[2]);
+            [0, 1, 2].map(|axis| behind[axis] + (front[axis] - behind[axis]) * t)
+        };
+        if from[2] <= self.near_plane {
+            from = clip(from, to);
+        } else if to[2] <= self.near_plane {
+            to = clip(to, from);
+        }
+        Some((
+            self.view_to_screen(from, screen_size),
+            self.view_to_screen(to, screen_size),
+        ))
+    }
+
+    /// Center and radius in pixels of the circle a sphere covers on the screen, `None` if its
+    /// center is behind the camera.
+    pub fn project_sphere(
+        &self,
+        center: [f32; 3],
+        radius: f32,
+        screen_size: [f32; 2],
+    ) -> Option<([f32; 2], f32)> {
+        let projection = self.project(center, screen_size)?;
+        let pixels = radius / (projection.depth * self.tan_half_fov) * screen_size[1] / 2.0;
+        Some((projection.position, pixels))
+    }
+
+    /// `point` relative to the camera along its right, up and forward axes.
+    fn to_view(&self, point: [f32; 3]) -> [f32; 3] {
+        let offset = [0, 1, 2].map(|axis| point[axis] - self.position[axis]);
+        [
+            dot(offset, self.right),
+            dot(offset, self.up),
+            dot(offset, self.forward),
+        ]
+    }
+
+    fn view_to_screen(&self, view: [f32; 3], screen_size: [f32; 2]) -> [f32; 2] {
+        let x = view[0] / (view[2] * self.tan_half_fov * self.aspect_ratio);
+        let y = view[1] / (view[2] * self.tan_half_fov);
+        [
+            (x + 1.0) / 2.0 * screen_size[0],
+            (1.0 - y) / 2.0 * screen_size[1],
+        ]
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// A 3D primitive the game draws in the world.
+#[derive(Debug, Clone, Copy)]
+pub enum WorldShape {
+    Line {
+        from: [f32; 3],
+        to: [f32; 3],
+    },
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+    },
+    /// Two hemispheres of `radius` around `from` and `to`, joined by a cylinder.
+    Capsule {
+        from: [f32; 3],
+        to: [f32; 3],
+        radius: f32,
+    },
+}
+
+/// The 2D shapes a world shape is drawn with, as a kind and two points like `ShapeEntry`.
+///
+/// Spheres are circles facing the camera. Capsules are circles at both ends, joined by lines
+/// along their sides.
+pub fn project_shape(
+    camera: &Camera,
+    shape: WorldShape,
+    screen_size: [f32; 2],
+) -> Vec<(ShapeKind, [f32; 2], [f32; 2])> {
+    // A point on the circle, which is what the circle's radius is taken from
+    let circle = |center: [f32; 2], radius: f32| {
+        (ShapeKind::Circle, center, [center[0] + radius, center[1]])
+    };
+    match shape {
+        WorldShape::Line { from, to } => camera
+            .project_line(from, to, screen_size)
+            .map(|(from, to)| (ShapeKind::Line, from, to))
+            .into_iter()
+            .collect(),
+        WorldShape::Sphere { center, radius } => camera
+            .project_sphere(center, radius, screen_size)
+            .map(|(center, radius)| circle(center, radius))
+            .into_iter()
+            .collect(),
+        WorldShape::Capsule { from, to, radius } => {
+            let ends = (
+                camera.project_sphere(from, radius, screen_size),
+                camera.project_sphere(to, radius, screen_size),
+            );
+            let (Some((a, radius_a)), Some((b, radius_b))) = ends else {
+                // Half behind the camera, the ends and sides can't be placed
+                return ends
+                    .0
+                    .or(ends.1)
+                    .map(|(center, radius)| circle(center, radius))
+                    .into_iter()
+                    .collect();
+            };
+            let mut shapes = vec![circle(a, radius_a), circle(b, radius_b)];
+            let direction = [b[0] - a[0], b[1] - a[1]];
+            let length = direction[0].hypot(direction[1]);
+            if length > f32::EPSILON {
+                let normal = [-direction[1] / length, direction[0] / length];
+                for side in [1.0, -1.0] {
+                    shapes.push((
+                        ShapeKind::Line,
+                        [
+                            a[0] + normal[0] * radius_a * side,
+                            a[1] + normal[1] * radius_a * side,
+                        ],
+                        [
+                            b[0] + normal[0] * radius_b * side,
+                            b[1] + normal[1] * radius_b * side,
+                        ],
+                    ));
+                }
+            }
+            shapes
+        }
+    }
+}
+
+/// Multiplier for the size of text `distance` away from the camera, `None` if it's too far away
+/// to be drawn.
+pub fn distance_scale(distance: f32, settings: &WorldTextSettings) -> Option<f32> {
+    if settings.max_distance > 0.0 && distance > settings.max_distance {
+        return None;
+    }
+    let scale = (settings.reference_distance / distance.max(f32::EPSILON)).powf(settings.falloff);
+    Some(scale.clamp(settings.min_scale, settings.max_scale))
+}
+
+/// Opacity of text `distance` away from the camera, fading out from `fade_distance` to
+/// `max_distance`.
+pub fn distance_alpha(distance: f32, settings: &WorldTextSettings) -> f32 {
+    if settings.max_distance <= 0.0 || settings.fade_distance >= settings.max_distance {
+        return 1.0;
+    }
+    let faded =
+        (distance - settings.fade_distance) / (settings.max_distance - settings.fade_distance);
+    1.0 - faded.clamp(0.0, 1.0)
+}
+
+/// Identity of a world label across frames: the code that drew it, the text up to its first
+/// digit so a changing number doesn't make it a new label, and how many labels with the same
+/// two were drawn before it in the frame.
+type LabelKey = (Option<u32>, TextId, usize);
+
+/// Where each world label was drawn, so its position can ease towards the projected one
+/// instead of jittering with the game's tick.
+pub struct WorldSmoothing {
+    positions: HashMap<LabelKey, ([f32; 2], Instant)>,
+    /// Labels seen this frame per caller and prefix, to tell apart labels that share them.
+    seen: HashMap<(Option<u32>, TextId), usize>,
+}
+
+impl WorldSmoothing {
+    pub fn new() -> Self {
+        Self {
+            positions: HashMap::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Moves the label `smoothing` of the way from where it was drawn last towards `target`,
+    /// or right to it when it's the first time it's drawn or it moved more than
+    /// `snap_distance` pixels.
+    pub fn smooth(
+        &mut self,
+        caller: Option<u32>,
+        text: &str,
+        target: [f32; 2],
+        settings: &WorldTextSettings,
+    ) -> [f32; 2] {
+        let prefix = &text[..text
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(text.len())];
+        let label = (caller, TextId::new(prefix, [0.0, 0.0]));
+        let index = self.seen.entry(label).or_default();
+        let key = (label.0, label.1, *index);
+        *index += 1;
+
+        if self.positions.len() >= MAX_TRACKED {
+            self.positions.clear();
+        }
+        let now = Instant::now();
+        let position = match self.positions.get(&key) {
+            Some(&(last, _))
+                if (target[0] - last[0]).hypot(target[1] - last[1]) <= settings.snap_distance =>
+            {
+                let keep = settings.smoothing.clamp(0.0, 1.0);
+                [
+                    target[0] + (last[0] - target[0]) * keep,
+                    target[1] + (last[1] - target[1]) * keep,
+                ]
+            }
+            _ => target,
+        };
+        self.positions.insert(key, (position, now));
+        position
+    }
+
+    /// Starts counting labels for a new frame.
+    pub fn finish_frame(&mut self) {
+        self.seen.clear();
+    }
+
+    /// Forgets labels that weren't drawn for `LABEL_TIMEOUT`.
+    pub fn prune(&mut self) {
+        self.positions
+            .retain(|_, (_, drawn)| drawn.elapsed() < LABEL_TIMEOUT);
+    }
+}